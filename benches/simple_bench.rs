@@ -1,10 +1,13 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use evalexpr::{eval, eval_empty_with_context_mut, Context, HashMapContext};
+use evaluatorrs::formulas::math::Sin;
 use evaluatorrs::formulas::Evaluate;
 use evaluatorrs::formulas::RootFormula;
-use evaluatorrs::formulas::Sin;
+use evaluatorrs::formulas::{ClosureFormula, CompiledFormula};
 use evaluatorrs::function_stores::{EmptyFunctionStore, HashMapFunctionStore, RegisterParser};
-use evaluatorrs::variable_stores::{EmptyVariableStore, HashMapVariableStore, SetVariable};
+use evaluatorrs::variable_stores::{
+    EmptyVariableStore, HashMapVariableStore, SetVariable, Variable, VectorVariableStore,
+};
 use std::hint::black_box;
 use std::time::Duration;
 
@@ -18,7 +21,7 @@ fn singl_token_bench(c: &mut Criterion) {
             black_box({
                 let store = EmptyVariableStore;
                 let a = RootFormula::parse("1", &EmptyFunctionStore).unwrap();
-                a.eval(&store);
+                a.eval(&store)
             })
         })
     });
@@ -34,7 +37,7 @@ fn singl_eval_bench(c: &mut Criterion) {
             black_box({
                 let mut context = HashMapContext::new();
                 eval_empty_with_context_mut("a = 5", &mut context).unwrap();
-                context.get_value("a");
+                context.get_value("a").cloned()
             })
         })
     });
@@ -44,7 +47,7 @@ fn singl_eval_bench(c: &mut Criterion) {
                 let mut store = HashMapVariableStore::new();
                 store.set("a", RootFormula::parse("5", &EmptyFunctionStore).unwrap());
                 let a = RootFormula::parse("a", &EmptyFunctionStore).unwrap();
-                a.eval(&store);
+                a.eval(&store)
             })
         })
     });
@@ -56,11 +59,7 @@ fn big_expression(c: &mut Criterion) {
     group.warm_up_time(Duration::from_secs(15));
     group.sample_size(10000);
     group.bench_function("evalexpr", |b| {
-        b.iter(|| {
-            black_box({
-                eval("5*(45+6/math::sin(1))").unwrap();
-            })
-        })
+        b.iter(|| black_box(eval("5*(45+6/math::sin(1))").unwrap()))
     });
     group.bench_function("evaluatorrs", |b| {
         b.iter(|| {
@@ -68,12 +67,49 @@ fn big_expression(c: &mut Criterion) {
                 let mut function_store = HashMapFunctionStore::new();
                 function_store.register::<Sin>();
                 let a = RootFormula::parse("5*(45+6/sin(1))", &function_store).unwrap();
-                a.eval(&EmptyVariableStore);
+                a.eval(&EmptyVariableStore)
             })
         })
     });
     group.finish();
 }
 
-criterion_group!(benches, big_expression, singl_token_bench, singl_eval_bench);
+fn compiled_eval_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compiled_eval");
+    group.warm_up_time(Duration::from_secs(15));
+    group.sample_size(10000);
+    let mut function_store = HashMapFunctionStore::new();
+    function_store.register::<Sin>();
+    let formula = RootFormula::parse(
+        "x * (y + 2) - sin(x) / (y * y + 1) + x * x * 0.5 - (z - x) * (z + y) / 3",
+        &function_store,
+    )
+    .unwrap();
+    let variables = [Variable::new("x"), Variable::new("y"), Variable::new("z")];
+    let values = [1.5, -0.5, 2.0];
+    let mut store = VectorVariableStore::new();
+    for (variable, value) in variables.iter().zip(values) {
+        store.set(variable.clone(), value);
+    }
+    group.bench_function("tree", |b| {
+        b.iter(|| black_box(formula.eval(black_box(&store)).unwrap()))
+    });
+    let compiled = CompiledFormula::new(&formula, &variables).unwrap();
+    group.bench_function("bytecode", |b| {
+        b.iter(|| black_box(compiled.eval(black_box(&values)).unwrap()))
+    });
+    let closure = ClosureFormula::new(&formula, &variables).unwrap();
+    group.bench_function("closure", |b| {
+        b.iter(|| black_box(closure.eval(black_box(&values)).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    big_expression,
+    singl_token_bench,
+    singl_eval_bench,
+    compiled_eval_bench
+);
 criterion_main!(benches);
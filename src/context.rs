@@ -1,9 +1,15 @@
 use crate::__lib::boxed::Box;
 use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
 use crate::formulas::Function;
-use crate::formulas::RootFormula;
-use crate::function_stores::{ArgumentBounds, GetFunction, Parser, RegisterParser};
-use crate::variable_stores::{GetVariable, PopVariable, SetVariable, Variable};
+use crate::formulas::{Limits, RootFormula};
+use crate::function_stores::{
+    ArgumentBounds, GetFunction, Parser, RegisterParser, SetLimits, SetSeed,
+};
+use crate::variable_stores::{
+    EvaluationBudget, GetVariable, ListVariables, PopVariable, ResolutionChain, SetVariable,
+    Variable,
+};
 
 /// Struct for interacting with variable store and function store.
 #[derive(Debug, Default, Clone)]
@@ -33,6 +39,23 @@ impl<T: GetVariable, U> GetVariable for Context<T, U> {
     fn as_dyn(&self) -> &dyn GetVariable {
         self
     }
+
+    #[inline]
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.variable_store.resolution()
+    }
+
+    #[inline]
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.variable_store.budget()
+    }
+}
+
+impl<T: ListVariables, U> ListVariables for Context<T, U> {
+    #[inline]
+    fn variables(&self) -> Vec<Variable> {
+        self.variable_store.variables()
+    }
 }
 
 impl<T: SetVariable, U> SetVariable for Context<T, U> {
@@ -61,6 +84,16 @@ where
     fn iter(&'a self) -> Self::Iter {
         self.function_store.iter()
     }
+
+    #[inline]
+    fn next_seed(&self) -> Option<u64> {
+        self.function_store.next_seed()
+    }
+
+    #[inline]
+    fn limits(&self) -> Option<&Limits> {
+        self.function_store.limits()
+    }
 }
 
 impl<T, U: RegisterParser> RegisterParser for Context<T, U> {
@@ -71,6 +104,22 @@ impl<T, U: RegisterParser> RegisterParser for Context<T, U> {
     }
 }
 
+impl<T, U: SetSeed> SetSeed for Context<T, U> {
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[inline]
+    fn set_seed(&mut self, seed: u64) {
+        self.function_store.set_seed(seed)
+    }
+}
+
+impl<T, U: SetLimits> SetLimits for Context<T, U> {
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[inline]
+    fn set_limits(&mut self, limits: Limits) {
+        self.function_store.set_limits(limits)
+    }
+}
+
 impl<T: PopVariable, U> PopVariable for Context<T, U> {
     #[inline]
     fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
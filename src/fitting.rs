@@ -0,0 +1,422 @@
+// `mul_add` is not available without std, number of data points is small enough to be converted to float.
+#![allow(clippy::suboptimal_flops, clippy::cast_precision_loss)]
+
+use crate::__lib::string::format;
+use crate::__lib::vec::{vec, Vec};
+use crate::float::{abs, sqrt};
+use crate::formulas::binding::MultiBinding;
+use crate::formulas::{EvaluationError, MathError, RootFormula};
+use crate::solvers::linear::solve_linear;
+use crate::variable_stores::{GetVariable, Variable};
+
+const INITIAL_DAMPING: f64 = 1e-3;
+const MAX_DAMPING_INCREASES: usize = 20;
+// step of central differences, approximately cube root of machine epsilon
+const JACOBIAN_STEP: f64 = 6e-6;
+
+/// Options of fitting.
+#[derive(Debug, Clone, Copy)]
+pub struct FitOptions {
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-10,
+            max_iterations: 200,
+        }
+    }
+}
+
+impl FitOptions {
+    /// Creates default options: tolerance `1e-10` and maximum 200 iterations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets tolerance, fitting stops when sum of squared residuals changes less than tolerance,
+    /// relative to its value.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets maximum number of iterations.
+    #[must_use]
+    pub const fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+}
+
+/// Fitted parameters and estimates of their uncertainty.
+#[derive(Debug, Clone)]
+pub struct Fit {
+    parameters: Vec<f64>,
+    covariance: Vec<f64>,
+    residual_sum_of_squares: f64,
+    iterations: usize,
+    converged: bool,
+}
+
+impl Fit {
+    /// Returns fitted values of parameters, in the same order as parameters.
+    pub fn parameters(&self) -> &[f64] {
+        &self.parameters
+    }
+
+    /// Returns estimated covariance of parameters with indices `row` and `column`.
+    ///
+    /// Covariance is NAN if there are not more data points than parameters or parameters are not identifiable.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if index is not less than number of parameters.
+    pub fn covariance(&self, row: usize, column: usize) -> f64 {
+        let n = self.parameters.len();
+        assert!(row < n && column < n, "index of parameter is out of range");
+        self.covariance[row * n + column]
+    }
+
+    /// Returns estimated standard error of parameter with `index`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if index is not less than number of parameters.
+    pub fn standard_error(&self, index: usize) -> f64 {
+        sqrt(self.covariance(index, index))
+    }
+
+    /// Returns sum of squared residuals at fitted parameters.
+    pub const fn residual_sum_of_squares(&self) -> f64 {
+        self.residual_sum_of_squares
+    }
+
+    /// Returns number of iterations made.
+    pub const fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns `false` if maximum number of iterations was reached before tolerance.
+    pub const fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+// Model as function of independent variable and parameters.
+struct Model<'a> {
+    formula: &'a RootFormula,
+    binding: MultiBinding<'a>,
+    data: &'a [(f64, f64)],
+    // independent variable followed by parameters
+    point: Vec<f64>,
+}
+
+impl Model<'_> {
+    fn eval(&mut self, x: f64, parameters: &[f64]) -> Result<f64, EvaluationError> {
+        self.point[0] = x;
+        self.point[1..].copy_from_slice(parameters);
+        self.binding.set(&self.point);
+        self.binding.eval(self.formula)
+    }
+
+    fn residuals(&mut self, parameters: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+        let data = self.data;
+        data.iter()
+            .map(|(x, y)| Ok(y - self.eval(*x, parameters)?))
+            .collect()
+    }
+
+    // Jacobian of model by central differences, stored by rows, one row per data point
+    fn jacobian(&mut self, parameters: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+        let n = parameters.len();
+        let data = self.data;
+        let mut jacobian = vec![0.0; data.len() * n];
+        let mut shifted = parameters.to_vec();
+        for column in 0..n {
+            let step = JACOBIAN_STEP * abs(parameters[column]).max(1.0);
+            for (row, (x, _)) in data.iter().enumerate() {
+                shifted[column] = parameters[column] + step;
+                let forward = self.eval(*x, &shifted)?;
+                shifted[column] = parameters[column] - step;
+                let backward = self.eval(*x, &shifted)?;
+                jacobian[row * n + column] = (forward - backward) / (2.0 * step);
+            }
+            shifted[column] = parameters[column];
+        }
+        Ok(jacobian)
+    }
+}
+
+fn sum_of_squares(residuals: &[f64]) -> f64 {
+    residuals.iter().map(|r| r * r).sum()
+}
+
+fn fit_error(message: &str) -> EvaluationError {
+    EvaluationError::MathError(MathError::new(format!("fitting, {message}")))
+}
+
+// returns `J^T J` and `J^T r`
+fn normal_equations(jacobian: &[f64], residuals: &[f64], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut matrix = vec![0.0; n * n];
+    let mut rhs = vec![0.0; n];
+    for (row, residual) in jacobian.chunks_exact(n).zip(residuals) {
+        for i in 0..n {
+            rhs[i] += row[i] * residual;
+            for j in 0..n {
+                matrix[i * n + j] += row[i] * row[j];
+            }
+        }
+    }
+    (matrix, rhs)
+}
+
+// `sigma^2 (J^T J)^-1`, NAN if it can not be estimated
+fn covariance(matrix: &[f64], variance: f64, n: usize) -> Vec<f64> {
+    let mut covariance = vec![f64::NAN; n * n];
+    if !variance.is_finite() {
+        return covariance;
+    }
+    for column in 0..n {
+        let mut unit = vec![0.0; n];
+        unit[column] = 1.0;
+        if !solve_linear(&mut matrix.to_vec(), &mut unit) {
+            return vec![f64::NAN; n * n];
+        }
+        for (row, value) in unit.iter().enumerate() {
+            covariance[row * n + column] = variance * value;
+        }
+    }
+    covariance
+}
+
+/// Fits `parameters` of `model` to `data` by least squares, other variables are taken from `store`.
+///
+/// Data points are pairs of values of `independent` variable and measured values of model.
+/// Parameters are pairs of variables and their initial values. Fitting is done by Levenberg-Marquardt method
+/// with numeric Jacobian. Covariance of parameters is estimated from residuals at found parameters.
+///
+/// # Errors
+///
+/// Will return Err if there are less data points than parameters, model fails to evaluate
+/// or model is not defined at initial parameters.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::fitting::{fit, FitOptions};
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{EmptyVariableStore, Variable};
+///
+/// let model = RootFormula::parse("a * t + b", &EmptyFunctionStore).unwrap();
+/// let data = [(0.0, 1.1), (1.0, 2.9), (2.0, 5.1), (3.0, 6.9)];
+/// let parameters = [(Variable::new("a"), 1.0), (Variable::new("b"), 0.0)];
+/// let fit = fit(&model, &Variable::new("t"), &data, &parameters, &EmptyVariableStore, &FitOptions::new()).unwrap();
+/// assert!((fit.parameters()[0] - 1.96).abs() < 1e-6);
+/// assert!((fit.parameters()[1] - 1.06).abs() < 1e-6);
+/// ```
+pub fn fit(
+    model: &RootFormula,
+    independent: &Variable,
+    data: &[(f64, f64)],
+    parameters: &[(Variable, f64)],
+    store: &dyn GetVariable,
+    options: &FitOptions,
+) -> Result<Fit, EvaluationError> {
+    let n = parameters.len();
+    if data.len() < n {
+        return Err(fit_error(&format!(
+            "number of data points {} is less than number of parameters {n}",
+            data.len()
+        )));
+    }
+    let variables = core::iter::once(independent.clone())
+        .chain(parameters.iter().map(|(variable, _)| variable.clone()))
+        .collect::<Vec<_>>();
+    let mut model = Model {
+        formula: model,
+        binding: MultiBinding::new(&variables, store),
+        data,
+        point: vec![0.0; n + 1],
+    };
+    let mut current = parameters
+        .iter()
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    let mut residuals = model.residuals(&current)?;
+    let mut sum = sum_of_squares(&residuals);
+    if !sum.is_finite() {
+        return Err(fit_error("model is not defined at initial parameters"));
+    }
+    let mut jacobian = model.jacobian(&current)?;
+    let mut damping = INITIAL_DAMPING;
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < options.max_iterations {
+        iterations += 1;
+        let (matrix, rhs) = normal_equations(&jacobian, &residuals, n);
+        let mut improved = None;
+        for _ in 0..MAX_DAMPING_INCREASES {
+            let mut damped = matrix.clone();
+            for i in 0..n {
+                damped[i * n + i] += damping * matrix[i * n + i].max(f64::EPSILON);
+            }
+            let mut step = rhs.clone();
+            if solve_linear(&mut damped, &mut step) {
+                let candidate = current
+                    .iter()
+                    .zip(&step)
+                    .map(|(p, dp)| p + dp)
+                    .collect::<Vec<_>>();
+                let candidate_residuals = model.residuals(&candidate)?;
+                let candidate_sum = sum_of_squares(&candidate_residuals);
+                if candidate_sum <= sum {
+                    improved = Some((candidate, candidate_residuals, candidate_sum));
+                    damping = (damping / 10.0).max(f64::EPSILON);
+                    break;
+                }
+            }
+            damping *= 10.0;
+        }
+        let Some((candidate, candidate_residuals, candidate_sum)) = improved else {
+            // no step decreases residuals, parameters are optimal within precision of Jacobian
+            converged = true;
+            break;
+        };
+        let step_is_small = current
+            .iter()
+            .zip(&candidate)
+            .all(|(p, new)| abs(new - p) <= options.tolerance * abs(*new).max(1.0));
+        let sum_change = sum - candidate_sum;
+        current = candidate;
+        residuals = candidate_residuals;
+        sum = candidate_sum;
+        jacobian = model.jacobian(&current)?;
+        if step_is_small || sum_change <= options.tolerance * sum {
+            converged = true;
+            break;
+        }
+    }
+    let (matrix, _) = normal_equations(&jacobian, &residuals, n);
+    let degrees_of_freedom = data.len() - n;
+    let variance = if degrees_of_freedom == 0 {
+        f64::NAN
+    } else {
+        sum / degrees_of_freedom as f64
+    };
+    Ok(Fit {
+        covariance: covariance(&matrix, variance, n),
+        parameters: current,
+        residual_sum_of_squares: sum,
+        iterations,
+        converged,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::vec::Vec;
+    use crate::fitting::{fit, FitOptions};
+    use crate::float::sqrt;
+    use crate::formulas::math::Exp;
+    use crate::formulas::{Evaluate, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{EmptyVariableStore, SetVariable, Variable, VectorVariableStore};
+
+    #[test]
+    fn test_linear_fit() {
+        let model = RootFormula::parse("a * t + b", &EmptyFunctionStore).unwrap();
+        let data = [(0.0, 1.0), (1.0, 3.5), (2.0, 4.5), (3.0, 7.0)];
+        let parameters = [(Variable::new("a"), 0.0), (Variable::new("b"), 0.0)];
+        let fit = fit(
+            &model,
+            &Variable::new("t"),
+            &data,
+            &parameters,
+            &EmptyVariableStore,
+            &FitOptions::new(),
+        )
+        .unwrap();
+        assert!(fit.converged());
+        // ordinary least squares: a = 1.9, b = 1.15, residual variance 0.45 / 2
+        assert!((fit.parameters()[0] - 1.9).abs() < 1e-8, "{fit:?}");
+        assert!((fit.parameters()[1] - 1.15).abs() < 1e-8, "{fit:?}");
+        assert!((fit.residual_sum_of_squares() - 0.45).abs() < 1e-9);
+        assert!((fit.covariance(0, 0) - 0.045).abs() < 1e-8, "{fit:?}");
+        assert!((fit.covariance(0, 1) + 0.0675).abs() < 1e-8, "{fit:?}");
+        assert!((fit.covariance(1, 0) - fit.covariance(0, 1)).abs() < 1e-9);
+        assert!((fit.standard_error(1) - sqrt(0.1575)).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_exponential_fit() {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<Exp>();
+        let model = RootFormula::parse("a * exp(b * t) + c", &functions).unwrap();
+        let mut store = VectorVariableStore::new();
+        store.set("a", 3.0);
+        store.set("b", -0.7);
+        store.set("c", 0.5);
+        let data = (0..20)
+            .map(|i| {
+                let t = f64::from(i) / 4.0;
+                store.set("t", t);
+                (t, model.eval(&store).unwrap())
+            })
+            .collect::<Vec<_>>();
+        let parameters = [
+            (Variable::new("a"), 1.0),
+            (Variable::new("b"), -0.1),
+            (Variable::new("c"), 0.0),
+        ];
+        let fit = fit(
+            &model,
+            &Variable::new("t"),
+            &data,
+            &parameters,
+            &EmptyVariableStore,
+            &FitOptions::new(),
+        )
+        .unwrap();
+        assert!(fit.converged());
+        for (value, expected) in fit.parameters().iter().zip([3.0, -0.7, 0.5]) {
+            assert!((value - expected).abs() < 1e-6, "{fit:?}");
+        }
+        assert!(fit.residual_sum_of_squares() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors() {
+        let model = RootFormula::parse("a * t + b + k", &EmptyFunctionStore).unwrap();
+        let parameters = [(Variable::new("a"), 0.0), (Variable::new("b"), 0.0)];
+        let t = Variable::new("t");
+        let options = FitOptions::new();
+        let data = [(0.0, 1.0), (1.0, 2.0)];
+        assert!(fit(
+            &model,
+            &t,
+            &data[..1],
+            &parameters,
+            &EmptyVariableStore,
+            &options
+        )
+        .is_err());
+        assert!(fit(
+            &model,
+            &t,
+            &data,
+            &parameters,
+            &EmptyVariableStore,
+            &options
+        )
+        .is_err());
+        let mut store = VectorVariableStore::new();
+        store.set("k", 1.0);
+        let fit = fit(&model, &t, &data, &parameters, &store, &options).unwrap();
+        assert!((fit.parameters()[1]).abs() < 1e-9, "{fit:?}");
+        assert!(fit.covariance(0, 0).is_nan());
+    }
+}
@@ -0,0 +1,274 @@
+// Floating point functions, that are provided by std or libm depending on enabled features.
+
+#[cfg(feature = "std")]
+mod implementation {
+    #[inline]
+    pub(crate) fn abs(x: f64) -> f64 {
+        x.abs()
+    }
+
+    #[inline]
+    pub(crate) fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+
+    #[inline]
+    pub(crate) fn ln(x: f64) -> f64 {
+        x.ln()
+    }
+
+    #[inline]
+    pub(crate) fn cos(x: f64) -> f64 {
+        x.cos()
+    }
+
+    #[inline]
+    pub(crate) fn sin(x: f64) -> f64 {
+        x.sin()
+    }
+
+    #[inline]
+    pub(crate) fn cosh(x: f64) -> f64 {
+        x.cosh()
+    }
+
+    #[inline]
+    pub(crate) fn sinh(x: f64) -> f64 {
+        x.sinh()
+    }
+
+    #[inline]
+    pub(crate) fn powf(x: f64, y: f64) -> f64 {
+        x.powf(y)
+    }
+    #[inline]
+    pub(crate) fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+
+    #[inline]
+    pub(crate) fn exp_m1(x: f64) -> f64 {
+        x.exp_m1()
+    }
+
+    #[inline]
+    pub(crate) fn ln_1p(x: f64) -> f64 {
+        x.ln_1p()
+    }
+
+    #[inline]
+    pub(crate) fn floor(x: f64) -> f64 {
+        x.floor()
+    }
+
+    #[cfg(feature = "libm")]
+    #[inline]
+    pub(crate) fn lgamma(x: f64) -> f64 {
+        libm::lgamma(x)
+    }
+
+    #[cfg(feature = "libm")]
+    #[inline]
+    pub(crate) fn erfc(x: f64) -> f64 {
+        libm::erfc(x)
+    }
+
+    #[cfg(feature = "libm")]
+    #[inline]
+    pub(crate) fn gamma(x: f64) -> f64 {
+        libm::tgamma(x)
+    }
+
+    // std does not provide these functions
+    #[cfg(not(feature = "libm"))]
+    pub(crate) use super::fallback::{erfc, gamma, lgamma};
+}
+
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+mod implementation {
+    #[inline]
+    pub(crate) fn abs(x: f64) -> f64 {
+        libm::fabs(x)
+    }
+
+    #[inline]
+    pub(crate) fn sqrt(x: f64) -> f64 {
+        libm::sqrt(x)
+    }
+
+    #[inline]
+    pub(crate) fn ln(x: f64) -> f64 {
+        libm::log(x)
+    }
+
+    #[inline]
+    pub(crate) fn cos(x: f64) -> f64 {
+        libm::cos(x)
+    }
+
+    #[inline]
+    pub(crate) fn sin(x: f64) -> f64 {
+        libm::sin(x)
+    }
+
+    #[inline]
+    pub(crate) fn cosh(x: f64) -> f64 {
+        libm::cosh(x)
+    }
+
+    #[inline]
+    pub(crate) fn sinh(x: f64) -> f64 {
+        libm::sinh(x)
+    }
+
+    #[inline]
+    pub(crate) fn powf(x: f64, y: f64) -> f64 {
+        libm::pow(x, y)
+    }
+    #[inline]
+    pub(crate) fn exp(x: f64) -> f64 {
+        libm::exp(x)
+    }
+
+    #[inline]
+    pub(crate) fn exp_m1(x: f64) -> f64 {
+        libm::expm1(x)
+    }
+
+    #[inline]
+    pub(crate) fn ln_1p(x: f64) -> f64 {
+        libm::log1p(x)
+    }
+
+    #[inline]
+    pub(crate) fn floor(x: f64) -> f64 {
+        libm::floor(x)
+    }
+
+    #[inline]
+    pub(crate) fn lgamma(x: f64) -> f64 {
+        libm::lgamma(x)
+    }
+
+    #[inline]
+    pub(crate) fn erfc(x: f64) -> f64 {
+        libm::erfc(x)
+    }
+
+    #[inline]
+    pub(crate) fn gamma(x: f64) -> f64 {
+        libm::tgamma(x)
+    }
+}
+
+// Numerical code follows notation of the formulas it implements.
+#[cfg(all(feature = "std", not(feature = "libm")))]
+#[allow(clippy::many_single_char_names, clippy::suboptimal_flops)]
+mod fallback {
+    // Lanczos approximation with g = 7 and reflection formula for `x < 0.5`.
+    pub(crate) fn lgamma(x: f64) -> f64 {
+        const G: f64 = 7.0;
+        const COEFFICIENTS: [f64; 9] = [
+            0.999_999_999_999_809_9,
+            676.520_368_121_885_1,
+            -1_259.139_216_722_402_8,
+            771.323_428_777_653_1,
+            -176.615_029_162_140_6,
+            12.507_343_278_686_905,
+            -0.138_571_095_265_720_12,
+            9.984_369_578_019_572e-6,
+            1.505_632_735_149_311_6e-7,
+        ];
+        if x.is_nan() || x.is_infinite() {
+            return x.abs();
+        }
+        if x < 0.5 {
+            let sin = (core::f64::consts::PI * x).sin().abs();
+            return (core::f64::consts::PI / sin).ln() - lgamma(1.0 - x);
+        }
+        let x = x - 1.0;
+        let mut sum = COEFFICIENTS[0];
+        let mut denominator = x;
+        for coefficient in &COEFFICIENTS[1..] {
+            denominator += 1.0;
+            sum += coefficient / denominator;
+        }
+        let t = x + G + 0.5;
+        0.5 * (2.0 * core::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+    }
+
+    // Exponent of `lgamma`, which is negative between odd and even negative numbers.
+    // Zero and negative integers are poles, where sign is undefined.
+    #[allow(clippy::float_cmp)]
+    pub(crate) fn gamma(x: f64) -> f64 {
+        if x < 0.0 && x.floor() == x {
+            return f64::NAN;
+        }
+        let value = lgamma(x).exp();
+        if x < 0.0 && x.floor() % 2.0 != 0.0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    // Series of erf for small `x` and continued fraction of upper incomplete gamma
+    // function Q(1/2, x^2) for large `x`.
+    pub(crate) fn erfc(x: f64) -> f64 {
+        const EPSILON: f64 = 1e-16;
+        const TINY: f64 = 1e-300;
+        const MAX_ITERATIONS: usize = 500;
+        if x.is_nan() {
+            return x;
+        }
+        if x < 0.0 {
+            return 2.0 - erfc(-x);
+        }
+        let z = x * x;
+        if x < 1.5 {
+            let mut term = x;
+            let mut sum = x;
+            let mut denominator = 1.0;
+            for _ in 0..MAX_ITERATIONS {
+                denominator += 2.0;
+                term *= 2.0 * z / denominator;
+                sum += term;
+                if term < sum * EPSILON {
+                    break;
+                }
+            }
+            return 1.0 - 2.0 / core::f64::consts::PI.sqrt() * (-z).exp() * sum;
+        }
+        if z.is_infinite() {
+            return 0.0;
+        }
+        // modified Lentz's method
+        let mut b = z + 0.5;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..MAX_ITERATIONS {
+            #[allow(clippy::cast_precision_loss)]
+            let i = i as f64;
+            let an = -i * (i - 0.5);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (-z).exp() * x / core::f64::consts::PI.sqrt() * h
+    }
+}
+
+pub(crate) use implementation::*;
@@ -0,0 +1,207 @@
+// Helpers for functions, that bind variable inside of their argument, like `diff(x^2, x, 1)`.
+// Bound variable is set by function itself while evaluating, so it is never substituted from outside.
+
+use crate::__lib::string::String;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::formulas::IsConst;
+use crate::formulas::{
+    Evaluate, EvaluationError, FunctionLike, MathError, ParserError, RootFormula, UnknownTokenError,
+};
+use crate::function_stores::GetFunction;
+use crate::variable_stores::{
+    EvaluationBudget, GetVariable, LayeredVariableStore, ResolutionChain, SetVariable, Variable,
+};
+
+/// Parses function argument, that must be name of variable.
+pub(crate) fn parse_variable_name<T: for<'a> GetFunction<'a>>(
+    name: &str,
+    formulas: &T,
+) -> Result<Variable, ParserError> {
+    RootFormula::parse(name, formulas)?
+        .as_variable()
+        .cloned()
+        .ok_or_else(|| UnknownTokenError(String::from(name.trim())).into())
+}
+
+// Variable store, that hides bound variable from formula.
+struct Shadowed<'a> {
+    parent: &'a dyn GetVariable,
+    variable: &'a Variable,
+}
+
+impl GetVariable for Shadowed<'_> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        if name == self.variable {
+            None
+        } else {
+            self.parent.get(name)
+        }
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.parent.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.parent.budget()
+    }
+}
+
+/// Formula with bound variable.
+#[derive(Debug, Clone)]
+pub(crate) struct BoundExpression {
+    formula: RootFormula,
+    variable: Variable,
+}
+
+impl BoundExpression {
+    pub(crate) fn parse<T: for<'a> GetFunction<'a>>(
+        expression: &str,
+        variable: &str,
+        formulas: &T,
+    ) -> Result<Self, ParserError> {
+        Ok(Self {
+            formula: RootFormula::parse(expression, formulas)?,
+            variable: parse_variable_name(variable, formulas)?,
+        })
+    }
+
+    /// Creates binding, that evaluates formula for different values of bound variable.
+    /// Other variables are taken from `args`.
+    pub(crate) fn binding<'a>(&'a self, args: &'a dyn GetVariable) -> Binding<'a> {
+        Binding::new(&self.formula, &self.variable, args)
+    }
+
+    // formula is constant only if it does not depend even on bound variable
+    #[cfg(any(feature = "std", feature = "libm"))]
+    pub(crate) fn is_const(&self) -> bool {
+        self.formula.is_const()
+    }
+
+    pub(crate) fn collapse_inner(&mut self) -> Result<(), MathError> {
+        self.formula.collapse_inner()
+    }
+
+    pub(crate) fn set_all_variables_shared(&mut self, args: &dyn GetVariable) {
+        self.formula.set_all_variables_shared(&Shadowed {
+            parent: args,
+            variable: &self.variable,
+        });
+    }
+
+    pub(crate) fn set_all_variables_owned(&mut self, args: &dyn GetVariable) {
+        self.formula.set_all_variables_owned(&Shadowed {
+            parent: args,
+            variable: &self.variable,
+        });
+    }
+
+    pub(crate) fn set_variable_shared(&mut self, name: &Variable, function: &Arc<RootFormula>) {
+        if *name != self.variable {
+            self.formula.set_variable_shared(name, function);
+        }
+    }
+
+    pub(crate) fn set_variable_owned(&mut self, name: &Variable, function: &RootFormula) {
+        if *name != self.variable {
+            self.formula.set_variable_owned(name, function);
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.formula.reset();
+    }
+
+    // bound variable is not a dependency of function, so it is skipped
+    pub(crate) fn collect_variables(&self, variables: &mut Vec<Variable>) {
+        let mut inner = Vec::new();
+        self.formula.collect_variables(&mut inner);
+        variables.extend(
+            inner
+                .into_iter()
+                .filter(|variable| *variable != self.variable),
+        );
+    }
+}
+
+// Every evaluation of bound formula is one step, so functions evaluating it many times are limited.
+fn step(store: &dyn GetVariable) -> Result<(), EvaluationError> {
+    if let Some(budget) = store.budget() {
+        budget.step().map_err(EvaluationError::LimitError)?;
+    }
+    Ok(())
+}
+
+/// Formula with bound variable, ready for evaluation.
+pub(crate) struct Binding<'a> {
+    formula: &'a RootFormula,
+    variable: &'a Variable,
+    store: LayeredVariableStore<'a>,
+}
+
+impl<'a> Binding<'a> {
+    /// Creates binding of `variable` in `formula`, other variables are taken from `args`.
+    pub(crate) fn new(
+        formula: &'a RootFormula,
+        variable: &'a Variable,
+        args: &'a dyn GetVariable,
+    ) -> Self {
+        Self {
+            formula,
+            variable,
+            store: LayeredVariableStore::new(args),
+        }
+    }
+
+    /// Evaluates formula with bound variable set to `value`.
+    pub(crate) fn eval(&mut self, value: f64) -> Result<f64, EvaluationError> {
+        step(&self.store)?;
+        self.store.set(self.variable.clone(), value);
+        self.formula.eval(&self.store)
+    }
+
+    /// Evaluates formula and its derivative with respect to bound variable set to `value`.
+    #[cfg(any(feature = "std", feature = "libm"))]
+    pub(crate) fn eval_derivative(&mut self, value: f64) -> Result<(f64, f64), EvaluationError> {
+        step(&self.store)?;
+        self.store.set(self.variable.clone(), value);
+        self.formula.eval_derivative(&self.store, self.variable)
+    }
+}
+
+/// Formulas with several bound variables, ready for evaluation.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub(crate) struct MultiBinding<'a> {
+    variables: &'a [Variable],
+    store: LayeredVariableStore<'a>,
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl<'a> MultiBinding<'a> {
+    /// Creates binding of `variables`, other variables are taken from `args`.
+    pub(crate) fn new(variables: &'a [Variable], args: &'a dyn GetVariable) -> Self {
+        Self {
+            variables,
+            store: LayeredVariableStore::new(args),
+        }
+    }
+
+    /// Sets bound variables to `values`, in the same order as variables.
+    pub(crate) fn set(&mut self, values: &[f64]) {
+        for (variable, value) in self.variables.iter().zip(values) {
+            self.store.set(variable.clone(), *value);
+        }
+    }
+
+    /// Evaluates `formula` with bound variables set to last values.
+    pub(crate) fn eval(&self, formula: &RootFormula) -> Result<f64, EvaluationError> {
+        step(&self.store)?;
+        formula.eval(&self.store)
+    }
+}
@@ -0,0 +1,488 @@
+use crate::__lib::boxed::Box;
+use crate::__lib::collections::BinaryHeap;
+use crate::__lib::string::format;
+use crate::__lib::vec::vec;
+use crate::float::abs;
+use crate::formulas::binding::{Binding, BoundExpression};
+use crate::formulas::{
+    Evaluate, EvaluationError, Function, FunctionLike, IsConst, MathError, ParserError, RootFormula,
+};
+use crate::function_stores::GetFunction;
+use crate::solvers::root::brent;
+use crate::solvers::SolverOptions;
+use crate::variable_stores::GetVariable;
+use core::cmp::Ordering;
+
+// Ridders' method: central differences with decreasing step are extrapolated to zero step
+// in Neville tableau, extrapolation stops when error starts to grow.
+// `mul_add` is not available without std.
+#[allow(clippy::suboptimal_flops)]
+pub(crate) fn ridders(binding: &mut Binding<'_>, x: f64) -> Result<f64, EvaluationError> {
+    const SHRINK: f64 = 1.4;
+    const SHRINK_SQUARED: f64 = SHRINK * SHRINK;
+    const SIZE: usize = 10;
+    const SAFE: f64 = 2.0;
+
+    let mut central_difference = |step: f64| -> Result<f64, EvaluationError> {
+        Ok((binding.eval(x + step)? - binding.eval(x - step)?) / (2.0 * step))
+    };
+
+    let mut step = 0.1 * abs(x).max(1.0);
+    let mut tableau = [[0.0; SIZE]; SIZE];
+    tableau[0][0] = central_difference(step)?;
+    let mut result = tableau[0][0];
+    let mut error = f64::MAX;
+    for i in 1..SIZE {
+        step /= SHRINK;
+        tableau[0][i] = central_difference(step)?;
+        let mut factor = SHRINK_SQUARED;
+        for j in 1..=i {
+            tableau[j][i] = (tableau[j - 1][i] * factor - tableau[j - 1][i - 1]) / (factor - 1.0);
+            factor *= SHRINK_SQUARED;
+            let new_error = abs(tableau[j][i] - tableau[j - 1][i])
+                .max(abs(tableau[j][i] - tableau[j - 1][i - 1]));
+            if new_error <= error {
+                error = new_error;
+                result = tableau[j][i];
+            }
+        }
+        if abs(tableau[i][i] - tableau[i - 1][i - 1]) >= SAFE * error {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+/// Function for numerical differentiation, called as `diff(expression, variable, at)`.
+/// Returns derivative of `expression` with respect to `variable` at point `at`.
+///
+/// `variable` is bound inside of `expression`, while evaluating other variables are taken from variable store.
+/// Variables from store, that depend on `variable`, are evaluated with its bound value too.
+#[derive(Debug, Clone)]
+pub struct Diff {
+    expression: BoundExpression,
+    at: RootFormula,
+}
+
+impl IsConst for Diff {
+    fn is_const(&self) -> bool {
+        self.expression.is_const() && self.at.is_const()
+    }
+}
+
+impl Evaluate for Diff {
+    fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
+        let at = self.at.eval(args)?;
+        ridders(&mut self.expression.binding(args), at)
+    }
+}
+
+impl FunctionLike for Diff {
+    crate::delegate_function_like!((expression, at));
+
+    fn clone_into_box(&self) -> Box<dyn FunctionLike> {
+        Box::new(self.clone())
+    }
+}
+
+impl Function for Diff {
+    const MIN_NUMBER_OF_ARGUMENTS: usize = 3;
+    const MAX_NUMBER_OF_ARGUMENTS: usize = 3;
+    const NAME: &'static str = "diff";
+
+    fn parse<T: for<'a> GetFunction<'a>>(
+        arguments: &[&str],
+        formulas: &T,
+    ) -> Result<Self, ParserError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            expression: BoundExpression::parse(arguments[0], arguments[1], formulas)?,
+            at: RootFormula::parse(arguments[2], formulas)?,
+        })
+    }
+}
+
+// Abscissae of 15-point Kronrod rule on [-1, 1], odd entries are abscissae of 7-point Gauss rule.
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0.0,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_2,
+    0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_8,
+];
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+
+// Integral over [a, b] by 15-point Kronrod rule, difference with embedded 7-point Gauss rule is used as error.
+fn gauss_kronrod(binding: &mut Binding<'_>, a: f64, b: f64) -> Result<(f64, f64), EvaluationError> {
+    let center = (a + b) / 2.0;
+    let half_length = (b - a) / 2.0;
+    let value = binding.eval(center)?;
+    let mut kronrod = value * KRONROD_WEIGHTS[7];
+    let mut gauss = value * GAUSS_WEIGHTS[3];
+    for (i, (node, weight)) in KRONROD_NODES[..7].iter().zip(KRONROD_WEIGHTS).enumerate() {
+        let offset = half_length * node;
+        let sum = binding.eval(center - offset)? + binding.eval(center + offset)?;
+        kronrod += weight * sum;
+        if i % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[i / 2] * sum;
+        }
+    }
+    Ok((kronrod * half_length, abs((kronrod - gauss) * half_length)))
+}
+
+// Subinterval of adaptive quadrature, ordered by its error.
+struct Subinterval {
+    a: f64,
+    b: f64,
+    value: f64,
+    error: f64,
+}
+
+impl Subinterval {
+    fn new(binding: &mut Binding<'_>, a: f64, b: f64) -> Result<Self, EvaluationError> {
+        let (value, error) = gauss_kronrod(binding, a, b)?;
+        Ok(Self { a, b, value, error })
+    }
+}
+
+impl PartialEq for Subinterval {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Subinterval {}
+
+impl PartialOrd for Subinterval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Subinterval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.error.total_cmp(&other.error)
+    }
+}
+
+// Adaptive Gauss-Kronrod quadrature, interval with the largest error is bisected until
+// total error is small enough. Intervals are kept in heap by error and totals are updated
+// on every bisection, so every step takes logarithmic time.
+fn adaptive_quadrature(
+    binding: &mut Binding<'_>,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+    max_subdivisions: usize,
+) -> Result<f64, EvaluationError> {
+    let whole = Subinterval::new(binding, a, b)?;
+    let (mut value, mut error) = (whole.value, whole.error);
+    let mut intervals = BinaryHeap::from(vec![whole]);
+    loop {
+        if value.is_nan() || error <= tolerance * abs(value).max(1.0) {
+            return Ok(value);
+        }
+        if intervals.len() >= max_subdivisions {
+            return Err(EvaluationError::MathError(MathError(format!(
+                "integral, estimated error {error} is larger than tolerance {tolerance} after {max_subdivisions} subdivisions"
+            ))));
+        }
+        let Some(worst) = intervals.pop() else {
+            return Ok(value);
+        };
+        let middle = (worst.a + worst.b) / 2.0;
+        let left = Subinterval::new(binding, worst.a, middle)?;
+        let right = Subinterval::new(binding, middle, worst.b)?;
+        value += left.value + right.value - worst.value;
+        error += left.error + right.error - worst.error;
+        intervals.push(left);
+        intervals.push(right);
+    }
+}
+
+/// Function for numerical integration, returns integral of `expression` over `variable` from `a` to `b`.
+///
+/// Called as `integrate(expression, variable, a, b)` or `integrate(expression, variable, a, b, tolerance, max_subdivisions)`.
+/// Integral is calculated by adaptive Gauss-Kronrod quadrature, until estimated error is less than
+/// `tolerance * max(1, |integral|)` (`1e-10` by default). If it is not reached, when interval is split into
+/// `max_subdivisions` (`100` by default) subintervals, evaluation returns [`MathError`].
+/// Returns NAN if bounds are not finite, `tolerance` is not positive or `max_subdivisions` is not in `[1, 100000]`.
+///
+/// `variable` is bound inside of `expression` the same way as in [`Diff`].
+#[derive(Debug, Clone)]
+pub struct Integrate {
+    expression: BoundExpression,
+    lower: RootFormula,
+    upper: RootFormula,
+    tolerance: RootFormula,
+    max_subdivisions: RootFormula,
+}
+
+impl Integrate {
+    const DEFAULT_TOLERANCE: f64 = 1e-10;
+    const DEFAULT_MAX_SUBDIVISIONS: f64 = 100.0;
+    // every subdivision is stored, so their number is limited, whatever is passed
+    const MAX_SUBDIVISIONS: f64 = 100_000.0;
+}
+
+impl IsConst for Integrate {
+    fn is_const(&self) -> bool {
+        self.expression.is_const()
+            && self.lower.is_const()
+            && self.upper.is_const()
+            && self.tolerance.is_const()
+            && self.max_subdivisions.is_const()
+    }
+}
+
+impl Evaluate for Integrate {
+    // subdivisions are counted in float, so cast is intended to drop fraction
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
+        let lower = self.lower.eval(args)?;
+        let upper = self.upper.eval(args)?;
+        let tolerance = self.tolerance.eval(args)?;
+        let max_subdivisions = self.max_subdivisions.eval(args)?;
+        if !(lower.is_finite()
+            && upper.is_finite()
+            && tolerance > 0.0
+            && (1.0..=Self::MAX_SUBDIVISIONS).contains(&max_subdivisions))
+        {
+            return Ok(f64::NAN);
+        }
+        adaptive_quadrature(
+            &mut self.expression.binding(args),
+            lower,
+            upper,
+            tolerance,
+            max_subdivisions as usize,
+        )
+    }
+}
+
+impl FunctionLike for Integrate {
+    crate::delegate_function_like!((expression, lower, upper, tolerance, max_subdivisions));
+
+    fn clone_into_box(&self) -> Box<dyn FunctionLike> {
+        Box::new(self.clone())
+    }
+}
+
+impl Function for Integrate {
+    const MIN_NUMBER_OF_ARGUMENTS: usize = 4;
+    const MAX_NUMBER_OF_ARGUMENTS: usize = 6;
+    const NAME: &'static str = "integrate";
+
+    fn parse<T: for<'a> GetFunction<'a>>(
+        arguments: &[&str],
+        formulas: &T,
+    ) -> Result<Self, ParserError>
+    where
+        Self: Sized,
+    {
+        let optional = |index: usize, default: f64| {
+            arguments.get(index).map_or_else(
+                || Ok(RootFormula::new(default)),
+                |argument| RootFormula::parse(argument, formulas),
+            )
+        };
+        Ok(Self {
+            expression: BoundExpression::parse(arguments[0], arguments[1], formulas)?,
+            lower: RootFormula::parse(arguments[2], formulas)?,
+            upper: RootFormula::parse(arguments[3], formulas)?,
+            tolerance: optional(4, Self::DEFAULT_TOLERANCE)?,
+            max_subdivisions: optional(5, Self::DEFAULT_MAX_SUBDIVISIONS)?,
+        })
+    }
+}
+
+/// Function for root finding, called as `solve(expression, variable, a, b)`.
+/// Returns value of `variable` from interval `[a, b]`, for which `expression` evaluates to zero.
+///
+/// Root is found by Brent's method, `expression` must have different signs at `a` and `b`, otherwise
+/// or if method does not converge evaluation returns [`MathError`]. See also [`RootFormula::solve_for`].
+///
+/// `variable` is bound inside of `expression` the same way as in [`Diff`].
+#[derive(Debug, Clone)]
+pub struct Solve {
+    expression: BoundExpression,
+    lower: RootFormula,
+    upper: RootFormula,
+}
+
+impl IsConst for Solve {
+    fn is_const(&self) -> bool {
+        self.expression.is_const() && self.lower.is_const() && self.upper.is_const()
+    }
+}
+
+impl Evaluate for Solve {
+    fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
+        let lower = self.lower.eval(args)?;
+        let upper = self.upper.eval(args)?;
+        brent(
+            &mut self.expression.binding(args),
+            lower,
+            upper,
+            &SolverOptions::default(),
+        )
+        .map(|solution| solution.root())
+    }
+}
+
+impl FunctionLike for Solve {
+    crate::delegate_function_like!((expression, lower, upper));
+
+    fn clone_into_box(&self) -> Box<dyn FunctionLike> {
+        Box::new(self.clone())
+    }
+}
+
+impl Function for Solve {
+    const MIN_NUMBER_OF_ARGUMENTS: usize = 4;
+    const MAX_NUMBER_OF_ARGUMENTS: usize = 4;
+    const NAME: &'static str = "solve";
+
+    fn parse<T: for<'a> GetFunction<'a>>(
+        arguments: &[&str],
+        formulas: &T,
+    ) -> Result<Self, ParserError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            expression: BoundExpression::parse(arguments[0], arguments[1], formulas)?,
+            lower: RootFormula::parse(arguments[2], formulas)?,
+            upper: RootFormula::parse(arguments[3], formulas)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::calculus::{Diff, Integrate, Solve};
+    use crate::formulas::math::Sin;
+    use crate::formulas::{Evaluate, EvaluationError, FunctionLike, ParserError, RootFormula};
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn store() -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Diff>();
+        store.register::<Integrate>();
+        store.register::<Solve>();
+        store.register::<Sin>();
+        store
+    }
+
+    fn assert_close(expression: &str, variables: &VectorVariableStore, expected: f64) {
+        let value = RootFormula::parse(expression, &store())
+            .unwrap()
+            .eval(variables)
+            .unwrap();
+        assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    }
+
+    #[test]
+    fn test_diff() {
+        let variables = VectorVariableStore::new();
+        assert_close("diff(x^3, x, 2)", &variables, 12.0);
+        assert_close("diff(sin(x), x, 0)", &variables, 1.0);
+        assert_close("diff(diff(x^3, x, y), y, 1)", &variables, 6.0);
+    }
+
+    #[test]
+    fn test_diff_with_store_variables() {
+        let mut variables = VectorVariableStore::new();
+        variables.set("a", 3.0);
+        variables.set("x", 100.0);
+        variables.set("y", RootFormula::parse("a * x^2", &store()).unwrap());
+        assert_close("diff(a * x^2, x, 1)", &variables, 6.0);
+        assert_close("diff(y, x, 1)", &variables, 6.0);
+        assert_close("diff(x, x, x)", &variables, 1.0);
+    }
+
+    #[test]
+    fn test_bound_variable_is_not_substituted() {
+        let mut formula = RootFormula::parse("diff(x^2, x, x)", &store()).unwrap();
+        formula.set_variable_owned(&Variable::new("x"), &RootFormula::new(3.0));
+        let value = formula.eval(&VectorVariableStore::new()).unwrap();
+        assert!((value - 6.0).abs() < 1e-9, "{value}");
+    }
+
+    #[test]
+    fn test_not_a_variable() {
+        let result = RootFormula::parse("diff(x^2, 2 * x, 1)", &store());
+        assert!(matches!(result, Err(ParserError::UnknownTokenError(_))));
+    }
+
+    #[test]
+    fn test_integrate() {
+        let mut variables = VectorVariableStore::new();
+        variables.set("a", 2.0);
+        assert_close("integrate(x^2, x, 0, 3)", &variables, 9.0);
+        assert_close(
+            "integrate(a * sin(x), x, 0, 3.141592653589793)",
+            &variables,
+            4.0,
+        );
+        assert_close("integrate(x, x, 1, 0)", &variables, -0.5);
+        assert_close(
+            "integrate(integrate(x * y, x, 0, 1), y, 0, 2)",
+            &variables,
+            1.0,
+        );
+        assert_close(
+            "integrate(x^0.5, x, 0, 1, 0.00000001, 200)",
+            &variables,
+            2.0 / 3.0,
+        );
+    }
+
+    #[test]
+    fn test_integrate_not_converged() {
+        let formula =
+            RootFormula::parse("integrate(1 / x, x, 0, 1, 0.000000000001, 3)", &store()).unwrap();
+        let result = formula.eval(&VectorVariableStore::new());
+        assert!(matches!(result, Err(EvaluationError::MathError(_))));
+        let formula = RootFormula::parse("integrate(x, x, 0, 1, 0)", &store()).unwrap();
+        assert!(formula.eval(&VectorVariableStore::new()).unwrap().is_nan());
+        let formula = RootFormula::parse(
+            "integrate(x, x, 0, 1, 0.001, 1000000000000000000)",
+            &store(),
+        )
+        .unwrap();
+        assert!(formula.eval(&VectorVariableStore::new()).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut variables = VectorVariableStore::new();
+        variables.set("target", 2.0);
+        assert_close("solve(x^2 - target, x, 0, 2)", &variables, 2.0_f64.sqrt());
+        assert_close("solve(diff(x^2, x, y) - 3, y, 0, 10)", &variables, 1.5);
+        let formula = RootFormula::parse("solve(x^2 + 1, x, 0, 2)", &store()).unwrap();
+        let result = formula.eval(&variables);
+        assert!(matches!(result, Err(EvaluationError::MathError(_))));
+    }
+}
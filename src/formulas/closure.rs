@@ -0,0 +1,307 @@
+use crate::__lib::boxed::Box;
+use crate::__lib::fmt::{Debug, Formatter};
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::compiled::{slot, Slots};
+use crate::formulas::{
+    Evaluate, EvaluationError, FunctionLike, IsConst, Lowering, NoVariableError, Operation,
+    RootFormula,
+};
+use crate::variable_stores::{EmptyVariableStore, Variable};
+
+type Node = Box<dyn Fn(&[f64]) -> Result<f64, EvaluationError> + Send + Sync>;
+
+// Compiled part of formula, constants and variables are kept separately, so parent node can capture them directly.
+enum Part {
+    Number(f64),
+    Slot(usize),
+    Node(Node),
+}
+
+impl Part {
+    fn node(
+        function: impl Fn(&[f64]) -> Result<f64, EvaluationError> + Send + Sync + 'static,
+    ) -> Self {
+        Self::Node(Box::new(function))
+    }
+
+    fn into_node(self) -> Node {
+        match self {
+            Self::Number(value) => Box::new(move |_| Ok(value)),
+            Self::Slot(slot) => Box::new(move |values| Ok(values[slot])),
+            Self::Node(node) => node,
+        }
+    }
+}
+
+// Creates part, that applies `$body` to values of two parts, closure is specialized for every kind of arguments.
+macro_rules! binary_part {
+    ($first:expr, $second:expr, |$a:ident, $b:ident| $body:expr) => {
+        match ($first, $second) {
+            (Part::Number($a), Part::Number($b)) => Part::Number($body),
+            (Part::Slot(first), Part::Slot(second)) => Part::node(move |values| {
+                let ($a, $b) = (values[first], values[second]);
+                Ok($body)
+            }),
+            (Part::Slot(first), Part::Number($b)) => Part::node(move |values| {
+                let $a = values[first];
+                Ok($body)
+            }),
+            (Part::Number($a), Part::Slot(second)) => Part::node(move |values| {
+                let $b = values[second];
+                Ok($body)
+            }),
+            (Part::Node(first), Part::Number($b)) => Part::node(move |values| {
+                let $a = first(values)?;
+                Ok($body)
+            }),
+            (Part::Number($a), Part::Node(second)) => Part::node(move |values| {
+                let $b = second(values)?;
+                Ok($body)
+            }),
+            (Part::Node(first), Part::Slot(second)) => Part::node(move |values| {
+                let ($a, $b) = (first(values)?, values[second]);
+                Ok($body)
+            }),
+            (Part::Slot(first), Part::Node(second)) => Part::node(move |values| {
+                let ($a, $b) = (values[first], second(values)?);
+                Ok($body)
+            }),
+            (Part::Node(first), Part::Node(second)) => Part::node(move |values| {
+                let ($a, $b) = (first(values)?, second(values)?);
+                Ok($body)
+            }),
+        }
+    };
+}
+
+fn operation_part(operation: Operation, first: Part, second: Part) -> Part {
+    match operation {
+        Operation::Add => binary_part!(first, second, |a, b| a + b),
+        Operation::Subtract => binary_part!(first, second, |a, b| a - b),
+        Operation::Multiply => binary_part!(first, second, |a, b| a * b),
+        Operation::Divide => binary_part!(first, second, |a, b| a / b),
+        #[cfg(any(feature = "std", feature = "libm"))]
+        Operation::Power => binary_part!(first, second, |a, b| crate::float::powf(a, b)),
+    }
+}
+
+fn unary_part(function: fn(f64) -> f64, argument: Part) -> Part {
+    match argument {
+        Part::Number(value) => Part::Number(function(value)),
+        Part::Slot(slot) => Part::node(move |values| Ok(function(values[slot]))),
+        Part::Node(node) => Part::node(move |values| Ok(function(node(values)?))),
+    }
+}
+
+// Functions with at most this many arguments get their values in buffer on stack.
+const STACK_ARGUMENTS: usize = 8;
+
+fn many_part(function: fn(&[f64]) -> f64, arguments: Vec<Part>) -> Part {
+    let arguments = arguments
+        .into_iter()
+        .map(Part::into_node)
+        .collect::<Vec<_>>();
+    if arguments.len() <= STACK_ARGUMENTS {
+        return Part::node(move |values| {
+            let mut evaluated = [0.0; STACK_ARGUMENTS];
+            for (value, argument) in evaluated.iter_mut().zip(&arguments) {
+                *value = argument(values)?;
+            }
+            Ok(function(&evaluated[..arguments.len()]))
+        });
+    }
+    Part::node(move |values| {
+        let evaluated = arguments
+            .iter()
+            .map(|argument| argument(values))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(function(&evaluated))
+    })
+}
+
+// Functions, which can not be lowered, are added to `calls`, so their inner state can be reset.
+fn compile(
+    formula: &RootFormula,
+    variables: &Arc<[Variable]>,
+    calls: &mut Vec<Arc<RootFormula>>,
+) -> Result<Part, NoVariableError> {
+    if let Some(variable) = formula.as_variable() {
+        return Ok(Part::Slot(slot(variables, variable)?));
+    }
+    if formula.is_const() {
+        if let Ok(value) = formula.eval(&EmptyVariableStore) {
+            return Ok(Part::Number(value));
+        }
+    }
+    Ok(match formula.as_function().and_then(FunctionLike::lower) {
+        Some(Lowering::Operation(operation, first, second)) => operation_part(
+            operation,
+            compile(first, variables, calls)?,
+            compile(second, variables, calls)?,
+        ),
+        Some(Lowering::Binary(function, first, second)) => binary_part!(
+            compile(first, variables, calls)?,
+            compile(second, variables, calls)?,
+            |a, b| function(a, b)
+        ),
+        Some(Lowering::Unary(function, argument)) => {
+            unary_part(function, compile(argument, variables, calls)?)
+        }
+        Some(Lowering::Many(function, arguments)) => many_part(
+            function,
+            arguments
+                .into_iter()
+                .map(|argument| compile(argument, variables, calls))
+                .collect::<Result<_, _>>()?,
+        ),
+        Some(Lowering::Formula(inner)) => compile(inner, variables, calls)?,
+        None => {
+            let formula = Arc::new(formula.clone());
+            calls.push(Arc::clone(&formula));
+            let variables = Arc::clone(variables);
+            Part::node(move |values| formula.eval(&Slots::new(&variables, values)))
+        }
+    })
+}
+
+/// Formula compiled into nested closures, which is evaluated with values of variables passed by position.
+///
+/// Every lowered function becomes closure specialized for its operation and kinds of arguments,
+/// constants and positions of variables are captured by closures directly.
+/// Functions, which can not be lowered, are evaluated as is, the same way as in [`CompiledFormula`](crate::formulas::CompiledFormula).
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::{ClosureFormula, RootFormula};
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::Variable;
+///
+/// let formula = RootFormula::parse("x * (y + 2)", &EmptyFunctionStore).unwrap();
+/// let compiled = ClosureFormula::new(&formula, &[Variable::new("x"), Variable::new("y")]).unwrap();
+/// assert_eq!(compiled.eval(&[3.0, 1.0]).unwrap(), 9.0);
+/// ```
+pub struct ClosureFormula {
+    root: Node,
+    variables: Arc<[Variable]>,
+    calls: Vec<Arc<RootFormula>>,
+}
+
+impl Debug for ClosureFormula {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        f.debug_struct("ClosureFormula")
+            .field("variables", &self.variables)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClosureFormula {
+    /// Compiles `formula`, values of `variables` will be passed to [`ClosureFormula::eval`] in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if formula contains variable, which is not in `variables`.
+    /// Variables of functions, which can not be lowered, are checked only on evaluation.
+    pub fn new(formula: &RootFormula, variables: &[Variable]) -> Result<Self, NoVariableError> {
+        let variables: Arc<[Variable]> = variables.into();
+        let mut calls = Vec::new();
+        Ok(Self {
+            root: compile(formula, &variables, &mut calls)?.into_node(),
+            variables,
+            calls,
+        })
+    }
+
+    /// Returns variables of compiled formula in the order of their values.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    /// Evaluates compiled formula, `values` are values of variables in the same order as variables.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if there are less values than variables or function, which was not lowered, failed to evaluate.
+    #[inline]
+    pub fn eval(&self, values: &[f64]) -> Result<f64, EvaluationError> {
+        if values.len() < self.variables.len() {
+            let missing = self.variables[values.len()].clone();
+            return Err(EvaluationError::NoVariableError(NoVariableError::new(
+                missing,
+            )));
+        }
+        (self.root)(values)
+    }
+
+    /// Resets inner state of functions, which were not lowered, see [`FunctionLike::reset`].
+    pub fn reset(&self) {
+        for formula in &self.calls {
+            formula.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+// compiled formula must return exactly the same values as tree
+#[allow(clippy::float_cmp)]
+mod test {
+    #[cfg(any(feature = "std", feature = "libm"))]
+    use crate::formulas::math::Sin;
+    use crate::formulas::stateful::Prev;
+    use crate::formulas::{ClosureFormula, Evaluate, Min, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn store() -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        #[cfg(any(feature = "std", feature = "libm"))]
+        store.register::<Sin>();
+        store.register::<Min>();
+        store.register::<Prev>();
+        store
+    }
+
+    fn assert_same_as_tree(expression: &str) {
+        let variables = [Variable::new("x"), Variable::new("y")];
+        let formula = RootFormula::parse(expression, &store()).unwrap();
+        let compiled = ClosureFormula::new(&formula, &variables).unwrap();
+        let mut tree_variables = VectorVariableStore::new();
+        for (x, y) in [(0.5, 2.0), (-1.0, 3.5), (4.0, -0.25)] {
+            tree_variables.set("x", x);
+            tree_variables.set("y", y);
+            let expected = formula.eval(&tree_variables).unwrap();
+            assert_eq!(compiled.eval(&[x, y]).unwrap(), expected, "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_same_as_tree() {
+        assert_same_as_tree("x + y");
+        assert_same_as_tree("x - 2 * y + 3 / x - y / 4");
+        assert_same_as_tree("(x + y) * (x - y) / (1 + x * y)");
+        assert_same_as_tree("min(x, y, 3) - min(y * 2, x)");
+        assert_same_as_tree("min(x, y, 1, 2, 3, 4, 5, 6, 7, 8) + min(y, x, 1, 2, 3, 4, 5, 6)");
+        assert_same_as_tree("2 * 3 + x");
+    }
+
+    #[cfg(any(feature = "std", feature = "libm"))]
+    #[test]
+    fn test_functions_same_as_tree() {
+        assert_same_as_tree("sin(x) * sin(y + 1) + 1");
+        assert_same_as_tree("x ^ 2 + sin(1) ^ y + 2 ^ 3");
+    }
+
+    #[test]
+    fn test_call_and_errors() {
+        let formula = RootFormula::parse("prev(x * y) + x", &store()).unwrap();
+        let variables = [Variable::new("x"), Variable::new("y")];
+        let compiled = ClosureFormula::new(&formula, &variables).unwrap();
+        assert!(compiled.eval(&[1.0, 2.0]).unwrap().is_nan());
+        assert_eq!(compiled.eval(&[3.0, 4.0]).unwrap(), 5.0);
+        compiled.reset();
+        assert!(compiled.eval(&[1.0, 2.0]).unwrap().is_nan());
+        assert!(compiled.eval(&[3.0]).is_err());
+        let formula = RootFormula::parse("x + z", &EmptyFunctionStore).unwrap();
+        assert!(ClosureFormula::new(&formula, &variables).is_err());
+    }
+}
@@ -0,0 +1,445 @@
+// Arguments are checked to be integers before casting, so casts can not truncate them.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use crate::__lib::fmt::Debug;
+use crate::__lib::string::format;
+use crate::formulas::{EvaluationError, MathError};
+use core::marker::PhantomData;
+
+/// Defines result of combinatorics functions for non-integral arguments.
+pub trait NonIntegerPolicy: Debug + Send + Sync + 'static {
+    /// Returns result of function `name` called with non-integral argument `value`.
+    ///
+    /// # Errors
+    ///
+    /// will return Err if policy treats non-integral arguments as errors.
+    fn non_integer(name: &str, value: f64) -> Result<f64, EvaluationError>;
+}
+
+/// Policy that makes functions return NAN for non-integral arguments.
+#[derive(Debug)]
+pub struct ReturnNan;
+
+impl NonIntegerPolicy for ReturnNan {
+    #[inline]
+    fn non_integer(_name: &str, _value: f64) -> Result<f64, EvaluationError> {
+        Ok(f64::NAN)
+    }
+}
+
+/// Policy that makes functions return [`MathError`] for non-integral arguments.
+#[derive(Debug)]
+pub struct ReturnError;
+
+impl NonIntegerPolicy for ReturnError {
+    fn non_integer(name: &str, value: f64) -> Result<f64, EvaluationError> {
+        Err(EvaluationError::MathError(MathError(format!(
+            "{name} of non-integer argument {value}"
+        ))))
+    }
+}
+
+#[inline]
+fn is_integer(value: f64) -> bool {
+    value.is_finite() && value % 1.0 == 0.0
+}
+
+#[inline]
+fn abs(value: f64) -> f64 {
+    f64::from_bits(value.to_bits() & !(1 << 63))
+}
+
+#[inline]
+fn as_u64(value: f64) -> Option<u64> {
+    if value >= 0.0 && value < u64::MAX as f64 {
+        Some(value as u64)
+    } else {
+        None
+    }
+}
+
+#[inline]
+const fn bool_to_f64(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+// product of integers in `low..=high`, computed exactly while result fits into u128.
+fn product(low: u64, high: u64) -> f64 {
+    let mut exact: u128 = 1;
+    for value in low..=high {
+        if let Some(res) = exact.checked_mul(u128::from(value)) {
+            exact = res;
+        } else {
+            let mut approximate = exact as f64;
+            for value in value..=high {
+                approximate *= value as f64;
+                if approximate.is_infinite() {
+                    break;
+                }
+            }
+            return approximate;
+        }
+    }
+    exact as f64
+}
+
+fn factorial(n: f64) -> f64 {
+    // 171! does not fit into f64
+    match as_u64(n) {
+        Some(n) if n <= 170 => product(1, n),
+        Some(_) => f64::INFINITY,
+        None => f64::NAN,
+    }
+}
+
+fn binomial(n: f64, k: f64) -> f64 {
+    let Some(n) = as_u64(n) else {
+        return f64::NAN;
+    };
+    let Some(k) = as_u64(k).filter(|k| *k <= n) else {
+        return 0.0;
+    };
+    let k = k.min(n - k);
+    let mut exact: u128 = 1;
+    for i in 1..=k {
+        // product of `i` consecutive integers is always divisible by `i!`
+        if let Some(res) = exact.checked_mul(u128::from(n - k + i)) {
+            exact = res / u128::from(i);
+        } else {
+            let mut approximate = exact as f64;
+            for i in i..=k {
+                approximate = approximate * (n - k + i) as f64 / i as f64;
+            }
+            return approximate;
+        }
+    }
+    exact as f64
+}
+
+fn permutations(n: f64, k: f64) -> f64 {
+    let Some(n) = as_u64(n) else {
+        return f64::NAN;
+    };
+    match as_u64(k) {
+        Some(0) => 1.0,
+        Some(k) if k <= n => product(n - k + 1, n),
+        _ => 0.0,
+    }
+}
+
+const fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn gcd(a: f64, b: f64) -> f64 {
+    match (as_u64(abs(a)), as_u64(abs(b))) {
+        (Some(a), Some(b)) => gcd_u64(a, b) as f64,
+        _ => f64::NAN,
+    }
+}
+
+fn lcm(a: f64, b: f64) -> f64 {
+    match (as_u64(abs(a)), as_u64(abs(b))) {
+        (Some(0), Some(_)) | (Some(_), Some(0)) => 0.0,
+        (Some(a), Some(b)) => (u128::from(a / gcd_u64(a, b)) * u128::from(b)) as f64,
+        _ => f64::NAN,
+    }
+}
+
+fn mul_mod(a: u64, b: u64, modulo: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(modulo)) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulo: u64) -> u64 {
+    let mut res = 1;
+    base %= modulo;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            res = mul_mod(res, base, modulo);
+        }
+        base = mul_mod(base, base, modulo);
+        exponent >>= 1;
+    }
+    res
+}
+
+// deterministic Miller-Rabin test, these bases are enough for all 64 bit integers.
+fn is_prime_u64(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for base in BASES {
+        if n % base == 0 {
+            return n == base;
+        }
+    }
+    let shift = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> shift;
+    'bases: for base in BASES {
+        let mut x = pow_mod(base, odd, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..shift {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn is_prime(n: f64) -> f64 {
+    bool_to_f64(as_u64(n).is_some_and(is_prime_u64))
+}
+
+fn isqrt(n: f64) -> f64 {
+    let Some(n) = as_u64(n) else {
+        return f64::NAN;
+    };
+    if n < 2 {
+        return n as f64;
+    }
+    // Newton's method on integers, starting from value that is not less than the answer
+    let mut x = 1_u64 << ((64 - n.leading_zeros()) / 2 + 1);
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x as f64;
+        }
+        x = next;
+    }
+}
+
+macro_rules! impl_integer_function {
+    (
+        $parser_name:expr, ($($argument:ident),+), $function:ident,
+        $(#[$meta: meta])*
+        $StructName:ident
+    ) => {
+        $(#[$meta])*
+        ///
+        /// Result for non-integral arguments is defined by [`NonIntegerPolicy`] `P`.
+        #[derive(Debug)]
+        pub struct $StructName<P = ReturnNan> {
+            $($argument: $crate::formulas::RootFormula,)+
+            policy: PhantomData<P>,
+        }
+
+        impl<P: NonIntegerPolicy> $crate::formulas::IsConst for $StructName<P> {
+            #[inline]
+            fn is_const(&self) -> bool {
+                $(self.$argument.is_const())&&+
+            }
+        }
+
+        impl<P: NonIntegerPolicy> $crate::formulas::Evaluate for $StructName<P> {
+            fn eval(&self, args: &dyn $crate::variable_stores::GetVariable) -> Result<f64, EvaluationError> {
+                $(
+                    let $argument = self.$argument.eval(args)?;
+                    if !is_integer($argument) {
+                        return P::non_integer($parser_name, $argument);
+                    }
+                )+
+                Ok($function($($argument),+))
+            }
+        }
+
+        impl<P: NonIntegerPolicy> $crate::formulas::FunctionLike for $StructName<P> {
+            $crate::delegate_function_like!(($($argument),+));
+
+            fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
+                $crate::__lib::boxed::Box::new(Self {
+                    $($argument: self.$argument.clone(),)+
+                    policy: PhantomData,
+                })
+            }
+        }
+
+        impl<P: NonIntegerPolicy> $crate::formulas::Function for $StructName<P> {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = [$(stringify!($argument)),+].len();
+            const MAX_NUMBER_OF_ARGUMENTS: usize = Self::MIN_NUMBER_OF_ARGUMENTS;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> $crate::function_stores::GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, $crate::formulas::ParserError>
+            where
+                Self: Sized,
+            {
+                let mut arguments = arguments.iter();
+                Ok(Self {
+                    $($argument: $crate::formulas::RootFormula::parse(
+                        arguments.next().copied().unwrap_or_default(),
+                        formulas,
+                    )?,)+
+                    policy: PhantomData,
+                })
+            }
+        }
+    };
+}
+
+impl_integer_function!(
+    "factorial", (n), factorial,
+    /// Factorial of `n`, called as `factorial(n)`.
+    Factorial
+);
+
+impl_integer_function!(
+    "binomial", (n, k), binomial,
+    /// Number of ways to choose `k` elements out of `n`, called as `binomial(n, k)`.
+    Binomial
+);
+
+impl_integer_function!(
+    "permutations", (n, k), permutations,
+    /// Number of ordered arrangements of `k` elements out of `n`, called as `permutations(n, k)`.
+    Permutations
+);
+
+impl_integer_function!(
+    "gcd", (a, b), gcd,
+    /// Greatest common divisor of `a` and `b`, called as `gcd(a, b)`.
+    Gcd
+);
+
+impl_integer_function!(
+    "lcm", (a, b), lcm,
+    /// Least common multiple of `a` and `b`, called as `lcm(a, b)`.
+    Lcm
+);
+
+impl_integer_function!(
+    "is_prime", (n), is_prime,
+    /// Returns 1 if `n` is prime number, else returns 0, called as `is_prime(n)`.
+    IsPrime
+);
+
+impl_integer_function!(
+    "isqrt", (n), isqrt,
+    /// Integer square root of `n`, called as `isqrt(n)`.
+    Isqrt
+);
+
+fn is_integer_function(value: f64) -> f64 {
+    bool_to_f64(is_integer(value))
+}
+
+fn is_even(value: f64) -> f64 {
+    bool_to_f64(is_integer(value) && value % 2.0 == 0.0)
+}
+
+fn is_odd(value: f64) -> f64 {
+    bool_to_f64(is_integer(value) && value % 2.0 != 0.0)
+}
+
+impl_one_arg_function!(
+    "is_integer", is_integer_function,
+    /// Returns 1 if argument is integer, else returns 0.
+    pub IsInteger
+);
+
+impl_one_arg_function!(
+    "is_even", is_even,
+    /// Returns 1 if argument is even integer, else returns 0.
+    pub IsEven
+);
+
+impl_one_arg_function!(
+    "is_odd", is_odd,
+    /// Returns 1 if argument is odd integer, else returns 0.
+    pub IsOdd
+);
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::combinatorics::{
+        Binomial, Factorial, Gcd, IsPrime, Isqrt, Lcm, Permutations, ReturnError,
+    };
+    use crate::formulas::{Evaluate, EvaluationError, RootFormula};
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::EmptyVariableStore;
+
+    fn eval(expression: &str) -> f64 {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Factorial>();
+        store.register::<Binomial>();
+        store.register::<Permutations>();
+        store.register::<Gcd>();
+        store.register::<Lcm>();
+        store.register::<IsPrime>();
+        store.register::<Isqrt>();
+        RootFormula::parse(expression, &store)
+            .unwrap()
+            .eval(&EmptyVariableStore)
+            .unwrap()
+    }
+
+    fn assert_eval(expression: &str, expected: f64) {
+        let res = eval(expression);
+        assert!(
+            (res - expected).abs() < f64::EPSILON,
+            "{expression} = {res}"
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eval("factorial(5)", 120.0);
+        assert_eval("binomial(10, 3)", 120.0);
+        assert_eval("binomial(3, 10)", 0.0);
+        assert_eval("permutations(10, 3)", 720.0);
+        assert_eval("gcd(12, 18)", 6.0);
+        assert_eval("lcm(4, 6)", 12.0);
+        assert_eval("is_prime(97)", 1.0);
+        assert_eval("is_prime(2147483649)", 0.0);
+        assert_eval("isqrt(99)", 9.0);
+        assert_eval("isqrt(100)", 10.0);
+    }
+
+    #[test]
+    fn test_big_binomial() {
+        let res = eval("binomial(100, 50)");
+        assert!(
+            (res - 1.008_913_445_455_642e29).abs() / res < 1e-14,
+            "{res}"
+        );
+    }
+
+    #[test]
+    fn test_non_integer_nan() {
+        assert!(eval("factorial(2.5)").is_nan());
+    }
+
+    #[test]
+    fn test_non_integer_error() {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Factorial<ReturnError>>();
+        let res = RootFormula::parse("factorial(2.5)", &store);
+        assert!(
+            matches!(
+                res,
+                Err(crate::formulas::ParserError::EvaluationError(
+                    EvaluationError::MathError(_)
+                ))
+            ),
+            "{res:?}"
+        );
+    }
+}
@@ -0,0 +1,599 @@
+use crate::__lib::string::format;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::{vec, Vec};
+use crate::formulas::{
+    Evaluate, EvaluationError, FunctionLike, IsConst, MathError, NoVariableError, RootFormula,
+};
+use crate::variable_stores::{GetVariable, Variable};
+
+/// Arithmetic operation of two numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Addition.
+    Add,
+    /// Subtraction.
+    Subtract,
+    /// Multiplication.
+    Multiply,
+    /// Division.
+    Divide,
+    /// Exponentiation.
+    #[cfg(any(feature = "std", feature = "libm"))]
+    Power,
+}
+
+impl Operation {
+    fn function(self) -> fn(f64, f64) -> f64 {
+        match self {
+            Self::Add => |first, second| first + second,
+            Self::Subtract => |first, second| first - second,
+            Self::Multiply => |first, second| first * second,
+            Self::Divide => |first, second| first / second,
+            #[cfg(any(feature = "std", feature = "libm"))]
+            Self::Power => crate::float::powf,
+        }
+    }
+}
+
+/// Description of function in terms of plain functions of numbers, which allows to compile it into [`CompiledFormula`].
+///
+/// Function is returned from [`FunctionLike::lower`], arguments are evaluated before calling plain function.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Lowering<'a> {
+    /// Arithmetic operation, which allows compilers to specialize it.
+    Operation(Operation, &'a RootFormula, &'a RootFormula),
+    /// Function of one argument.
+    Unary(fn(f64) -> f64, &'a RootFormula),
+    /// Function of two arguments.
+    Binary(fn(f64, f64) -> f64, &'a RootFormula, &'a RootFormula),
+    /// Function of any number of arguments, values of arguments are passed in the same order.
+    Many(fn(&[f64]) -> f64, Vec<&'a RootFormula>),
+    /// Function is the same as formula.
+    Formula(&'a RootFormula),
+}
+
+#[derive(Debug, Clone)]
+enum Instruction {
+    Number(f64),
+    Variable(usize),
+    Unary(fn(f64) -> f64),
+    Binary(fn(f64, f64) -> f64),
+    Many(fn(&[f64]) -> f64, usize),
+    // function, that can not be lowered, is evaluated as is
+    Call(RootFormula),
+}
+
+// stacks up to this size are kept on the stack of thread instead of heap
+const INLINE_STACK: usize = 32;
+// number of rows, that are evaluated together by batch evaluation
+const BATCH_CHUNK: usize = 256;
+
+/// Formula compiled into bytecode of stack machine, which is evaluated with values of variables passed by position.
+///
+/// Built-in operators and functions are lowered into instructions, other functions are called as is.
+/// Such functions are evaluated with variable store, which contains variables of compiled formula,
+/// so they are slower than lowered ones.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::{CompiledFormula, RootFormula};
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::Variable;
+///
+/// let formula = RootFormula::parse("x * (y + 2)", &EmptyFunctionStore).unwrap();
+/// let compiled = CompiledFormula::new(&formula, &[Variable::new("x"), Variable::new("y")]).unwrap();
+/// assert_eq!(compiled.eval(&[3.0, 1.0]).unwrap(), 9.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompiledFormula {
+    instructions: Vec<Instruction>,
+    variables: Arc<[Variable]>,
+    max_depth: usize,
+}
+
+struct Compiler<'a> {
+    variables: &'a [Variable],
+    instructions: Vec<Instruction>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Compiler<'_> {
+    fn push(&mut self, instruction: Instruction, arguments: usize) {
+        self.depth = self.depth + 1 - arguments;
+        self.max_depth = self.max_depth.max(self.depth);
+        self.instructions.push(instruction);
+    }
+
+    fn compile(&mut self, formula: &RootFormula) -> Result<(), NoVariableError> {
+        if let Some(variable) = formula.as_variable() {
+            self.push(Instruction::Variable(slot(self.variables, variable)?), 0);
+            return Ok(());
+        }
+        if formula.is_const() {
+            if let Ok(value) = formula.eval(&crate::variable_stores::EmptyVariableStore) {
+                self.push(Instruction::Number(value), 0);
+                return Ok(());
+            }
+        }
+        match formula.as_function().and_then(FunctionLike::lower) {
+            Some(Lowering::Unary(function, argument)) => {
+                self.compile(argument)?;
+                self.push(Instruction::Unary(function), 1);
+            }
+            Some(Lowering::Binary(function, first, second)) => {
+                self.compile(first)?;
+                self.compile(second)?;
+                self.push(Instruction::Binary(function), 2);
+            }
+            Some(Lowering::Operation(operation, first, second)) => {
+                self.compile(first)?;
+                self.compile(second)?;
+                self.push(Instruction::Binary(operation.function()), 2);
+            }
+            Some(Lowering::Many(function, arguments)) => {
+                for argument in &arguments {
+                    self.compile(argument)?;
+                }
+                self.push(
+                    Instruction::Many(function, arguments.len()),
+                    arguments.len(),
+                );
+            }
+            Some(Lowering::Formula(inner)) => self.compile(inner)?,
+            None => self.push(Instruction::Call(formula.clone()), 0),
+        }
+        Ok(())
+    }
+}
+
+/// Returns position of `variable` in `variables`.
+pub(crate) fn slot(variables: &[Variable], variable: &Variable) -> Result<usize, NoVariableError> {
+    variables
+        .iter()
+        .position(|known| known == variable)
+        .ok_or_else(|| NoVariableError::new(variable.clone()))
+}
+
+/// Variable store with values of compiled formula variables, used to evaluate functions, which were not lowered.
+pub(crate) struct Slots(Vec<(Variable, Arc<RootFormula>)>);
+
+impl Slots {
+    pub(crate) fn new(variables: &[Variable], values: &[f64]) -> Self {
+        Self(
+            variables
+                .iter()
+                .zip(values)
+                .map(|(variable, value)| (variable.clone(), Arc::new(RootFormula::new(*value))))
+                .collect(),
+        )
+    }
+}
+
+impl GetVariable for Slots {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.0
+            .iter()
+            .find(|(variable, _)| variable == name)
+            .map(|(_, formula)| formula)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+}
+
+impl CompiledFormula {
+    /// Compiles `formula`, values of `variables` will be passed to [`CompiledFormula::eval`] in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if formula contains variable, which is not in `variables`.
+    /// Variables of functions, which can not be lowered, are checked only on evaluation.
+    pub fn new(formula: &RootFormula, variables: &[Variable]) -> Result<Self, NoVariableError> {
+        let mut compiler = Compiler {
+            variables,
+            instructions: Vec::new(),
+            depth: 0,
+            max_depth: 0,
+        };
+        compiler.compile(formula)?;
+        Ok(Self {
+            instructions: compiler.instructions,
+            variables: variables.into(),
+            max_depth: compiler.max_depth,
+        })
+    }
+
+    /// Returns variables of compiled formula in the order of their values.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    /// Evaluates compiled formula, `values` are values of variables in the same order as variables.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if there are less values than variables or function, which was not lowered, failed to evaluate.
+    pub fn eval(&self, values: &[f64]) -> Result<f64, EvaluationError> {
+        if values.len() < self.variables.len() {
+            let missing = self.variables[values.len()].clone();
+            return Err(EvaluationError::NoVariableError(NoVariableError::new(
+                missing,
+            )));
+        }
+        if self.max_depth <= INLINE_STACK {
+            self.run(values, &mut [0.0; INLINE_STACK])
+        } else {
+            self.run(values, &mut vec![0.0; self.max_depth])
+        }
+    }
+
+    fn run(&self, values: &[f64], stack: &mut [f64]) -> Result<f64, EvaluationError> {
+        let mut slots = None;
+        let mut top = 0;
+        for instruction in &self.instructions {
+            match instruction {
+                Instruction::Number(value) => {
+                    stack[top] = *value;
+                    top += 1;
+                }
+                Instruction::Variable(slot) => {
+                    stack[top] = values[*slot];
+                    top += 1;
+                }
+                Instruction::Unary(function) => stack[top - 1] = function(stack[top - 1]),
+                Instruction::Binary(function) => {
+                    top -= 1;
+                    stack[top - 1] = function(stack[top - 1], stack[top]);
+                }
+                Instruction::Many(function, arguments) => {
+                    let start = top - *arguments;
+                    stack[start] = function(&stack[start..top]);
+                    top = start + 1;
+                }
+                Instruction::Call(formula) => {
+                    let slots = slots.get_or_insert_with(|| Slots::new(&self.variables, values));
+                    stack[top] = formula.eval(slots)?;
+                    top += 1;
+                }
+            }
+        }
+        Ok(stack[0])
+    }
+
+    /// Evaluates compiled formula for every row of `columns` and writes results into `output`.
+    ///
+    /// `columns` contain values of variables in the same order as variables, row `i` consists of `i`-th
+    /// values of columns. Formula is evaluated by chunks of rows, every instruction is applied to whole chunk,
+    /// so evaluation of lowered functions is much faster than evaluation of every row separately.
+    /// Functions, which were not lowered, are evaluated row by row.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if there are less columns than variables, length of column differs from length of `output`
+    /// or function, which was not lowered, failed to evaluate.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use evaluatorrs::formulas::{CompiledFormula, RootFormula};
+    /// use evaluatorrs::function_stores::EmptyFunctionStore;
+    /// use evaluatorrs::variable_stores::Variable;
+    ///
+    /// let formula = RootFormula::parse("x * y + 1", &EmptyFunctionStore).unwrap();
+    /// let compiled = CompiledFormula::new(&formula, &[Variable::new("x"), Variable::new("y")]).unwrap();
+    /// let mut output = [0.0; 3];
+    /// compiled.eval_batch(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]], &mut output).unwrap();
+    /// assert_eq!(output, [5.0, 11.0, 19.0]);
+    /// ```
+    pub fn eval_batch(
+        &self,
+        columns: &[&[f64]],
+        output: &mut [f64],
+    ) -> Result<(), EvaluationError> {
+        if columns.len() < self.variables.len() {
+            let missing = self.variables[columns.len()].clone();
+            return Err(EvaluationError::NoVariableError(NoVariableError::new(
+                missing,
+            )));
+        }
+        if let Some((variable, column)) = self
+            .variables
+            .iter()
+            .zip(columns)
+            .find(|(_, column)| column.len() != output.len())
+        {
+            return Err(EvaluationError::MathError(MathError::new(format!(
+                "batch, column of {variable} has {} rows instead of {}",
+                column.len(),
+                output.len()
+            ))));
+        }
+        let mut stack = vec![[0.0; BATCH_CHUNK]; self.max_depth];
+        let mut start = 0;
+        for chunk in output.chunks_mut(BATCH_CHUNK) {
+            self.run_chunk(columns, start, chunk.len(), &mut stack)?;
+            chunk.copy_from_slice(&stack[0][..chunk.len()]);
+            start += chunk.len();
+        }
+        Ok(())
+    }
+
+    fn run_chunk(
+        &self,
+        columns: &[&[f64]],
+        start: usize,
+        rows: usize,
+        stack: &mut [[f64; BATCH_CHUNK]],
+    ) -> Result<(), EvaluationError> {
+        let mut top = 0;
+        for instruction in &self.instructions {
+            match instruction {
+                Instruction::Number(value) => {
+                    stack[top][..rows].fill(*value);
+                    top += 1;
+                }
+                Instruction::Variable(slot) => {
+                    stack[top][..rows].copy_from_slice(&columns[*slot][start..start + rows]);
+                    top += 1;
+                }
+                Instruction::Unary(function) => {
+                    for value in &mut stack[top - 1][..rows] {
+                        *value = function(*value);
+                    }
+                }
+                Instruction::Binary(function) => {
+                    top -= 1;
+                    let (first, second) = stack.split_at_mut(top);
+                    for (first, second) in first[top - 1][..rows].iter_mut().zip(&second[0][..rows])
+                    {
+                        *first = function(*first, *second);
+                    }
+                }
+                Instruction::Many(function, arguments) => {
+                    let first = top - *arguments;
+                    let mut values = vec![0.0; *arguments];
+                    for row in 0..rows {
+                        for (value, argument) in values.iter_mut().zip(&stack[first..top]) {
+                            *value = argument[row];
+                        }
+                        stack[first][row] = function(&values);
+                    }
+                    top = first + 1;
+                }
+                Instruction::Call(formula) => {
+                    let mut values = vec![0.0; self.variables.len()];
+                    for row in 0..rows {
+                        for (value, column) in values.iter_mut().zip(columns) {
+                            *value = column[start + row];
+                        }
+                        stack[top][row] = formula.eval(&Slots::new(&self.variables, &values))?;
+                    }
+                    top += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resets inner state of functions, which were not lowered, see [`FunctionLike::reset`].
+    pub fn reset(&self) {
+        for instruction in &self.instructions {
+            if let Instruction::Call(formula) = instruction {
+                formula.reset();
+            }
+        }
+    }
+}
+
+/// Formula with variables bound to positions, created by [`RootFormula::bind`].
+///
+/// Unlike [`CompiledFormula::new`], binding checks variables of all functions, including ones which can not
+/// be lowered, so evaluation never fails because of unknown variable.
+#[derive(Debug, Clone)]
+pub struct BoundFormula {
+    compiled: CompiledFormula,
+}
+
+impl BoundFormula {
+    /// Returns bound variables in the order of their values.
+    pub fn variables(&self) -> &[Variable] {
+        self.compiled.variables()
+    }
+
+    /// Evaluates formula, `values` are values of bound variables in the same order as variables.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if there are less values than variables or function failed to evaluate.
+    #[inline]
+    pub fn eval(&self, values: &[f64]) -> Result<f64, EvaluationError> {
+        self.compiled.eval(values)
+    }
+
+    /// Evaluates formula for every row of `columns` and writes results into `output`,
+    /// see [`CompiledFormula::eval_batch`].
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if there are less columns than variables, length of column differs from length of `output`
+    /// or function failed to evaluate.
+    #[inline]
+    pub fn eval_batch(
+        &self,
+        columns: &[&[f64]],
+        output: &mut [f64],
+    ) -> Result<(), EvaluationError> {
+        self.compiled.eval_batch(columns, output)
+    }
+
+    /// Resets inner state of functions, see [`FunctionLike::reset`].
+    pub fn reset(&self) {
+        self.compiled.reset();
+    }
+}
+
+impl RootFormula {
+    /// Binds variables of formula to positions in `variables`, values are passed to [`BoundFormula::eval`]
+    /// in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if formula depends on variable, which is not in `variables`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use evaluatorrs::formulas::RootFormula;
+    /// use evaluatorrs::function_stores::EmptyFunctionStore;
+    ///
+    /// let formula = RootFormula::parse("x * y + z", &EmptyFunctionStore).unwrap();
+    /// let bound = formula.bind(&["x", "y", "z"]).unwrap();
+    /// assert_eq!(bound.eval(&[2.0, 3.0, 1.0]).unwrap(), 7.0);
+    /// assert!(formula.bind(&["x", "y"]).is_err());
+    /// ```
+    pub fn bind<S: AsRef<str>>(&self, variables: &[S]) -> Result<BoundFormula, NoVariableError> {
+        let variables = variables
+            .iter()
+            .map(|variable| Variable::new(variable.as_ref()))
+            .collect::<Vec<_>>();
+        if let Some(unknown) = self
+            .variables()
+            .into_iter()
+            .find(|variable| !variables.contains(variable))
+        {
+            return Err(NoVariableError::new(unknown));
+        }
+        Ok(BoundFormula {
+            compiled: CompiledFormula::new(self, &variables)?,
+        })
+    }
+}
+
+#[cfg(test)]
+// compiled formula must return exactly the same values as tree
+#[allow(clippy::float_cmp)]
+mod test {
+    use crate::__lib::string::{format, String};
+    use crate::__lib::vec::{vec, Vec};
+    #[cfg(any(feature = "std", feature = "libm"))]
+    use crate::formulas::math::{Cos, Sin};
+    use crate::formulas::stateful::Prev;
+    use crate::formulas::{CompiledFormula, Evaluate, Min, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn store() -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        #[cfg(any(feature = "std", feature = "libm"))]
+        store.register::<Sin>();
+        #[cfg(any(feature = "std", feature = "libm"))]
+        store.register::<Cos>();
+        store.register::<Min>();
+        store.register::<Prev>();
+        store
+    }
+
+    fn assert_same_as_tree(expression: &str) {
+        let variables = [Variable::new("x"), Variable::new("y")];
+        let formula = RootFormula::parse(expression, &store()).unwrap();
+        let compiled = CompiledFormula::new(&formula, &variables).unwrap();
+        let mut tree_variables = VectorVariableStore::new();
+        for (x, y) in [(0.5, 2.0), (-1.0, 3.5), (4.0, -0.25)] {
+            tree_variables.set("x", x);
+            tree_variables.set("y", y);
+            let expected = formula.eval(&tree_variables).unwrap();
+            assert_eq!(compiled.eval(&[x, y]).unwrap(), expected, "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_same_as_tree() {
+        assert_same_as_tree("x + y * 2 - x / y");
+        assert_same_as_tree("min(x, y, 3) - min(y, x)");
+        assert_same_as_tree("(x + 1) * (y - 2) * 3 + 4 / 2");
+    }
+
+    #[cfg(any(feature = "std", feature = "libm"))]
+    #[test]
+    fn test_functions_same_as_tree() {
+        assert_same_as_tree("sin(x) * cos(y) + 1");
+        assert_same_as_tree("x ^ 2 + sin(1) ^ y");
+    }
+
+    #[test]
+    fn test_call() {
+        let formula = RootFormula::parse("prev(x * y) + x", &store()).unwrap();
+        let compiled =
+            CompiledFormula::new(&formula, &[Variable::new("x"), Variable::new("y")]).unwrap();
+        assert!(compiled.eval(&[1.0, 2.0]).unwrap().is_nan());
+        assert_eq!(compiled.eval(&[3.0, 4.0]).unwrap(), 5.0);
+        compiled.reset();
+        assert!(compiled.eval(&[1.0, 2.0]).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_errors() {
+        let formula = RootFormula::parse("x + z", &EmptyFunctionStore).unwrap();
+        assert!(CompiledFormula::new(&formula, &[Variable::new("x")]).is_err());
+        let variables = [Variable::new("x"), Variable::new("z")];
+        let compiled = CompiledFormula::new(&formula, &variables).unwrap();
+        assert!(compiled.eval(&[1.0]).is_err());
+        assert_eq!(compiled.eval(&[1.0, 2.0]).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_bind() {
+        let formula = RootFormula::parse("prev(x * y) + z", &store()).unwrap();
+        assert!(formula.bind(&["x", "z"]).is_err());
+        assert!(formula.bind(&["y", "z"]).is_err());
+        let bound = formula.bind(&["z", "y", "x", "w"]).unwrap();
+        assert_eq!(bound.variables()[0], Variable::new("z"));
+        assert!(bound.eval(&[1.0, 2.0, 3.0, 4.0]).unwrap().is_nan());
+        assert_eq!(bound.eval(&[1.0, 2.0, 3.0, 4.0]).unwrap(), 7.0);
+        assert!(bound.eval(&[1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let formula = RootFormula::parse("min(a, prev(b + a), 2) * c", &store()).unwrap();
+        let expected = [Variable::new("a"), Variable::new("b"), Variable::new("c")];
+        assert_eq!(formula.variables(), expected);
+    }
+
+    #[test]
+    fn test_eval_batch() {
+        let formula = RootFormula::parse("min(x, y) * 2 + x / y - prev(x)", &store()).unwrap();
+        let bound = formula.bind(&["x", "y"]).unwrap();
+        let x = (0..600).map(f64::from).collect::<Vec<_>>();
+        let y = x.iter().map(|x| 300.0 - x / 2.0).collect::<Vec<_>>();
+        let mut output = vec![0.0; x.len()];
+        bound.eval_batch(&[&x, &y], &mut output).unwrap();
+        let row_bound = formula.bind(&["x", "y"]).unwrap();
+        for ((x, y), value) in x.iter().zip(&y).zip(&output) {
+            let expected = row_bound.eval(&[*x, *y]).unwrap();
+            assert!(
+                expected == *value || (expected.is_nan() && value.is_nan()),
+                "{x}, {y}: {value} != {expected}"
+            );
+        }
+        assert!(bound.eval_batch(&[&x], &mut output).is_err());
+        assert!(bound.eval_batch(&[&x, &y[1..]], &mut output).is_err());
+        bound.eval_batch(&[&[], &[]], &mut []).unwrap();
+        let constant = RootFormula::parse("2 * 3", &store())
+            .unwrap()
+            .bind::<&str>(&[])
+            .unwrap();
+        constant.eval_batch(&[], &mut output).unwrap();
+        assert!(output.iter().all(|value| *value == 6.0));
+    }
+
+    #[test]
+    fn test_deep_formula() {
+        let expression = (0..40).fold(String::from("x"), |inner, _| format!("x + ({inner})"));
+        let formula = RootFormula::parse(&expression, &EmptyFunctionStore).unwrap();
+        let compiled = CompiledFormula::new(&formula, &[Variable::new("x")]).unwrap();
+        assert_eq!(compiled.eval(&[1.0]).unwrap(), 41.0);
+    }
+}
@@ -0,0 +1,591 @@
+// Numerical code follows notation of the formulas it implements.
+#![allow(
+    clippy::float_cmp,
+    clippy::many_single_char_names,
+    clippy::suboptimal_flops
+)]
+
+use crate::float::{abs, erfc, exp, exp_m1, floor, lgamma, ln, ln_1p, powf, sqrt};
+
+const EPSILON: f64 = 1e-15;
+const TINY: f64 = 1e-300;
+const MAX_ITERATIONS: usize = 500;
+
+#[inline]
+fn is_probability(p: f64) -> bool {
+    (0.0..=1.0).contains(&p)
+}
+
+#[inline]
+fn is_non_negative_integer(n: f64) -> bool {
+    n >= 0.0 && n.is_finite() && n % 1.0 == 0.0
+}
+
+// regularized lower incomplete gamma function P(a, x), evaluated by series for small `x`
+// and by continued fraction for large `x`.
+fn lower_regularized_gamma(a: f64, x: f64) -> f64 {
+    if x.is_nan() || a <= 0.0 || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return 0.0;
+    }
+    if x == f64::INFINITY {
+        return 1.0;
+    }
+    let prefix = exp(a * ln(x) - x - lgamma(a));
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut denominator = a;
+        for _ in 0..MAX_ITERATIONS {
+            denominator += 1.0;
+            term *= x / denominator;
+            sum += term;
+            if abs(term) < abs(sum) * EPSILON {
+                break;
+            }
+        }
+        return sum * prefix;
+    }
+    // modified Lentz's method
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..MAX_ITERATIONS {
+        #[allow(clippy::cast_precision_loss)]
+        let i = i as f64;
+        let an = -i * (i - a);
+        b += 2.0;
+        d = an * d + b;
+        if abs(d) < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if abs(c) < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if abs(delta - 1.0) < EPSILON {
+            break;
+        }
+    }
+    1.0 - prefix * h
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if abs(d) < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..MAX_ITERATIONS {
+        #[allow(clippy::cast_precision_loss)]
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if abs(d) < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if abs(c) < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if abs(d) < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if abs(c) < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if abs(delta - 1.0) < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+// regularized incomplete beta function I_x(a, b).
+fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x.is_nan() || a <= 0.0 || b <= 0.0 {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let prefix = exp(lgamma(a + b) - lgamma(a) - lgamma(b) + a * ln(x) + b * ln_1p(-x));
+    if x < (a + 1.0) / (a + b + 2.0) {
+        prefix * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - prefix * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// finds `x` in which monotonically increasing `cdf` equals `p` by bisection,
+// bracket is expanded until it contains the answer.
+fn invert_continuous(p: f64, mut low: f64, mut high: f64, cdf: impl Fn(f64) -> f64) -> f64 {
+    for _ in 0..MAX_ITERATIONS {
+        if cdf(high) >= p {
+            break;
+        }
+        low = high;
+        high *= 2.0;
+    }
+    for _ in 0..MAX_ITERATIONS {
+        if cdf(low) <= p {
+            break;
+        }
+        high = low;
+        low *= 2.0;
+    }
+    for _ in 0..MAX_ITERATIONS {
+        let middle = low + (high - low) / 2.0;
+        if middle <= low || middle >= high {
+            break;
+        }
+        if cdf(middle) < p {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low + (high - low) / 2.0
+}
+
+// finds smallest non-negative integer `k` for which `cdf(k) >= p`.
+fn invert_discrete(p: f64, mut high: f64, cdf: impl Fn(f64) -> f64) -> f64 {
+    if cdf(0.0) >= p {
+        return 0.0;
+    }
+    let mut low = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        if cdf(high) >= p {
+            break;
+        }
+        low = high;
+        high *= 2.0;
+    }
+    for _ in 0..MAX_ITERATIONS {
+        if high - low <= 1.0 {
+            break;
+        }
+        let middle = floor(low + (high - low) / 2.0);
+        if cdf(middle) >= p {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    high
+}
+
+fn standard_normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / core::f64::consts::SQRT_2)
+}
+
+// Acklam's rational approximation refined with one step of Halley's method.
+fn standard_normal_inverse(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    if !is_probability(p) {
+        return f64::NAN;
+    }
+    if p == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    let x = if p < P_LOW {
+        tail(sqrt(-2.0 * ln(p)))
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -tail(sqrt(-2.0 * ln_1p(-p)))
+    };
+    let error = standard_normal_cdf(x) - p;
+    let u = error * sqrt(2.0 * core::f64::consts::PI) * exp(x * x / 2.0);
+    x - u / (1.0 + x * u / 2.0)
+}
+
+fn norm_pdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma <= 0.0 {
+        return f64::NAN;
+    }
+    let z = (x - mu) / sigma;
+    exp(-z * z / 2.0) / (sigma * sqrt(2.0 * core::f64::consts::PI))
+}
+
+fn norm_cdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma <= 0.0 {
+        return f64::NAN;
+    }
+    standard_normal_cdf((x - mu) / sigma)
+}
+
+fn norm_inv(p: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma <= 0.0 {
+        return f64::NAN;
+    }
+    mu + sigma * standard_normal_inverse(p)
+}
+
+fn lognorm_pdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma <= 0.0 {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 0.0;
+    }
+    norm_pdf(ln(x), mu, sigma) / x
+}
+
+fn lognorm_cdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma <= 0.0 {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 0.0;
+    }
+    norm_cdf(ln(x), mu, sigma)
+}
+
+fn lognorm_inv(p: f64, mu: f64, sigma: f64) -> f64 {
+    exp(norm_inv(p, mu, sigma))
+}
+
+fn expon_pdf(x: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 {
+        return f64::NAN;
+    }
+    if x < 0.0 {
+        return 0.0;
+    }
+    lambda * exp(-lambda * x)
+}
+
+fn expon_cdf(x: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 {
+        return f64::NAN;
+    }
+    if x < 0.0 {
+        return 0.0;
+    }
+    -exp_m1(-lambda * x)
+}
+
+fn expon_inv(p: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 || !is_probability(p) {
+        return f64::NAN;
+    }
+    -ln_1p(-p) / lambda
+}
+
+fn uniform_pdf(x: f64, a: f64, b: f64) -> f64 {
+    if a >= b {
+        return f64::NAN;
+    }
+    if x < a || x > b {
+        return 0.0;
+    }
+    1.0 / (b - a)
+}
+
+fn uniform_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if a >= b {
+        return f64::NAN;
+    }
+    ((x - a) / (b - a)).clamp(0.0, 1.0)
+}
+
+fn uniform_inv(p: f64, a: f64, b: f64) -> f64 {
+    if a >= b || !is_probability(p) {
+        return f64::NAN;
+    }
+    a + p * (b - a)
+}
+
+fn t_pdf(x: f64, nu: f64) -> f64 {
+    if nu <= 0.0 {
+        return f64::NAN;
+    }
+    exp(lgamma((nu + 1.0) / 2.0) - lgamma(nu / 2.0)) / sqrt(nu * core::f64::consts::PI)
+        * powf(1.0 + x * x / nu, -(nu + 1.0) / 2.0)
+}
+
+fn t_cdf(x: f64, nu: f64) -> f64 {
+    if nu <= 0.0 || x.is_nan() {
+        return f64::NAN;
+    }
+    let tail = regularized_beta(nu / 2.0, 0.5, nu / (nu + x * x)) / 2.0;
+    if x > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+fn t_inv(p: f64, nu: f64) -> f64 {
+    if nu <= 0.0 || !is_probability(p) {
+        return f64::NAN;
+    }
+    if p == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    invert_continuous(p, -1.0, 1.0, |x| t_cdf(x, nu))
+}
+
+fn chi2_pdf(x: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return f64::NAN;
+    }
+    if x < 0.0 {
+        return 0.0;
+    }
+    if x == 0.0 {
+        return match k {
+            k if k < 2.0 => f64::INFINITY,
+            k if k > 2.0 => 0.0,
+            _ => 0.5,
+        };
+    }
+    let half_k = k / 2.0;
+    exp((half_k - 1.0) * ln(x) - x / 2.0 - half_k * core::f64::consts::LN_2 - lgamma(half_k))
+}
+
+fn chi2_cdf(x: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 0.0;
+    }
+    lower_regularized_gamma(k / 2.0, x / 2.0)
+}
+
+fn chi2_inv(p: f64, k: f64) -> f64 {
+    if k <= 0.0 || !is_probability(p) {
+        return f64::NAN;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    invert_continuous(p, 0.0, k, |x| chi2_cdf(x, k))
+}
+
+fn binom_pmf(k: f64, n: f64, p: f64) -> f64 {
+    if !is_non_negative_integer(n) || !is_probability(p) {
+        return f64::NAN;
+    }
+    if !is_non_negative_integer(k) || k > n {
+        return 0.0;
+    }
+    if p == 0.0 || p == 1.0 {
+        let certain = if p == 0.0 { 0.0 } else { n };
+        return if k == certain { 1.0 } else { 0.0 };
+    }
+    exp(lgamma(n + 1.0) - lgamma(k + 1.0) - lgamma(n - k + 1.0) + k * ln(p) + (n - k) * ln_1p(-p))
+}
+
+fn binom_cdf(k: f64, n: f64, p: f64) -> f64 {
+    if !is_non_negative_integer(n) || !is_probability(p) || k.is_nan() {
+        return f64::NAN;
+    }
+    if k < 0.0 {
+        return 0.0;
+    }
+    let k = floor(k);
+    if k >= n {
+        return 1.0;
+    }
+    if p == 0.0 {
+        return 1.0;
+    }
+    if p == 1.0 {
+        return 0.0;
+    }
+    regularized_beta(n - k, k + 1.0, 1.0 - p)
+}
+
+fn binom_inv(q: f64, n: f64, p: f64) -> f64 {
+    if !is_non_negative_integer(n) || !is_probability(p) || !is_probability(q) {
+        return f64::NAN;
+    }
+    invert_discrete(q, n, |k| binom_cdf(k, n, p))
+}
+
+fn poisson_pmf(k: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 {
+        return f64::NAN;
+    }
+    if !is_non_negative_integer(k) {
+        return 0.0;
+    }
+    exp(k * ln(lambda) - lambda - lgamma(k + 1.0))
+}
+
+fn poisson_cdf(k: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 || k.is_nan() {
+        return f64::NAN;
+    }
+    if k < 0.0 {
+        return 0.0;
+    }
+    1.0 - lower_regularized_gamma(floor(k) + 1.0, lambda)
+}
+
+fn poisson_inv(q: f64, lambda: f64) -> f64 {
+    if lambda <= 0.0 || !is_probability(q) {
+        return f64::NAN;
+    }
+    if q == 1.0 {
+        return f64::INFINITY;
+    }
+    invert_discrete(q, floor(lambda) + 1.0, |k| poisson_cdf(k, lambda))
+}
+
+macro_rules! impl_distribution_functions {
+    ($($function:ident, ($($argument:ident),+), $struct_name:ident, $doc:expr);+ $(;)?) => {
+        $(impl_multi_arg_function!(
+            stringify!($function), ($($argument),+), ($function),
+            #[doc = $doc]
+            pub $struct_name
+        );)+
+    };
+}
+
+impl_distribution_functions!(
+    norm_pdf, (x, mu, sigma), NormPdf, "Probability density function of normal distribution, called as `norm_pdf(x, mu, sigma)`.";
+    norm_cdf, (x, mu, sigma), NormCdf, "Cumulative distribution function of normal distribution, called as `norm_cdf(x, mu, sigma)`.";
+    norm_inv, (p, mu, sigma), NormInv, "Inverse cumulative distribution function of normal distribution, called as `norm_inv(p, mu, sigma)`.";
+    lognorm_pdf, (x, mu, sigma), LognormPdf, "Probability density function of log-normal distribution, called as `lognorm_pdf(x, mu, sigma)`.";
+    lognorm_cdf, (x, mu, sigma), LognormCdf, "Cumulative distribution function of log-normal distribution, called as `lognorm_cdf(x, mu, sigma)`.";
+    lognorm_inv, (p, mu, sigma), LognormInv, "Inverse cumulative distribution function of log-normal distribution, called as `lognorm_inv(p, mu, sigma)`.";
+    expon_pdf, (x, lambda), ExponPdf, "Probability density function of exponential distribution, called as `expon_pdf(x, lambda)`.";
+    expon_cdf, (x, lambda), ExponCdf, "Cumulative distribution function of exponential distribution, called as `expon_cdf(x, lambda)`.";
+    expon_inv, (p, lambda), ExponInv, "Inverse cumulative distribution function of exponential distribution, called as `expon_inv(p, lambda)`.";
+    uniform_pdf, (x, a, b), UniformPdf, "Probability density function of uniform distribution on `[a, b]`, called as `uniform_pdf(x, a, b)`.";
+    uniform_cdf, (x, a, b), UniformCdf, "Cumulative distribution function of uniform distribution on `[a, b]`, called as `uniform_cdf(x, a, b)`.";
+    uniform_inv, (p, a, b), UniformInv, "Inverse cumulative distribution function of uniform distribution on `[a, b]`, called as `uniform_inv(p, a, b)`.";
+    t_pdf, (x, nu), TPdf, "Probability density function of Student's t-distribution, called as `t_pdf(x, nu)`.";
+    t_cdf, (x, nu), TCdf, "Cumulative distribution function of Student's t-distribution, called as `t_cdf(x, nu)`.";
+    t_inv, (p, nu), TInv, "Inverse cumulative distribution function of Student's t-distribution, called as `t_inv(p, nu)`.";
+    chi2_pdf, (x, k), Chi2Pdf, "Probability density function of chi-squared distribution, called as `chi2_pdf(x, k)`.";
+    chi2_cdf, (x, k), Chi2Cdf, "Cumulative distribution function of chi-squared distribution, called as `chi2_cdf(x, k)`.";
+    chi2_inv, (p, k), Chi2Inv, "Inverse cumulative distribution function of chi-squared distribution, called as `chi2_inv(p, k)`.";
+    binom_pmf, (k, n, p), BinomPmf, "Probability mass function of binomial distribution, called as `binom_pmf(k, n, p)`.";
+    binom_cdf, (k, n, p), BinomCdf, "Cumulative distribution function of binomial distribution, called as `binom_cdf(k, n, p)`.";
+    binom_inv, (q, n, p), BinomInv, "Smallest `k` for which `binom_cdf(k, n, p) >= q`, called as `binom_inv(q, n, p)`.";
+    poisson_pmf, (k, lambda), PoissonPmf, "Probability mass function of Poisson distribution, called as `poisson_pmf(k, lambda)`.";
+    poisson_cdf, (k, lambda), PoissonCdf, "Cumulative distribution function of Poisson distribution, called as `poisson_cdf(k, lambda)`.";
+    poisson_inv, (q, lambda), PoissonInv, "Smallest `k` for which `poisson_cdf(k, lambda) >= q`, called as `poisson_inv(q, lambda)`.";
+);
+
+#[cfg(test)]
+mod test {
+    use super::{
+        binom_cdf, binom_inv, binom_pmf, chi2_cdf, chi2_inv, norm_cdf, norm_inv, poisson_cdf,
+        poisson_inv, t_cdf, t_inv,
+    };
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_normal() {
+        assert_close(norm_cdf(1.96, 0.0, 1.0), 0.975_002_104_851_780);
+        assert_close(norm_cdf(12.0, 10.0, 2.0), 0.841_344_746_068_543);
+        assert_close(norm_inv(0.975, 0.0, 1.0), 1.959_963_984_540_054);
+        assert_close(norm_inv(1e-10, 0.0, 1.0), -6.361_340_902_404_056);
+        assert!(norm_cdf(0.0, 0.0, -1.0).is_nan());
+    }
+
+    #[test]
+    fn test_student() {
+        assert_close(t_cdf(2.0, 5.0), 0.949_030_260_585_070_2);
+        assert_close(t_cdf(-2.0, 5.0), 0.050_969_739_414_929_8);
+        assert_close(t_inv(0.975, 10.0), 2.228_138_851_964_938);
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        assert_close(chi2_cdf(3.0, 4.0), 0.442_174_599_628_925_6);
+        assert_close(chi2_inv(0.95, 3.0), 7.814_727_903_251_178);
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert_close(binom_pmf(3.0, 10.0, 0.5), 0.117_187_5);
+        assert_close(binom_cdf(3.0, 10.0, 0.5), 0.171_875);
+        assert_close(binom_inv(0.5, 10.0, 0.5), 5.0);
+        assert!(binom_pmf(3.0, 10.5, 0.5).is_nan());
+    }
+
+    #[test]
+    fn test_poisson() {
+        assert_close(poisson_cdf(2.0, 3.0), 0.423_190_081_126_844_6);
+        assert_close(poisson_inv(0.5, 3.0), 3.0);
+    }
+}
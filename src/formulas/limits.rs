@@ -0,0 +1,191 @@
+use crate::formulas::{Limit, LimitError};
+use crate::tokens::Operator;
+
+/// Limits of resources used by parsing and evaluation of untrusted expressions.
+///
+/// Parser checks expression length, nesting depth and number of nodes, when limits are set
+/// on function store with [`SetLimits`](crate::function_stores::SetLimits).
+/// Evaluation checks number of steps and recursion depth through variables,
+/// when formula is evaluated with [`LimitedVariableStore`](crate::variable_stores::LimitedVariableStore).
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::{Evaluate, EvaluationError, Limit, Limits, ParserError, RootFormula};
+/// use evaluatorrs::function_stores::{SetLimits, VectorFunctionStore};
+/// use evaluatorrs::variable_stores::{LimitedVariableStore, SetVariable, VectorVariableStore};
+///
+/// let limits = Limits::new().with_max_depth(3).with_max_steps(5);
+/// let mut functions = VectorFunctionStore::new();
+/// functions.set_limits(limits);
+/// let result = RootFormula::parse("((((1))))", &functions);
+/// assert!(matches!(result, Err(ParserError::LimitError(e)) if e.limit() == Limit::Depth));
+/// let formula = RootFormula::parse("x + x + x + x + x + x", &functions).unwrap();
+/// let mut store = LimitedVariableStore::new(VectorVariableStore::new(), limits);
+/// store.set("x", 1.0);
+/// let result = formula.eval(&store);
+/// assert!(matches!(result, Err(EvaluationError::LimitError(e)) if e.limit() == Limit::Steps));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    length: usize,
+    depth: usize,
+    nodes: usize,
+    steps: usize,
+    recursion: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            length: 4096,
+            depth: 32,
+            nodes: 1024,
+            steps: 100_000,
+            recursion: 32,
+        }
+    }
+}
+
+impl Limits {
+    /// Creates default limits: expression of 4096 bytes, nesting depth 32, 1024 nodes,
+    /// 100000 evaluation steps and recursion depth 32.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates limits, which allow anything.
+    pub const fn unlimited() -> Self {
+        Self {
+            length: usize::MAX,
+            depth: usize::MAX,
+            nodes: usize::MAX,
+            steps: usize::MAX,
+            recursion: usize::MAX,
+        }
+    }
+
+    /// Sets maximum length of expression in bytes.
+    #[must_use]
+    pub const fn with_max_length(mut self, max_length: usize) -> Self {
+        self.length = max_length;
+        self
+    }
+
+    /// Sets maximum nesting depth of parenthesis, function arguments are nested in parenthesis too.
+    #[must_use]
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.depth = max_depth;
+        self
+    }
+
+    /// Sets maximum number of numbers, variables, functions and operators in expression.
+    #[must_use]
+    pub const fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.nodes = max_nodes;
+        self
+    }
+
+    /// Sets maximum number of steps, every evaluated variable and every evaluation of expression by function,
+    /// like a term of series, is one step.
+    #[must_use]
+    pub const fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.steps = max_steps;
+        self
+    }
+
+    /// Sets maximum number of variables, which are evaluated one inside of another.
+    #[must_use]
+    pub const fn with_max_recursion(mut self, max_recursion: usize) -> Self {
+        self.recursion = max_recursion;
+        self
+    }
+
+    // Checks expression before it is parsed, so parser never recurses deeper than allowed.
+    pub(crate) fn check_expression(&self, expression: &str) -> Result<(), LimitError> {
+        if expression.len() > self.length {
+            return Err(LimitError::new(Limit::Length, self.length));
+        }
+        let mut depth: usize = 0;
+        let mut nodes: usize = 0;
+        let mut in_name = false;
+        for elem in expression.chars() {
+            let name_char = elem.is_alphanumeric() || elem == '.' || elem == '_';
+            match elem {
+                '(' => {
+                    depth += 1;
+                    if depth > self.depth {
+                        return Err(LimitError::new(Limit::Depth, self.depth));
+                    }
+                }
+                ')' => depth = depth.saturating_sub(1),
+                // numbers, variables and names of functions are counted once
+                _ if name_char && !in_name => nodes += 1,
+                _ if Operator::parse(elem).is_some() => nodes += 1,
+                _ => {}
+            }
+            in_name = name_char;
+            if nodes > self.nodes {
+                return Err(LimitError::new(Limit::Nodes, self.nodes));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) const fn check_steps(&self, steps: usize) -> Result<(), LimitError> {
+        if steps > self.steps {
+            return Err(LimitError::new(Limit::Steps, self.steps));
+        }
+        Ok(())
+    }
+
+    pub(crate) const fn check_recursion(&self, depth: usize) -> Result<(), LimitError> {
+        if depth > self.recursion {
+            return Err(LimitError::new(Limit::Recursion, self.recursion));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::series::Sum;
+    use crate::formulas::{Evaluate, EvaluationError, Limit, Limits, ParserError, RootFormula};
+    use crate::function_stores::{RegisterParser, SetLimits, VectorFunctionStore};
+    use crate::variable_stores::{LimitedVariableStore, SetVariable, VectorVariableStore};
+
+    #[test]
+    fn test_check_expression() {
+        let limits = Limits::new()
+            .with_max_length(20)
+            .with_max_depth(2)
+            .with_max_nodes(5);
+        assert!(limits.check_expression("max(x1, (2.5))").is_ok());
+        let limit = |expression| limits.check_expression(expression).unwrap_err().limit();
+        assert_eq!(limit("1 + 1 + 1 + 1 + 1 + 1"), Limit::Length);
+        assert_eq!(limit("f(g(h(1)))"), Limit::Depth);
+        assert_eq!(limit("a*b*c*d"), Limit::Nodes);
+        assert!(Limits::unlimited().check_expression("((((1))))").is_ok());
+    }
+
+    #[test]
+    fn test_parse() {
+        let limits = Limits::new().with_max_depth(4).with_max_steps(1000);
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<Sum>();
+        functions.set_limits(limits);
+        let limit = |expression| match RootFormula::parse(expression, &functions) {
+            Err(ParserError::LimitError(e)) => e.limit(),
+            other => panic!("expected exceeded limit, got {other:?}"),
+        };
+        assert_eq!(limit("sum(i, 1, 2, ((((i)))))"), Limit::Depth);
+        let formula = RootFormula::parse("sum(i, 1, 10, i)", &functions).unwrap();
+        assert!((formula.eval(&VectorVariableStore::new()).unwrap() - 55.0).abs() < f64::EPSILON);
+        let formula = RootFormula::parse("sum(i, 1, n, i)", &functions).unwrap();
+        let mut store = LimitedVariableStore::new(VectorVariableStore::new(), limits);
+        store.set("n", 100_000.0);
+        assert!(matches!(
+            formula.eval(&store),
+            Err(EvaluationError::LimitError(e)) if e.limit() == Limit::Steps
+        ));
+    }
+}
@@ -1,6 +1,96 @@
 #[cfg(doc)]
 use crate::formulas::{Evaluate, Function, FunctionLike, IsConst};
 
+/// Implements methods of [`FunctionLike`], which only pass the call to every argument of function.
+///
+/// Arguments are listed by name of fields in parenthesis, or name of field with slice of arguments is given
+/// in square brackets. Fields listed after `reset` are reset together with arguments.
+/// Methods `clone_into_box` and `lower` are left to be implemented by caller.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! delegate_function_like {
+    (($($argument:ident),* $(,)?) $(, reset($($state:ident),+))?) => {
+        fn collapse_inner(&mut self) -> Result<(), $crate::formulas::MathError> {
+            $(self.$argument.collapse_inner()?;)*
+            Ok(())
+        }
+
+        #[allow(unused_variables)]
+        fn set_all_variables_shared(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
+            $(self.$argument.set_all_variables_shared(args);)*
+        }
+
+        #[allow(unused_variables)]
+        fn set_all_variables_owned(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
+            $(self.$argument.set_all_variables_owned(args);)*
+        }
+
+        #[allow(unused_variables)]
+        fn set_variable_shared(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::__lib::sync::Arc<$crate::formulas::RootFormula>) {
+            $(self.$argument.set_variable_shared(name, function);)*
+        }
+
+        #[allow(unused_variables)]
+        fn set_variable_owned(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::formulas::RootFormula) {
+            $(self.$argument.set_variable_owned(name, function);)*
+        }
+
+        fn reset(&self) {
+            $(self.$argument.reset();)*
+            $($(self.$state.reset();)+)?
+        }
+
+        #[allow(unused_variables)]
+        fn collect_variables(&self, variables: &mut $crate::__lib::vec::Vec<$crate::variable_stores::Variable>) {
+            $(self.$argument.collect_variables(variables);)*
+        }
+    };
+    ([$arguments:ident]) => {
+        fn collapse_inner(&mut self) -> Result<(), $crate::formulas::MathError> {
+            for argument in self.$arguments.iter_mut() {
+                argument.collapse_inner()?;
+            }
+            Ok(())
+        }
+
+        fn set_all_variables_shared(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
+            for argument in self.$arguments.iter_mut() {
+                argument.set_all_variables_shared(args);
+            }
+        }
+
+        fn set_all_variables_owned(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
+            for argument in self.$arguments.iter_mut() {
+                argument.set_all_variables_owned(args);
+            }
+        }
+
+        fn set_variable_shared(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::__lib::sync::Arc<$crate::formulas::RootFormula>) {
+            for argument in self.$arguments.iter_mut() {
+                argument.set_variable_shared(name, function);
+            }
+        }
+
+        fn set_variable_owned(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::formulas::RootFormula) {
+            for argument in self.$arguments.iter_mut() {
+                argument.set_variable_owned(name, function);
+            }
+        }
+
+        fn reset(&self) {
+            for argument in self.$arguments.iter() {
+                argument.reset();
+            }
+        }
+
+        fn collect_variables(&self, variables: &mut $crate::__lib::vec::Vec<$crate::variable_stores::Variable>) {
+            for argument in self.$arguments.iter() {
+                argument.collect_variables(variables);
+            }
+        }
+    };
+}
+
 /// Macros for creating function with only one argument. And implementing [`IsConst`], [`Evaluate`], [`FunctionLike`], [`Function`].
 ///
 /// ## Examples
@@ -25,32 +115,44 @@ use crate::formulas::{Evaluate, Function, FunctionLike, IsConst};
 ///     pub Sin
 /// );
 /// ```
+///
+/// Derivative of function can be passed after name of struct, then function can be differentiated automatically
+/// by [`FunctionLike::eval_derivative`]
+/// ```rust
+/// use evaluatorrs::formulas::macros::impl_one_arg_function;
+///
+/// impl_one_arg_function!(
+///     "square", (|x: f64| x * x),
+///     /// Square function.
+///     pub Square, derivative (|x: f64| 2.0 * x)
+/// );
+/// ```
 #[macro_export(local_inner_macros)]
 macro_rules! impl_one_arg_function {
     (
         $parser_name:expr, $function_std:tt, $function_libm:tt,
         $(#[$meta: meta])*
-        $vis:vis $StructName:ident
+        $vis:vis $StructName:ident $(, derivative $derivative:tt)?
     ) => {
         #[cfg(feature = "std")]
         impl_one_arg_function!(
             $parser_name,
             $function_std,
             $(#[$meta])*
-            $vis $StructName
+            $vis $StructName $(, derivative $derivative)?
         );
         #[cfg(all(not(feature = "std"), feature = "libm"))]
         impl_one_arg_function!(
             $parser_name,
             $function_libm,
             $(#[$meta])*
-            $vis $StructName
+            $vis $StructName $(, derivative $derivative)?
         );
     };
     (
         $parser_name:expr, $function:tt,
         $(#[$meta: meta])*
-        $vis:vis $StructName:ident
+        $vis:vis $StructName:ident $(, derivative $derivative:tt)?
     ) => {
         $(#[$meta])*
         #[derive(Debug)]
@@ -72,45 +174,149 @@ macro_rules! impl_one_arg_function {
         }
 
         impl $crate::formulas::FunctionLike for $StructName {
-            #[inline]
-            fn collapse_inner(&mut self) -> Result<(), $crate::formulas::MathError> {
-                self.argument.collapse_inner()
+            $crate::delegate_function_like!((argument));
+
+            fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
+                $crate::__lib::boxed::Box::new(Self {
+                    argument: self.argument.clone(),
+                })
             }
 
-            #[allow(clippy::semicolon_if_nothing_returned)]
-            #[inline]
-            fn set_all_variables_shared(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
-                self.argument.set_all_variables_shared(args)
+            $(
+            fn eval_derivative(
+                &self,
+                args: &dyn $crate::variable_stores::GetVariable,
+                variable: &$crate::variable_stores::Variable,
+            ) -> Result<(f64, f64), $crate::formulas::EvaluationError> {
+                let (value, derivative) = self.argument.eval_derivative(args, variable)?;
+                // derivative of function may be infinite, where argument does not change
+                let derivative = if derivative == 0.0 { 0.0 } else { $derivative(value) * derivative };
+                Ok(($function(value), derivative))
             }
+            )?
 
-            #[allow(clippy::semicolon_if_nothing_returned)]
-            #[inline]
-            fn set_all_variables_owned(&mut self, args: &dyn $crate::variable_stores::GetVariable) {
-                self.argument.set_all_variables_owned(args)
+            fn lower(&self) -> Option<$crate::formulas::Lowering<'_>> {
+                Some($crate::formulas::Lowering::Unary(|x| $function(x), &self.argument))
             }
+        }
 
-            #[allow(clippy::semicolon_if_nothing_returned)]
-            #[inline]
-            fn set_variable_shared(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::__lib::sync::Arc<$crate::formulas::RootFormula>) {
-                self.argument.set_variable_shared(name, function)
+        impl $crate::formulas::Function for $StructName {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = 1;
+            const MAX_NUMBER_OF_ARGUMENTS: usize = 1;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> $crate::function_stores::GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, $crate::formulas::ParserError>
+            where
+                Self: Sized,
+            {
+                Ok(Self {
+                    argument: $crate::formulas::RootFormula::parse(arguments[0], formulas)?,
+                })
             }
+        }
+    };
+}
+
+pub use impl_one_arg_function;
+
+/// Macros for creating function with fixed number of arguments. And implementing [`IsConst`], [`Evaluate`], [`FunctionLike`], [`Function`].
+///
+/// Arguments are listed by name in parenthesis, they become fields of created struct and are passed to function in the same order.
+///
+/// ## Examples
+/// Function for calculating length of hypotenuse.
+/// ```rust
+/// use evaluatorrs::formulas::macros::impl_multi_arg_function;
+///
+/// fn hypot(a: f64, b: f64) -> f64 {
+///     (a * a + b * b).sqrt()
+/// }
+///
+/// impl_multi_arg_function!(
+///     "hypot", (a, b), (hypot),
+///     /// Length of hypotenuse.
+///     pub Hypot
+/// );
+/// ```
+///
+/// Like [`impl_one_arg_function`], two variants of function can be passed for std and libm features.
+#[macro_export(local_inner_macros)]
+macro_rules! impl_multi_arg_function {
+    (
+        $parser_name:expr, ($($argument:ident),+ $(,)?), $function_std:tt, $function_libm:tt,
+        $(#[$meta: meta])*
+        $vis:vis $StructName:ident
+    ) => {
+        #[cfg(feature = "std")]
+        impl_multi_arg_function!(
+            $parser_name,
+            ($($argument),+),
+            $function_std,
+            $(#[$meta])*
+            $vis $StructName
+        );
+        #[cfg(all(not(feature = "std"), feature = "libm"))]
+        impl_multi_arg_function!(
+            $parser_name,
+            ($($argument),+),
+            $function_libm,
+            $(#[$meta])*
+            $vis $StructName
+        );
+    };
+    (
+        $parser_name:expr, ($($argument:ident),+ $(,)?), $function:tt,
+        $(#[$meta: meta])*
+        $vis:vis $StructName:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        $vis struct $StructName {
+            $($argument: $crate::formulas::RootFormula,)+
+        }
 
-            #[allow(clippy::semicolon_if_nothing_returned)]
+        impl $crate::formulas::IsConst for $StructName {
             #[inline]
-            fn set_variable_owned(&mut self, name: &$crate::variable_stores::Variable, function: &$crate::formulas::RootFormula) {
-                self.argument.set_variable_owned(name, function)
+            fn is_const(&self) -> bool {
+                $(self.$argument.is_const())&&+
+            }
+        }
+
+        impl $crate::formulas::Evaluate for $StructName {
+            fn eval(&self, args: &dyn $crate::variable_stores::GetVariable) -> Result<f64, $crate::formulas::EvaluationError> {
+                Ok($function($(self.$argument.eval(args)?),+))
             }
+        }
+
+        impl $crate::formulas::FunctionLike for $StructName {
+            $crate::delegate_function_like!(($($argument),+));
 
             fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
                 $crate::__lib::boxed::Box::new(Self {
-                    argument: self.argument.clone(),
+                    $($argument: self.$argument.clone(),)+
                 })
             }
+
+            fn lower(&self) -> Option<$crate::formulas::Lowering<'_>> {
+                Some($crate::formulas::Lowering::Many(
+                    |values| {
+                        let mut values = values.iter().copied();
+                        $function($({
+                            let _ = ::core::stringify!($argument);
+                            values.next().unwrap_or(f64::NAN)
+                        }),+)
+                    },
+                    $crate::__lib::vec::vec![$(&self.$argument),+],
+                ))
+            }
         }
 
         impl $crate::formulas::Function for $StructName {
-            const MIN_NUMBER_OF_ARGUMENTS: usize = 1;
-            const MAX_NUMBER_OF_ARGUMENTS: usize = 1;
+            const MIN_NUMBER_OF_ARGUMENTS: usize = [$(::core::stringify!($argument)),+].len();
+            const MAX_NUMBER_OF_ARGUMENTS: usize = Self::MIN_NUMBER_OF_ARGUMENTS;
             const NAME: &'static str = $parser_name;
 
             fn parse<T: for<'a> $crate::function_stores::GetFunction<'a>>(
@@ -120,26 +326,30 @@ macro_rules! impl_one_arg_function {
             where
                 Self: Sized,
             {
+                let mut arguments = arguments.iter();
                 Ok(Self {
-                    argument: $crate::formulas::RootFormula::parse(arguments[0], formulas)?,
+                    $($argument: $crate::formulas::RootFormula::parse(
+                        arguments.next().copied().unwrap_or_default(),
+                        formulas,
+                    )?,)+
                 })
             }
         }
     };
 }
 
-pub use impl_one_arg_function;
+pub use impl_multi_arg_function;
 
 macro_rules! impl_many_one_arg_functions {
-    ($func_name:tt, $struct_name:tt) => {
+    ($func_name:tt, $struct_name:tt, $derivative:tt) => {
         impl_one_arg_function!(
             stringify!($func_name), (f64::$func_name), (libm::Libm::<f64>::$func_name),
             #[doc = concat!(stringify!($struct_name), " function.")]
-            pub $struct_name
+            pub $struct_name, derivative $derivative
         );
     };
-    ($($func_name:tt, $struct_name:tt);+ $(;)?) => {
-        $(impl_many_one_arg_functions!($func_name, $struct_name);)*
+    ($($func_name:tt, $struct_name:tt, $derivative:tt);+ $(;)?) => {
+        $(impl_many_one_arg_functions!($func_name, $struct_name, $derivative);)*
     };
 }
 
@@ -151,10 +361,12 @@ macro_rules! impl_operation_for_formula {
             fn $method(self, rhs: T) -> Self::Output {
                 let formula =
                     $crate::formulas::operator::OperatorFormula::new(self, rhs.into(), $operator);
-                formula.eval(&EmptyVariableStore).map_or_else(
-                    |_| Self::new(Box::new(formula) as Box<dyn FunctionLike>),
-                    Self::new,
-                )
+                if formula.is_const() {
+                    if let Ok(value) = formula.eval(&EmptyVariableStore) {
+                        return Self::new(value);
+                    }
+                }
+                Self::new(Box::new(formula) as Box<dyn FunctionLike>)
             }
         }
     };
@@ -1,32 +1,205 @@
+// Derivatives are written without `mul_add`, which is not available without std.
+#![allow(clippy::suboptimal_flops)]
+
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::float::{cos, cosh, exp, powf, sin, sinh, sqrt};
+
 impl_many_one_arg_functions!(
-    acos, Acos;
-    acosh, Acosh;
-    asin, Asin;
-    asinh, Asinh;
-    atan, Atan;
-    atanh, Atanh;
-    cbrt, Cbrt;
-    ceil, Ceil;
-    cos, Cos;
-    cosh, Cosh;
-    exp, Exp;
-    floor, Floor;
-    sin, Sin;
-    sinh, Sinh;
-    sqrt, Sqrt;
-    tan, Tan;
-    tanh, Tanh;
+    acos, Acos, (|x: f64| -1.0 / sqrt(1.0 - x * x));
+    acosh, Acosh, (|x: f64| 1.0 / sqrt(x * x - 1.0));
+    asin, Asin, (|x: f64| 1.0 / sqrt(1.0 - x * x));
+    asinh, Asinh, (|x: f64| 1.0 / sqrt(x * x + 1.0));
+    atan, Atan, (|x: f64| 1.0 / (1.0 + x * x));
+    atanh, Atanh, (|x: f64| 1.0 / (1.0 - x * x));
+    cbrt, Cbrt, (|x: f64| 1.0 / (3.0 * powf(x * x, 1.0 / 3.0)));
+    ceil, Ceil, (|_| 0.0);
+    cos, Cos, (|x: f64| -sin(x));
+    cosh, Cosh, (sinh);
+    exp, Exp, (exp);
+    floor, Floor, (|_| 0.0);
+    sin, Sin, (cos);
+    sinh, Sinh, (cosh);
+    sqrt, Sqrt, (|x: f64| 0.5 / sqrt(x));
+    tan, Tan, (|x: f64| 1.0 / (cos(x) * cos(x)));
+    tanh, Tanh, (|x: f64| 1.0 / (cosh(x) * cosh(x)));
 );
 
 impl_one_arg_function!(
     "log", (f64::ln), (libm::Libm::<f64>::log),
     /// Natural logarithm function.
-    pub Log
+    pub Log, derivative (|x: f64| 1.0 / x)
 );
 
 #[cfg(feature = "libm")]
 impl_one_arg_function!(
     "erf", (libm::Libm::<f64>::erf),
     /// Error function.
-    pub Erf
+    pub Erf, derivative (|x: f64| core::f64::consts::FRAC_2_SQRT_PI * exp(-x * x))
+);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl_one_arg_function!(
+    "erfc", (crate::float::erfc),
+    /// Complementary error function.
+    pub Erfc, derivative (|x: f64| -core::f64::consts::FRAC_2_SQRT_PI * exp(-x * x))
+);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl_one_arg_function!(
+    "gamma", (crate::float::gamma),
+    /// Gamma function.
+    pub Gamma
+);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl_one_arg_function!(
+    "lgamma", (crate::float::lgamma),
+    /// Natural logarithm of absolute value of gamma function.
+    pub Lgamma
+);
+
+#[cfg(feature = "libm")]
+impl_one_arg_function!(
+    "j0", (libm::Libm::<f64>::j0),
+    /// Bessel function of the first kind of order 0.
+    pub J0, derivative (|x: f64| -libm::Libm::<f64>::j1(x))
+);
+
+#[cfg(feature = "libm")]
+impl_one_arg_function!(
+    "j1", (libm::Libm::<f64>::j1),
+    /// Bessel function of the first kind of order 1.
+    pub J1
+);
+
+#[cfg(feature = "libm")]
+impl_one_arg_function!(
+    "y0", (libm::Libm::<f64>::y0),
+    /// Bessel function of the second kind of order 0.
+    pub Y0, derivative (|x: f64| -libm::Libm::<f64>::y1(x))
+);
+
+#[cfg(feature = "libm")]
+impl_one_arg_function!(
+    "y1", (libm::Libm::<f64>::y1),
+    /// Bessel function of the second kind of order 1.
+    pub Y1
+);
+
+// Bessel functions of order n are defined only for integer orders,
+// for other orders NAN is returned.
+#[cfg(feature = "libm")]
+#[allow(clippy::cast_possible_truncation)]
+fn integer_order(order: f64, function: fn(i32, f64) -> f64, x: f64) -> f64 {
+    if order % 1.0 != 0.0 || order < f64::from(i32::MIN) || order > f64::from(i32::MAX) {
+        return f64::NAN;
+    }
+    function(order as i32, x)
+}
+
+#[cfg(feature = "libm")]
+fn jn(order: f64, x: f64) -> f64 {
+    integer_order(order, libm::Libm::<f64>::jn, x)
+}
+
+#[cfg(feature = "libm")]
+fn yn(order: f64, x: f64) -> f64 {
+    integer_order(order, libm::Libm::<f64>::yn, x)
+}
+
+#[cfg(feature = "libm")]
+impl_multi_arg_function!(
+    "jn", (order, x), (jn),
+    /// Bessel function of the first kind of integer order `n`, called as `jn(n, x)`.
+    pub Jn
 );
+
+#[cfg(feature = "libm")]
+impl_multi_arg_function!(
+    "yn", (order, x), (yn),
+    /// Bessel function of the second kind of integer order `n`, called as `yn(n, x)`.
+    pub Yn
+);
+
+impl_one_arg_function!(
+    "expm1", (f64::exp_m1), (libm::Libm::<f64>::expm1),
+    /// Computes `exp(x) - 1` in a way that is accurate even if `x` is close to zero.
+    pub Expm1, derivative (exp)
+);
+
+impl_one_arg_function!(
+    "log1p", (f64::ln_1p), (libm::Libm::<f64>::log1p),
+    /// Computes `log(1 + x)` in a way that is accurate even if `x` is close to zero.
+    pub Log1p, derivative (|x: f64| 1.0 / (1.0 + x))
+);
+
+impl_one_arg_function!(
+    "exp2", (f64::exp2), (libm::Libm::<f64>::exp2),
+    /// Computes `2^x`.
+    pub Exp2, derivative (|x: f64| core::f64::consts::LN_2 * powf(2.0, x))
+);
+
+#[cfg(feature = "std")]
+fn exp10(x: f64) -> f64 {
+    10.0_f64.powf(x)
+}
+
+impl_one_arg_function!(
+    "exp10", (exp10), (libm::Libm::<f64>::exp10),
+    /// Computes `10^x`.
+    pub Exp10, derivative (|x: f64| core::f64::consts::LN_10 * powf(10.0, x))
+);
+
+#[cfg(all(test, any(feature = "std", feature = "libm")))]
+mod test {
+    use crate::formulas::math::{Erfc, Gamma, Lgamma};
+    use crate::formulas::{Evaluate, RootFormula};
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::EmptyVariableStore;
+
+    fn eval_with_special_functions(expression: &str) -> f64 {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Erfc>();
+        store.register::<Gamma>();
+        store.register::<Lgamma>();
+        #[cfg(feature = "libm")]
+        store.register::<super::Jn>();
+        RootFormula::parse(expression, &store)
+            .unwrap()
+            .eval(&EmptyVariableStore)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_gamma() {
+        assert!((eval_with_special_functions("gamma(5)") - 24.0).abs() < 1e-10);
+        let res = eval_with_special_functions("gamma(0 - 0.5)");
+        assert!((res + 3.544_907_701_811_032).abs() < 1e-10, "{res}");
+        let res = eval_with_special_functions("gamma(0 - 1.5)");
+        assert!((res - 2.363_271_801_207_355).abs() < 1e-10, "{res}");
+        assert!(eval_with_special_functions("gamma(0 - 2)").is_nan());
+        let res = eval_with_special_functions("lgamma(100)");
+        assert!((res - 359.134_205_369_575_4).abs() < 1e-9, "{res}");
+    }
+
+    #[test]
+    fn test_erfc() {
+        let res = eval_with_special_functions("erfc(0.5)");
+        assert!((res - 0.479_500_122_186_953_5).abs() < 1e-14, "{res}");
+        let res = eval_with_special_functions("erfc(3)");
+        assert!((res - 2.209_049_699_858_544e-5).abs() < 1e-18, "{res}");
+    }
+
+    #[cfg(feature = "libm")]
+    #[test]
+    fn test_bessel_of_integer_order() {
+        let res = eval_with_special_functions("jn(2, 1.5)");
+        assert!((res - 0.232_087_672_144_214_7).abs() < 1e-12, "{res}");
+    }
+
+    #[cfg(feature = "libm")]
+    #[test]
+    fn test_bessel_of_non_integer_order() {
+        assert!(eval_with_special_functions("jn(0.5, 1.5)").is_nan());
+    }
+}
@@ -3,7 +3,7 @@ use crate::__lib::sync::Arc;
 use crate::__lib::vec::Vec;
 use crate::formulas::root_formula::RootFormula;
 use crate::formulas::{
-    Evaluate, EvaluationError, Function, FunctionLike, IsConst, MathError, ParserError,
+    Evaluate, EvaluationError, Function, FunctionLike, IsConst, Lowering, MathError, ParserError,
 };
 use crate::function_stores::GetFunction;
 use crate::variable_stores::{GetVariable, Variable};
@@ -67,6 +67,25 @@ impl FunctionLike for Min {
             arguments: self.arguments.clone(),
         })
     }
+
+    fn reset(&self) {
+        for val in self.arguments.as_ref() {
+            val.reset();
+        }
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<Variable>) {
+        for val in self.arguments.as_ref() {
+            val.collect_variables(variables);
+        }
+    }
+
+    fn lower(&self) -> Option<Lowering<'_>> {
+        Some(Lowering::Many(
+            |values| values.iter().fold(f64::MAX, |min, value| min.min(*value)),
+            self.arguments.iter().collect(),
+        ))
+    }
 }
 
 impl Function for Min {
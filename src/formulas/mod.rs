@@ -4,18 +4,40 @@ use crate::__lib::error::Error;
 use crate::__lib::fmt::{Debug, Display, Formatter};
 use crate::__lib::string::String;
 use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
 use crate::function_stores::GetFunction;
 use crate::variable_stores::{GetVariable, Variable};
 
 /// Provides macros for fast construction of functions.
 #[macro_use]
 pub mod macros;
+pub(crate) mod binding;
+/// Provides functions for numerical calculus.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod calculus;
+mod closure;
+/// Provides combinatorics and number theory functions.
+pub mod combinatorics;
+mod compiled;
+/// Provides probability distribution functions.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod distributions;
+mod limits;
 /// Provides base mathematical functions.
 pub mod math;
 mod min;
 pub(crate) mod operator;
+/// Provides functions producing pseudo random numbers.
+pub mod random;
 mod root_formula;
-
+/// Provides summation and product of series.
+pub mod series;
+/// Provides functions with inner state, which is updated on every evaluation.
+pub mod stateful;
+
+pub use closure::ClosureFormula;
+pub use compiled::{BoundFormula, CompiledFormula, Lowering, Operation};
+pub use limits::Limits;
 pub use min::Min;
 pub use root_formula::RootFormula;
 
@@ -23,6 +45,12 @@ pub use root_formula::RootFormula;
 #[derive(Debug)]
 pub struct UnknownTokenError(String);
 
+impl UnknownTokenError {
+    pub(crate) fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+}
+
 impl Display for UnknownTokenError {
     fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
         write!(f, "Got unknown token {}", self.0)
@@ -63,6 +91,13 @@ impl Error for ArgumentsError {}
 #[derive(Debug)]
 pub struct MathError(String);
 
+impl MathError {
+    /// Creates new `MathError`, `message` describes what failed to evaluate.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
 impl Display for MathError {
     fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
         write!(f, "Failed to evaluate {}", self.0)
@@ -94,6 +129,105 @@ impl Display for NoVariableError {
 #[cfg(any(feature = "std", nightly))]
 impl Error for NoVariableError {}
 
+/// The error type which is returned when variables depend on each other in a cycle, so none of them can be evaluated.
+#[derive(Debug)]
+pub struct CycleError {
+    variables: Vec<Variable>,
+}
+
+impl CycleError {
+    /// Creates new `CycleError`, every variable of `variables` depends on the next one and the last one depends on the first.
+    pub const fn new(variables: Vec<Variable>) -> Self {
+        Self { variables }
+    }
+
+    /// Returns variables in cycle, every variable depends on the next one and the last one depends on the first.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        write!(f, "Variables form a cycle: ")?;
+        for variable in &self.variables {
+            write!(f, "{variable} -> ")?;
+        }
+        self.variables
+            .first()
+            .map_or(Ok(()), |first| write!(f, "{first}"))
+    }
+}
+
+#[cfg(any(feature = "std", nightly))]
+impl Error for CycleError {}
+
+/// Resource, which usage is limited by [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// Length of expression in bytes.
+    Length,
+    /// Nesting depth of parenthesis and function calls.
+    Depth,
+    /// Number of numbers, variables, functions and operators in expression.
+    Nodes,
+    /// Number of evaluated formulas.
+    Steps,
+    /// Number of variables, which are evaluated one inside of another.
+    Recursion,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        let name = match self {
+            Self::Length => "expression length",
+            Self::Depth => "nesting depth",
+            Self::Nodes => "number of nodes",
+            Self::Steps => "number of evaluation steps",
+            Self::Recursion => "recursion depth through variables",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The error type which is returned when parsing or evaluation exceeded one of [`Limits`].
+#[derive(Debug)]
+pub struct LimitError {
+    limit: Limit,
+    max: usize,
+}
+
+impl LimitError {
+    /// Creates new `LimitError`, `max` is the allowed maximum of `limit`.
+    pub const fn new(limit: Limit, max: usize) -> Self {
+        Self { limit, max }
+    }
+
+    /// Returns limit, which was exceeded.
+    pub const fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// Returns the allowed maximum.
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        write!(
+            f,
+            "Exceeded limit of {}, maximum is {}",
+            self.limit, self.max
+        )
+    }
+}
+
+#[cfg(any(feature = "std", nightly))]
+impl Error for LimitError {}
+
 /// The error variants which are returned when failed to evaluate formula for any reason.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -102,6 +236,10 @@ pub enum EvaluationError {
     MathError(MathError),
     /// Some variable's value is not set.
     NoVariableError(NoVariableError),
+    /// Some variable depends on itself.
+    CycleError(CycleError),
+    /// Evaluation exceeded limits of variable store.
+    LimitError(LimitError),
 }
 
 impl Display for EvaluationError {
@@ -109,6 +247,8 @@ impl Display for EvaluationError {
         match self {
             Self::MathError(e) => Display::fmt(e, f),
             Self::NoVariableError(e) => Display::fmt(e, f),
+            Self::CycleError(e) => Display::fmt(e, f),
+            Self::LimitError(e) => Display::fmt(e, f),
         }
     }
 }
@@ -128,6 +268,8 @@ pub enum ParserError {
     ArgumentsError(ArgumentsError),
     /// Failed to evaluate constant function.
     EvaluationError(EvaluationError),
+    /// Expression exceeded limits of function store.
+    LimitError(LimitError),
 }
 
 impl Display for ParserError {
@@ -137,6 +279,7 @@ impl Display for ParserError {
             Self::ParenthesisError(e) => Display::fmt(e, f),
             Self::ArgumentsError(e) => Display::fmt(e, f),
             Self::EvaluationError(e) => Display::fmt(e, f),
+            Self::LimitError(e) => Display::fmt(e, f),
         }
     }
 }
@@ -168,6 +311,12 @@ impl From<UnknownTokenError> for ParserError {
     }
 }
 
+impl From<LimitError> for ParserError {
+    fn from(value: LimitError) -> Self {
+        Self::LimitError(value)
+    }
+}
+
 impl From<MathError> for ParserError {
     fn from(value: MathError) -> Self {
         Self::EvaluationError(EvaluationError::MathError(value))
@@ -213,6 +362,42 @@ pub trait FunctionLike: Evaluate + IsConst + Debug + Send + Sync {
     fn set_variable_owned(&mut self, name: &Variable, function: &RootFormula);
     /// Creates clone of object stored in [`Box`] as trait object
     fn clone_into_box(&self) -> Box<dyn FunctionLike>;
+    /// Resets inner state of function and all its arguments to the state function had after parsing.
+    /// Functions without inner state only have to pass the call to their arguments, functions without arguments
+    /// and state can keep default implementation, which does nothing.
+    /// If function is set as shared in different formulas, reset affects all of them.
+    #[inline]
+    fn reset(&self) {}
+    /// Adds variables, that function depends on, to `variables`, variable may be added more than once.
+    /// Functions with arguments have to pass the call to their arguments, variables bound by function itself
+    /// must not be added. Dependencies are used to detect cycles and to find formulas, which have to be
+    /// evaluated again after variable changes, so functions without arguments implement it as doing nothing.
+    fn collect_variables(&self, variables: &mut Vec<Variable>);
+    /// Evaluates function together with its derivative with respect to `variable`, this is automatic
+    /// differentiation used by [`Derivative::Automatic`](crate::solvers::Derivative::Automatic).
+    /// Functions have to combine values and derivatives of their arguments by chain rule.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if function fails to evaluate. Default implementation returns [`MathError`],
+    /// because function can not be differentiated automatically.
+    #[inline]
+    fn eval_derivative(
+        &self,
+        args: &dyn GetVariable,
+        variable: &Variable,
+    ) -> Result<(f64, f64), EvaluationError> {
+        let _ = (args, variable);
+        Err(EvaluationError::MathError(MathError::new(
+            "derivative, function can not be differentiated automatically",
+        )))
+    }
+    /// Describes function for compilation into [`CompiledFormula`]. Functions, that return `None`,
+    /// are evaluated as is by compiled formula. Default implementation returns `None`.
+    #[inline]
+    fn lower(&self) -> Option<Lowering<'_>> {
+        None
+    }
 }
 
 impl IsConst for Box<dyn FunctionLike> {
@@ -258,6 +443,30 @@ impl FunctionLike for Box<dyn FunctionLike> {
     fn clone_into_box(&self) -> Box<dyn FunctionLike> {
         self.as_ref().clone_into_box()
     }
+
+    #[inline]
+    fn reset(&self) {
+        self.as_ref().reset();
+    }
+
+    #[inline]
+    fn eval_derivative(
+        &self,
+        args: &dyn GetVariable,
+        variable: &Variable,
+    ) -> Result<(f64, f64), EvaluationError> {
+        self.as_ref().eval_derivative(args, variable)
+    }
+
+    #[inline]
+    fn collect_variables(&self, variables: &mut Vec<Variable>) {
+        self.as_ref().collect_variables(variables);
+    }
+
+    #[inline]
+    fn lower(&self) -> Option<Lowering<'_>> {
+        self.as_ref().lower()
+    }
 }
 
 /// Trait provides methods to parse [`&str`] into [`FunctionLike`].
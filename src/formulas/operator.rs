@@ -1,7 +1,10 @@
 use crate::__lib::boxed::Box;
 use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
 use crate::formulas::root_formula::RootFormula;
-use crate::formulas::{Evaluate, EvaluationError, FunctionLike, IsConst, MathError};
+use crate::formulas::{
+    Evaluate, EvaluationError, FunctionLike, IsConst, Lowering, MathError, Operation,
+};
 use crate::tokens::Operator;
 use crate::variable_stores::{GetVariable, Variable};
 
@@ -30,18 +33,6 @@ impl IsConst for OperatorFormula {
     }
 }
 
-#[cfg(feature = "std")]
-#[inline]
-fn power_function(base: f64, power: f64) -> f64 {
-    base.powf(power)
-}
-
-#[cfg(all(not(feature = "std"), feature = "libm"))]
-#[inline]
-fn power_function(base: f64, power: f64) -> f64 {
-    libm::Libm::<f64>::pow(base, power)
-}
-
 impl Evaluate for OperatorFormula {
     fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
         let first = self.first.eval(args)?;
@@ -52,7 +43,7 @@ impl Evaluate for OperatorFormula {
             Operator::Multiply => Ok(first * second),
             Operator::Divide => Ok(first / second),
             #[cfg(any(feature = "std", feature = "libm"))]
-            Operator::Exponent => Ok(power_function(first, second)),
+            Operator::Exponent => Ok(crate::float::powf(first, second)),
         }
     }
 }
@@ -91,4 +82,69 @@ impl FunctionLike for OperatorFormula {
             operator: self.operator.clone(),
         })
     }
+
+    fn reset(&self) {
+        self.first.reset();
+        self.second.reset();
+    }
+
+    // `mul_add` is not available without std
+    #[allow(clippy::suboptimal_flops)]
+    fn eval_derivative(
+        &self,
+        args: &dyn GetVariable,
+        variable: &Variable,
+    ) -> Result<(f64, f64), EvaluationError> {
+        let (first, first_derivative) = self.first.eval_derivative(args, variable)?;
+        let (second, second_derivative) = self.second.eval_derivative(args, variable)?;
+        Ok(match &self.operator {
+            Operator::Plus => (first + second, first_derivative + second_derivative),
+            Operator::Minus => (first - second, first_derivative - second_derivative),
+            Operator::Multiply => (
+                first * second,
+                first_derivative * second + first * second_derivative,
+            ),
+            Operator::Divide => {
+                let value = first / second;
+                (
+                    value,
+                    (first_derivative - value * second_derivative) / second,
+                )
+            }
+            #[cfg(any(feature = "std", feature = "libm"))]
+            Operator::Exponent => {
+                let value = crate::float::powf(first, second);
+                // constant exponent is differentiated without logarithm, so negative base is allowed
+                let derivative = if second_derivative == 0.0 {
+                    if first_derivative == 0.0 {
+                        0.0
+                    } else {
+                        second * crate::float::powf(first, second - 1.0) * first_derivative
+                    }
+                } else {
+                    value
+                        * (second_derivative * crate::float::ln(first)
+                            + second * first_derivative / first)
+                };
+                (value, derivative)
+            }
+        })
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<Variable>) {
+        self.first.collect_variables(variables);
+        self.second.collect_variables(variables);
+    }
+
+    fn lower(&self) -> Option<Lowering<'_>> {
+        let operation = match &self.operator {
+            Operator::Plus => Operation::Add,
+            Operator::Minus => Operation::Subtract,
+            Operator::Multiply => Operation::Multiply,
+            Operator::Divide => Operation::Divide,
+            #[cfg(any(feature = "std", feature = "libm"))]
+            Operator::Exponent => Operation::Power,
+        };
+        Some(Lowering::Operation(operation, &self.first, &self.second))
+    }
 }
@@ -0,0 +1,284 @@
+// Random numbers are produced from 64 bit state, so casts are intended to drop precision.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use crate::formulas::EvaluationError;
+use crate::function_stores::GetFunction;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(doc)]
+use crate::function_stores::SetSeed;
+
+pub(crate) const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// finalizer of SplitMix64 generator
+pub(crate) const fn split_mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+// SplitMix64 generator, its state is advanced with single atomic operation,
+// so it can be shared between threads without locks.
+#[derive(Debug)]
+struct Generator {
+    seed: u64,
+    state: AtomicU64,
+}
+
+impl Generator {
+    const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    fn reset(&self) {
+        self.state.store(self.seed, Ordering::Relaxed);
+    }
+
+    fn next_u64(&self) -> u64 {
+        let previous = self.state.fetch_add(GOLDEN_GAMMA, Ordering::Relaxed);
+        split_mix(previous.wrapping_add(GOLDEN_GAMMA))
+    }
+
+    // uniformly distributed in [0, 1)
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+impl Clone for Generator {
+    fn clone(&self) -> Self {
+        Self {
+            seed: self.seed,
+            state: AtomicU64::new(self.state.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn entropy() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    RandomState::new().build_hasher().finish()
+}
+
+// without std there is no source of entropy, so every new function just gets next seed from global sequence.
+#[cfg(not(feature = "std"))]
+fn entropy() -> u64 {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    split_mix(SEQUENCE.fetch_add(GOLDEN_GAMMA, Ordering::Relaxed))
+}
+
+fn new_generator<T: for<'a> GetFunction<'a>>(formulas: &T) -> Generator {
+    Generator::new(formulas.next_seed().unwrap_or_else(entropy))
+}
+
+fn rand(generator: &Generator) -> f64 {
+    generator.next_f64()
+}
+
+fn rand_int(generator: &Generator, low: f64, high: f64) -> f64 {
+    let is_integer = |value: f64| value.is_finite() && value % 1.0 == 0.0;
+    if !is_integer(low) || !is_integer(high) || low > high {
+        return f64::NAN;
+    }
+    // number of integers in range must fit into u64
+    let width = high - low;
+    if width >= 18_446_744_073_709_551_616.0 {
+        return f64::NAN;
+    }
+    let range = width as u64 + 1;
+    // multiply-shift maps 64 random bits to `0..range` without division
+    let offset = (u128::from(generator.next_u64()) * u128::from(range)) >> 64;
+    low + offset as f64
+}
+
+// `mul_add` is not available without std
+#[allow(clippy::suboptimal_flops)]
+#[cfg(any(feature = "std", feature = "libm"))]
+fn normal(generator: &Generator, mu: f64, sigma: f64) -> f64 {
+    use crate::float::{cos, ln, sqrt};
+
+    if sigma < 0.0 {
+        return f64::NAN;
+    }
+    // Box-Muller transform, first uniform number is taken from (0, 1] to avoid ln(0)
+    let first = 1.0 - generator.next_f64();
+    let second = generator.next_f64();
+    let standard = sqrt(-2.0 * ln(first)) * cos(2.0 * core::f64::consts::PI * second);
+    mu + sigma * standard
+}
+
+macro_rules! impl_random_function {
+    (
+        $parser_name:expr, ($($argument:ident),*), $function:ident,
+        $(#[$meta: meta])*
+        $StructName:ident
+    ) => {
+        $(#[$meta])*
+        ///
+        /// Function is never constant, so it is not folded while parsing. Seed of the function is taken from
+        /// function store it is parsed with, see [`SetSeed`]. Owned clones of function continue its sequence
+        /// independently, while functions set as shared share one sequence.
+        #[derive(Debug)]
+        pub struct $StructName {
+            $($argument: $crate::formulas::RootFormula,)*
+            generator: Generator,
+        }
+
+        impl $crate::formulas::IsConst for $StructName {
+            #[inline]
+            fn is_const(&self) -> bool {
+                false
+            }
+        }
+
+        impl $crate::formulas::Evaluate for $StructName {
+            #[allow(unused_variables)]
+            fn eval(&self, args: &dyn $crate::variable_stores::GetVariable) -> Result<f64, EvaluationError> {
+                Ok($function(&self.generator, $(self.$argument.eval(args)?),*))
+            }
+        }
+
+        impl $crate::formulas::FunctionLike for $StructName {
+            $crate::delegate_function_like!(($($argument),*), reset(generator));
+
+            fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
+                $crate::__lib::boxed::Box::new(Self {
+                    $($argument: self.$argument.clone(),)*
+                    generator: self.generator.clone(),
+                })
+            }
+        }
+
+        impl $crate::formulas::Function for $StructName {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = <[&str]>::len(&[$(stringify!($argument)),*]);
+            const MAX_NUMBER_OF_ARGUMENTS: usize = Self::MIN_NUMBER_OF_ARGUMENTS;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, $crate::formulas::ParserError>
+            where
+                Self: Sized,
+            {
+                #[allow(unused_mut, unused_variables)]
+                let mut arguments = arguments.iter();
+                Ok(Self {
+                    $($argument: $crate::formulas::RootFormula::parse(
+                        arguments.next().copied().unwrap_or_default(),
+                        formulas,
+                    )?,)*
+                    generator: new_generator(formulas),
+                })
+            }
+        }
+    };
+}
+
+impl_random_function!(
+    "rand", (), rand,
+    /// Uniformly distributed random number from `[0, 1)`, called as `rand()`.
+    Rand
+);
+
+impl_random_function!(
+    "rand_int", (low, high), rand_int,
+    /// Uniformly distributed random integer from `[low, high]`, called as `rand_int(low, high)`.
+    RandInt
+);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl_random_function!(
+    "normal", (mu, sigma), normal,
+    /// Normally distributed random number, called as `normal(mu, sigma)`.
+    Normal
+);
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::sync::Arc;
+    use crate::formulas::random::{Rand, RandInt};
+    use crate::formulas::{Evaluate, FunctionLike, RootFormula};
+    use crate::function_stores::{RegisterParser, SetSeed, VectorFunctionStore};
+    use crate::variable_stores::{EmptyVariableStore, Variable};
+
+    fn seeded_store(seed: u64) -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Rand>();
+        store.register::<RandInt>();
+        store.set_seed(seed);
+        store
+    }
+
+    #[test]
+    fn test_same_seed_same_values() {
+        let first = RootFormula::parse("rand() - rand()", &seeded_store(42)).unwrap();
+        let second = RootFormula::parse("rand() - rand()", &seeded_store(42)).unwrap();
+        for _ in 0..10 {
+            let value = first.eval(&EmptyVariableStore).unwrap();
+            assert!(value.abs() > 0.0, "both functions got same seed");
+            assert!((value - second.eval(&EmptyVariableStore).unwrap()).abs() < f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_rand_int_bounds() {
+        let formula = RootFormula::parse("rand_int(1, 6)", &seeded_store(7)).unwrap();
+        for _ in 0..100 {
+            let value = formula.eval(&EmptyVariableStore).unwrap();
+            assert!(
+                (1.0..=6.0).contains(&value) && value % 1.0 == 0.0,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rand_int_huge_range() {
+        let store = seeded_store(7);
+        let formula = RootFormula::parse("rand_int(0, 10000000000000000000)", &store).unwrap();
+        assert!(formula.eval(&EmptyVariableStore).unwrap() >= 0.0);
+        for expression in [
+            "rand_int(0, 18446744073709551616)",
+            "rand_int(-10000000000000000000, 10000000000000000000)",
+        ] {
+            let formula = RootFormula::parse(expression, &store).unwrap();
+            assert!(
+                formula.eval(&EmptyVariableStore).unwrap().is_nan(),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_owned_and_shared_state() {
+        let random = Arc::new(RootFormula::parse("rand()", &seeded_store(1)).unwrap());
+        let mut shared = RootFormula::new(Variable::new("r"));
+        shared.set_variable_shared(&Variable::new("r"), &random);
+        let mut owned = RootFormula::new(Variable::new("r"));
+        owned.set_variable_owned(&Variable::new("r"), &random);
+
+        let from_owned = owned.eval(&EmptyVariableStore).unwrap();
+        let from_shared = shared.eval(&EmptyVariableStore).unwrap();
+        let from_original = random.eval(&EmptyVariableStore).unwrap();
+        assert!((from_owned - from_shared).abs() < f64::EPSILON);
+        assert!((from_shared - from_original).abs() > 0.0);
+    }
+
+    #[test]
+    fn test_not_folded_by_operators() {
+        let formula = RootFormula::parse("rand()", &seeded_store(3)).unwrap() + 1.0;
+        let first = formula.eval(&EmptyVariableStore).unwrap();
+        let second = formula.eval(&EmptyVariableStore).unwrap();
+        assert!((first - second).abs() > 0.0);
+    }
+}
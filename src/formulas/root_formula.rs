@@ -1,9 +1,13 @@
 use crate::__lib::boxed::Box;
+use crate::__lib::collections::BTreeSet;
 use crate::__lib::fmt::Debug;
 use crate::__lib::ops::{Add, Div, Mul, Sub};
+use crate::__lib::vec::Vec;
 use crate::formulas::root_formula::formula_argument::FormulaArgument;
 use crate::formulas::root_formula::lexer::lex_expression;
-use crate::formulas::{Evaluate, EvaluationError, FunctionLike, IsConst, MathError, ParserError};
+use crate::formulas::{
+    Evaluate, EvaluationError, FunctionLike, IsConst, Lowering, MathError, ParserError,
+};
 use crate::function_stores::GetFunction;
 use crate::tokens::{BaseToken, Operator};
 use crate::variable_stores::{EmptyVariableStore, GetVariable, Variable};
@@ -129,7 +133,13 @@ impl FunctionLike for RootFormula {
             self.tree = FormulaArgument::Number(match self.eval(&EmptyVariableStore) {
                 Ok(val) => val,
                 Err(EvaluationError::MathError(e)) => return Err(e),
-                Err(EvaluationError::NoVariableError(_)) => unreachable!(),
+                Err(
+                    EvaluationError::NoVariableError(_)
+                    | EvaluationError::CycleError(_)
+                    | EvaluationError::LimitError(_),
+                ) => {
+                    unreachable!()
+                }
             });
             return Ok(());
         }
@@ -177,12 +187,9 @@ impl FunctionLike for RootFormula {
 
     fn set_variable_shared(&mut self, name: &Variable, function: &Arc<RootFormula>) {
         match self.tree {
-            FormulaArgument::Variable(ref variable) => {
-                if variable == name {
-                    self.tree = FormulaArgument::SharedFunction(
-                        Arc::clone(function) as Arc<dyn FunctionLike>
-                    );
-                }
+            FormulaArgument::Variable(ref variable) if variable == name => {
+                self.tree =
+                    FormulaArgument::SharedFunction(Arc::clone(function) as Arc<dyn FunctionLike>);
             }
             FormulaArgument::OwnedFunction(ref mut local_function) => {
                 local_function.set_variable_shared(name, function);
@@ -193,10 +200,8 @@ impl FunctionLike for RootFormula {
 
     fn set_variable_owned(&mut self, name: &Variable, function: &RootFormula) {
         match self.tree {
-            FormulaArgument::Variable(ref variable) => {
-                if variable == name {
-                    self.tree = FormulaArgument::OwnedFunction(function.clone_into_box());
-                }
+            FormulaArgument::Variable(ref variable) if variable == name => {
+                self.tree = FormulaArgument::OwnedFunction(function.clone_into_box());
             }
             FormulaArgument::OwnedFunction(ref mut local_function) => {
                 local_function.set_variable_owned(name, function);
@@ -208,6 +213,50 @@ impl FunctionLike for RootFormula {
     fn clone_into_box(&self) -> Box<dyn FunctionLike> {
         Box::new(self.clone())
     }
+
+    fn reset(&self) {
+        match &self.tree {
+            FormulaArgument::OwnedFunction(function) => function.reset(),
+            FormulaArgument::SharedFunction(function) => function.reset(),
+            FormulaArgument::Number(_) | FormulaArgument::Variable(_) => {}
+        }
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<Variable>) {
+        match &self.tree {
+            FormulaArgument::OwnedFunction(function) => function.collect_variables(variables),
+            FormulaArgument::SharedFunction(function) => function.collect_variables(variables),
+            FormulaArgument::Variable(variable) => variables.push(variable.clone()),
+            FormulaArgument::Number(_) => {}
+        }
+    }
+
+    // variables from store, that depend on `variable`, are differentiated through their formulas,
+    // their values are evaluated first, so cycles are reported before formulas are followed
+    fn eval_derivative(
+        &self,
+        args: &dyn GetVariable,
+        variable: &Variable,
+    ) -> Result<(f64, f64), EvaluationError> {
+        match &self.tree {
+            FormulaArgument::OwnedFunction(function) => function.eval_derivative(args, variable),
+            FormulaArgument::SharedFunction(function) => function.eval_derivative(args, variable),
+            FormulaArgument::Variable(name) if name == variable => Ok((args.eval(name)?, 1.0)),
+            FormulaArgument::Variable(name) => {
+                let value = args.eval(name)?;
+                let derivative = match args.get(name) {
+                    Some(formula) => formula.eval_derivative(args, variable)?.1,
+                    None => 0.0,
+                };
+                Ok((value, derivative))
+            }
+            FormulaArgument::Number(num) => Ok((*num, 0.0)),
+        }
+    }
+
+    fn lower(&self) -> Option<Lowering<'_>> {
+        Some(Lowering::Formula(self))
+    }
 }
 
 mod lexer {
@@ -302,17 +351,21 @@ mod lexer {
         *expression = &expression[spaces..];
     }
 
+    // Variable is letters optionally followed by digits, like `x`, `x1` or `A10`.
     fn lex_variable(expression: &mut &str) -> Option<Variable> {
         let mut parsed: usize = 0;
         let mut started = false;
+        let mut digits = false;
         for elem in expression.chars() {
-            if elem.is_alphabetic() {
-                parsed += 1;
+            if elem.is_alphabetic() && !digits {
+                parsed += elem.len_utf8();
                 started = true;
                 continue;
             }
             if started && elem.is_ascii_digit() {
                 parsed += 1;
+                digits = true;
+                continue;
             }
             break;
         }
@@ -331,7 +384,7 @@ mod lexer {
         let mut prev_comma: usize = 0;
         let mut early_exit = false;
         let mut last_index = 0;
-        for (index, elem) in expression.chars().enumerate() {
+        for (index, elem) in expression.char_indices() {
             match elem {
                 '(' => brackets += 1,
                 ')' => {
@@ -355,6 +408,10 @@ mod lexer {
         }
 
         *expression = &expression[last_index + 1..];
+        // function called without arguments
+        if arguments.len() == 1 && arguments[0].trim().is_empty() {
+            arguments.clear();
+        }
         arguments.into_boxed_slice()
     }
 
@@ -427,10 +484,11 @@ mod lexer {
         use crate::__lib::convert::identity;
         use crate::formulas::root_formula::lexer::{
             collect_arguments, lex_expression, lex_function, lex_number, lex_parenthesis,
-            remove_spaces,
+            lex_variable, remove_spaces,
         };
         use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
         use crate::tokens::{BaseToken, Bracket, NumberLike, Operator};
+        use crate::variable_stores::Variable;
 
         impl_one_arg_function!(
             "ident",
@@ -550,6 +608,17 @@ mod lexer {
             // let res = res.unwrap();
         }
 
+        #[test]
+        fn test_lex_variable() {
+            let mut expression = "AB12+x1 y";
+            assert_eq!(lex_variable(&mut expression), Some(Variable::new("AB12")));
+            assert_eq!(expression, "+x1 y", "{expression}");
+            let mut expression = "v10a";
+            assert_eq!(lex_variable(&mut expression), Some(Variable::new("v10")));
+            assert_eq!(expression, "a", "{expression}");
+            assert_eq!(lex_variable(&mut "1a"), None);
+        }
+
         #[test]
         fn test_collect_arguments() {
             let mut expression = "1, 2, (3, 4))";
@@ -558,6 +627,14 @@ mod lexer {
             assert_eq!(arguments.as_ref(), ["1", " 2", " (3, 4)"]);
             assert_eq!(expression, "", "{expression}");
         }
+
+        #[test]
+        fn test_collect_no_arguments() {
+            let mut expression = " ) + 1";
+            let arguments = collect_arguments(&mut expression);
+            assert!(arguments.is_empty(), "{arguments:?}");
+            assert_eq!(expression, " + 1", "{expression}");
+        }
     }
 }
 
@@ -569,10 +646,10 @@ mod parser {
     use crate::formulas::root_formula::formula_argument::FormulaArgument;
     use crate::formulas::root_formula::RootFormula;
     use crate::formulas::{
-        ArgumentsError, EvaluationError, FunctionLike, MathError, ParenthesisError, ParserError,
+        ArgumentsError, EvaluationError, FunctionLike, Limits, ParenthesisError, ParserError,
     };
     use crate::tokens::{BaseToken, Bracket, OpenBracket, Operator, Side};
-    use crate::variable_stores::EmptyVariableStore;
+    use crate::variable_stores::{EmptyVariableStore, LimitedVariableStore};
 
     enum OperatorStackToken {
         Operator(Operator),
@@ -674,12 +751,21 @@ mod parser {
     fn push_formula<T: FunctionLike + Into<BaseToken>>(
         rpn: &mut VecDeque<BaseToken>,
         mut formula: T,
-    ) -> Result<(), MathError> {
+        limits: Option<&Limits>,
+    ) -> Result<(), ParserError> {
         if formula.is_const() {
-            rpn.push_back(match formula.eval(&EmptyVariableStore) {
+            // constant functions, like integrals, may take many steps, so they are limited too
+            let value = limits.map_or_else(
+                || formula.eval(&EmptyVariableStore),
+                |limits| formula.eval(&LimitedVariableStore::new(EmptyVariableStore, *limits)),
+            );
+            rpn.push_back(match value {
                 Ok(val) => val.into(),
-                Err(EvaluationError::MathError(e)) => return Err(e),
-                Err(EvaluationError::NoVariableError(_)) => unreachable!(),
+                Err(EvaluationError::MathError(e)) => return Err(e.into()),
+                Err(EvaluationError::LimitError(e)) => return Err(e.into()),
+                Err(EvaluationError::NoVariableError(_) | EvaluationError::CycleError(_)) => {
+                    unreachable!()
+                }
             });
             return Ok(());
         }
@@ -688,7 +774,10 @@ mod parser {
         Ok(())
     }
 
-    fn compress_rpn(mut rpn: VecDeque<BaseToken>) -> Result<FormulaArgument, ParserError> {
+    fn compress_rpn(
+        mut rpn: VecDeque<BaseToken>,
+        limits: Option<&Limits>,
+    ) -> Result<FormulaArgument, ParserError> {
         let initial_len = rpn.len();
         for _ in 0..initial_len {
             let token = rpn.pop_front().unwrap();
@@ -712,10 +801,10 @@ mod parser {
                         Some(val) => RootFormula::new::<FormulaArgument>(val.try_into().unwrap()),
                     };
                     let operator_formula = operator.into_formula(first, second);
-                    push_formula(&mut rpn, operator_formula)?;
+                    push_formula(&mut rpn, operator_formula, limits)?;
                 }
                 BaseToken::Formula(formula) => {
-                    push_formula(&mut rpn, formula)?;
+                    push_formula(&mut rpn, formula, limits)?;
                 }
                 BaseToken::Bracket(_) => unreachable!(),
             }
@@ -730,8 +819,9 @@ mod parser {
 
     pub(super) fn parse_tokens(
         tokens: VecDeque<BaseToken>,
+        limits: Option<&Limits>,
     ) -> Result<FormulaArgument, ParserError> {
-        compress_rpn(build_rpn(tokens)?)
+        compress_rpn(build_rpn(tokens)?, limits)
     }
 
     #[cfg(test)]
@@ -934,7 +1024,7 @@ mod parser {
             initial.push_back(1.0.into());
             initial.push_back(2.0.into());
             initial.push_back(Operator::Plus.into());
-            let result = compress_rpn(initial);
+            let result = compress_rpn(initial, None);
             assert!(result.is_ok(), "{result:?}");
             let result = result.unwrap();
             assert!(
@@ -953,18 +1043,62 @@ impl RootFormula {
 
     /// Parses [`&str`] into `RootFormula`.
     ///
+    /// If `formulas` has [`Limits`](crate::formulas::Limits), expression is checked before it is parsed
+    /// and evaluation of constant parts of expression is limited.
+    ///
     /// # Errors
     ///
-    /// will return Err if non valid expression is passed.
+    /// will return Err if non valid expression is passed or expression exceeds limits of `formulas`.
     pub fn parse<T: for<'a> GetFunction<'a>>(
         expression: &str,
         formulas: &T,
     ) -> Result<Self, ParserError> {
+        let limits = formulas.limits();
+        if let Some(limits) = limits {
+            limits.check_expression(expression)?;
+        }
         let parsed = lex_expression(expression, formulas)?;
         Ok(Self {
-            tree: parse_tokens(parsed)?,
+            tree: parse_tokens(parsed, limits)?,
         })
     }
+
+    /// Returns variables, that formula depends on, in order of their first appearance.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use evaluatorrs::formulas::RootFormula;
+    /// use evaluatorrs::function_stores::EmptyFunctionStore;
+    /// use evaluatorrs::variable_stores::Variable;
+    ///
+    /// let formula = RootFormula::parse("y * x + y", &EmptyFunctionStore).unwrap();
+    /// assert_eq!(formula.variables(), [Variable::new("y"), Variable::new("x")]);
+    /// ```
+    pub fn variables(&self) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        // keeps first appearance of every variable
+        let mut seen = BTreeSet::new();
+        variables.retain(|variable| seen.insert(variable.clone()));
+        variables
+    }
+
+    /// Returns variable if formula consists only of it.
+    pub(crate) const fn as_variable(&self) -> Option<&Variable> {
+        match &self.tree {
+            FormulaArgument::Variable(variable) => Some(variable),
+            _ => None,
+        }
+    }
+
+    /// Returns function if formula consists of it.
+    pub(crate) fn as_function(&self) -> Option<&dyn FunctionLike> {
+        match &self.tree {
+            FormulaArgument::OwnedFunction(function) => Some(function.as_ref()),
+            FormulaArgument::SharedFunction(function) => Some(function.as_ref()),
+            FormulaArgument::Number(_) | FormulaArgument::Variable(_) => None,
+        }
+    }
 }
 
 impl<T: Into<FormulaArgument>> From<T> for RootFormula {
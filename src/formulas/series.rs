@@ -0,0 +1,200 @@
+// Number of terms is counted in float, so casts are intended to drop fraction.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+
+use crate::formulas::binding::BoundExpression;
+use crate::formulas::{EvaluationError, RootFormula};
+use crate::function_stores::GetFunction;
+use crate::variable_stores::GetVariable;
+
+// Applies `operation` to values of `expression` for index going from `start` to `end` with step 1.
+fn fold_series(
+    expression: &BoundExpression,
+    args: &dyn GetVariable,
+    start: f64,
+    end: f64,
+    identity: f64,
+    operation: fn(f64, f64) -> f64,
+) -> Result<f64, EvaluationError> {
+    if !(start.is_finite() && end.is_finite()) {
+        return Ok(f64::NAN);
+    }
+    if end < start {
+        return Ok(identity);
+    }
+    // number of terms must fit into u64
+    let span = end - start;
+    if span >= 18_446_744_073_709_551_616.0 {
+        return Ok(f64::NAN);
+    }
+    let count = span as u64 + 1;
+    let mut binding = expression.binding(args);
+    let mut result = identity;
+    for step in 0..count {
+        result = operation(result, binding.eval(start + step as f64)?);
+    }
+    Ok(result)
+}
+
+macro_rules! impl_series_function {
+    (
+        $parser_name:expr, $identity:expr, $operation:expr,
+        $(#[$meta: meta])*
+        $StructName:ident
+    ) => {
+        $(#[$meta])*
+        ///
+        /// Index variable is bound inside of `expression`, so it is visible only there and variables with
+        /// the same name are not substituted into `expression`. `start` and `end` can be any formulas, index goes
+        /// from `start` with step 1 while it is not greater than `end`. Returns NAN if `start` or `end` are not finite
+        /// or number of terms does not fit into `u64`.
+        ///
+        /// Series is never constant, so it is not evaluated while parsing, however many terms it has.
+        #[derive(Debug, Clone)]
+        pub struct $StructName {
+            expression: BoundExpression,
+            start: RootFormula,
+            end: RootFormula,
+        }
+
+        impl $crate::formulas::IsConst for $StructName {
+            fn is_const(&self) -> bool {
+                false
+            }
+        }
+
+        impl $crate::formulas::Evaluate for $StructName {
+            fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
+                let start = self.start.eval(args)?;
+                let end = self.end.eval(args)?;
+                fold_series(&self.expression, args, start, end, $identity, $operation)
+            }
+        }
+
+        impl $crate::formulas::FunctionLike for $StructName {
+            $crate::delegate_function_like!((expression, start, end));
+
+            fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
+                $crate::__lib::boxed::Box::new(self.clone())
+            }
+        }
+
+        impl $crate::formulas::Function for $StructName {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = 4;
+            const MAX_NUMBER_OF_ARGUMENTS: usize = 4;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, $crate::formulas::ParserError>
+            where
+                Self: Sized,
+            {
+                Ok(Self {
+                    expression: BoundExpression::parse(arguments[3], arguments[0], formulas)?,
+                    start: RootFormula::parse(arguments[1], formulas)?,
+                    end: RootFormula::parse(arguments[2], formulas)?,
+                })
+            }
+        }
+    };
+}
+
+impl_series_function!(
+    "sum", 0.0, |result, value| result + value,
+    /// Summation of series, called as `sum(index, start, end, expression)`. Empty sum is 0.
+    Sum
+);
+
+impl_series_function!(
+    "prod", 1.0, |result, value| result * value,
+    /// Product of series, called as `prod(index, start, end, expression)`. Empty product is 1.
+    Prod
+);
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::series::{Prod, Sum};
+    use crate::formulas::{Evaluate, FunctionLike, IsConst, ParserError, RootFormula};
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn store() -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Sum>();
+        store.register::<Prod>();
+        store
+    }
+
+    fn eval(expression: &str, variables: &VectorVariableStore) -> f64 {
+        RootFormula::parse(expression, &store())
+            .unwrap()
+            .eval(variables)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sum_and_prod() {
+        let mut variables = VectorVariableStore::new();
+        variables.set("n", RootFormula::parse("2 + 3", &store()).unwrap());
+        variables.set("i", 100.0);
+        assert!((eval("sum(i, 1, n, i)", &variables) - 15.0).abs() < f64::EPSILON);
+        assert!((eval("prod(i, 1, n, i)", &variables) - 120.0).abs() < f64::EPSILON);
+        assert!((eval("sum(k, 1, 3, prod(j, 1, k, 2))", &variables) - 14.0).abs() < f64::EPSILON);
+        assert!((eval("i + sum(j, 0, i / 50, j)", &variables) - 103.0).abs() < f64::EPSILON);
+        assert!((eval("sum(i, 0.5, 2, i)", &variables) - 2.0).abs() < f64::EPSILON);
+        assert!(eval("sum(i, 1, 0, i)", &variables).abs() < f64::EPSILON);
+        assert!((eval("prod(i, 1, 0, i)", &variables) - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_huge_and_fractional_bounds() {
+        let variables = VectorVariableStore::new();
+        assert!(eval("sum(i, 0, 100000000000000000000, i)", &variables).is_nan());
+        assert!(eval(
+            "prod(i, 0 - 10000000000000000000, 10000000000000000000, i)",
+            &variables
+        )
+        .is_nan());
+        assert!((eval("sum(i, 0.5, 2.7, i)", &variables) - 4.5).abs() < f64::EPSILON);
+        assert!((eval("sum(i, 0 - 0.5, 0.4, i)", &variables) + 0.5).abs() < f64::EPSILON);
+        assert!(eval("sum(i, 1.5, 1.2, i)", &variables).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_not_evaluated_while_parsing() {
+        let formula = RootFormula::parse("sum(i, 1, 1000000000000000000, 2)", &store()).unwrap();
+        assert!(!formula.is_const());
+        let formula = RootFormula::parse("1 + sum(i, 1, 3, 2)", &store()).unwrap();
+        let value = formula.eval(&VectorVariableStore::new()).unwrap();
+        assert!((value - 7.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_index_is_not_substituted() {
+        let mut formula = RootFormula::parse("sum(i, 1, n, i * n)", &store()).unwrap();
+        formula.set_variable_owned(&Variable::new("i"), &RootFormula::new(10.0));
+        formula.set_variable_owned(&Variable::new("n"), &RootFormula::new(3.0));
+        let value = formula.eval(&VectorVariableStore::new()).unwrap();
+        assert!((value - 18.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_index_is_not_dependency() {
+        let formula = RootFormula::parse("sum(i, 1, n, i * x) + i", &store()).unwrap();
+        let expected = [Variable::new("x"), Variable::new("n"), Variable::new("i")];
+        assert_eq!(formula.variables(), expected);
+        let formula = RootFormula::parse("prod(j, 1, 3, j)", &store()).unwrap();
+        assert!(formula.variables().is_empty());
+    }
+
+    #[test]
+    fn test_index_must_be_variable() {
+        let result = RootFormula::parse("sum(1, 1, 3, i)", &store());
+        assert!(matches!(result, Err(ParserError::UnknownTokenError(_))));
+    }
+}
@@ -0,0 +1,316 @@
+// Window length is converted from and to float, casts are intended as window can not be long enough to lose precision.
+// `mul_add` is not available without std, so accumulation is written with plain operations.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::suboptimal_flops
+)]
+
+use crate::formulas::EvaluationError;
+use crate::function_stores::GetFunction;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// Inner state of stateful function, every state can be copied for owned clones of function
+// and returned to the value it had after parsing.
+trait State {
+    fn duplicate(&self) -> Self;
+
+    fn reset(&self);
+}
+
+// Single float value stored as bits, so it can be updated with atomic operations without locks.
+#[derive(Debug)]
+struct ScalarState {
+    initial: f64,
+    bits: AtomicU64,
+}
+
+impl ScalarState {
+    fn new(initial: f64) -> Self {
+        Self {
+            initial,
+            bits: AtomicU64::new(initial.to_bits()),
+        }
+    }
+
+    // stores `value` and returns previous value
+    fn swap(&self, value: f64) -> f64 {
+        f64::from_bits(self.bits.swap(value.to_bits(), Ordering::Relaxed))
+    }
+
+    // atomically replaces value with `update(value)` and returns new value
+    fn update(&self, update: impl Fn(f64) -> f64) -> f64 {
+        let previous = self
+            .bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some(update(f64::from_bits(bits)).to_bits())
+            })
+            .unwrap_or_else(|bits| bits);
+        update(f64::from_bits(previous))
+    }
+}
+
+impl State for ScalarState {
+    fn duplicate(&self) -> Self {
+        Self {
+            initial: self.initial,
+            bits: AtomicU64::new(self.bits.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn reset(&self) {
+        self.bits.store(self.initial.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// Last values of argument, oldest value is at the front.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct WindowState(std::sync::Mutex<crate::__lib::collections::VecDeque<f64>>);
+
+#[cfg(feature = "std")]
+impl WindowState {
+    fn lock(&self) -> std::sync::MutexGuard<'_, crate::__lib::collections::VecDeque<f64>> {
+        // window is always left consistent, so it can be used even if other thread panicked
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl State for WindowState {
+    fn duplicate(&self) -> Self {
+        Self(std::sync::Mutex::new(self.lock().clone()))
+    }
+
+    fn reset(&self) {
+        self.lock().clear();
+    }
+}
+
+fn prev(state: &ScalarState, x: f64) -> f64 {
+    state.swap(x)
+}
+
+fn delta(state: &ScalarState, x: f64) -> f64 {
+    x - state.swap(x)
+}
+
+fn ema(state: &ScalarState, x: f64, alpha: f64) -> f64 {
+    state.update(|average| {
+        if average.is_nan() {
+            x
+        } else {
+            alpha * x + (1.0 - alpha) * average
+        }
+    })
+}
+
+#[cfg(feature = "std")]
+fn sma(state: &WindowState, x: f64, n: f64) -> f64 {
+    if !(n >= 1.0 && n % 1.0 == 0.0) {
+        return f64::NAN;
+    }
+    let length = n as usize;
+    let mut window = state.lock();
+    window.push_back(x);
+    while window.len() > length {
+        window.pop_front();
+    }
+    window.iter().sum::<f64>() / window.len() as f64
+}
+
+fn integrate_dt(state: &ScalarState, x: f64, dt: f64) -> f64 {
+    state.update(|integral| integral + x * dt)
+}
+
+macro_rules! impl_stateful_function {
+    (
+        $parser_name:expr, ($($argument:ident),+), $function:ident, $state:ty, $initial:expr,
+        $(#[$meta: meta])*
+        $StructName:ident
+    ) => {
+        $(#[$meta])*
+        ///
+        /// Function updates its inner state on every evaluation, so it is never constant and is not folded while parsing.
+        /// Owned clones of function continue from its current state independently, while functions set as shared
+        /// share one state. State is returned to initial with [`FunctionLike::reset`](crate::formulas::FunctionLike::reset).
+        #[derive(Debug)]
+        pub struct $StructName {
+            $($argument: $crate::formulas::RootFormula,)+
+            state: $state,
+        }
+
+        impl $crate::formulas::IsConst for $StructName {
+            #[inline]
+            fn is_const(&self) -> bool {
+                false
+            }
+        }
+
+        impl $crate::formulas::Evaluate for $StructName {
+            fn eval(&self, args: &dyn $crate::variable_stores::GetVariable) -> Result<f64, EvaluationError> {
+                Ok($function(&self.state, $(self.$argument.eval(args)?),+))
+            }
+        }
+
+        impl $crate::formulas::FunctionLike for $StructName {
+            $crate::delegate_function_like!(($($argument),+), reset(state));
+
+            fn clone_into_box(&self) -> $crate::__lib::boxed::Box<dyn $crate::formulas::FunctionLike> {
+                $crate::__lib::boxed::Box::new(Self {
+                    $($argument: self.$argument.clone(),)+
+                    state: self.state.duplicate(),
+                })
+            }
+        }
+
+        impl $crate::formulas::Function for $StructName {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = [$(stringify!($argument)),+].len();
+            const MAX_NUMBER_OF_ARGUMENTS: usize = Self::MIN_NUMBER_OF_ARGUMENTS;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, $crate::formulas::ParserError>
+            where
+                Self: Sized,
+            {
+                let mut arguments = arguments.iter();
+                Ok(Self {
+                    $($argument: $crate::formulas::RootFormula::parse(
+                        arguments.next().copied().unwrap_or_default(),
+                        formulas,
+                    )?,)+
+                    state: $initial,
+                })
+            }
+        }
+    };
+}
+
+impl_stateful_function!(
+    "prev", (x), prev, ScalarState, ScalarState::new(f64::NAN),
+    /// Value argument had on previous evaluation, called as `prev(x)`. Returns NAN on the first evaluation.
+    Prev
+);
+
+impl_stateful_function!(
+    "delta", (x), delta, ScalarState, ScalarState::new(f64::NAN),
+    /// Change of argument since previous evaluation, called as `delta(x)`. Returns NAN on the first evaluation.
+    Delta
+);
+
+impl_stateful_function!(
+    "ema", (x, alpha), ema, ScalarState, ScalarState::new(f64::NAN),
+    /// Exponential moving average of argument with smoothing factor `alpha`, called as `ema(x, alpha)`.
+    /// First evaluation returns `x`.
+    Ema
+);
+
+#[cfg(feature = "std")]
+impl_stateful_function!(
+    "sma", (x, n), sma, WindowState, WindowState::default(),
+    /// Simple moving average of last `n` values of argument, called as `sma(x, n)`.
+    /// Until `n` values are evaluated, average of all evaluated values is returned.
+    /// Returns NAN if `n` is not positive integer.
+    Sma
+);
+
+impl_stateful_function!(
+    "integrate_dt", (x, dt), integrate_dt, ScalarState, ScalarState::new(0.0),
+    /// Running sum of `x * dt` over all evaluations, called as `integrate_dt(x, dt)`.
+    IntegrateDt
+);
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::sync::Arc;
+    use crate::formulas::stateful::{Delta, Ema, IntegrateDt, Prev};
+    use crate::formulas::{Evaluate, FunctionLike, RootFormula};
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn store() -> VectorFunctionStore {
+        let mut store = VectorFunctionStore::new();
+        store.register::<Prev>();
+        store.register::<Delta>();
+        store.register::<Ema>();
+        store.register::<IntegrateDt>();
+        #[cfg(feature = "std")]
+        store.register::<super::Sma>();
+        store
+    }
+
+    fn run(formula: &RootFormula, values: &[f64]) -> crate::__lib::vec::Vec<f64> {
+        values
+            .iter()
+            .map(|value| {
+                let mut variables = VectorVariableStore::new();
+                variables.set("x", RootFormula::new(*value));
+                formula.eval(&variables).unwrap()
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-12, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn test_prev_and_delta() {
+        let formula = RootFormula::parse("prev(x)", &store()).unwrap();
+        let values = run(&formula, &[1.0, 3.0, 6.0]);
+        assert!(values[0].is_nan());
+        assert_close(&values[1..], &[1.0, 3.0]);
+
+        let formula = RootFormula::parse("delta(x)", &store()).unwrap();
+        let values = run(&formula, &[1.0, 3.0, 6.0]);
+        assert!(values[0].is_nan());
+        assert_close(&values[1..], &[2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_ema() {
+        let formula = RootFormula::parse("ema(x, 0.5)", &store()).unwrap();
+        assert_close(&run(&formula, &[2.0, 4.0, 0.0]), &[2.0, 3.0, 1.5]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sma() {
+        let formula = RootFormula::parse("sma(x, 2)", &store()).unwrap();
+        assert_close(&run(&formula, &[2.0, 4.0, 8.0]), &[2.0, 3.0, 6.0]);
+        let formula = RootFormula::parse("sma(x, 0.5)", &store()).unwrap();
+        assert!(run(&formula, &[1.0])[0].is_nan());
+    }
+
+    #[test]
+    fn test_integrate_and_reset() {
+        let formula = RootFormula::parse("1 + integrate_dt(x, 0.5)", &store()).unwrap();
+        assert_close(&run(&formula, &[2.0, 4.0]), &[2.0, 4.0]);
+        formula.reset();
+        assert_close(&run(&formula, &[2.0]), &[2.0]);
+    }
+
+    #[test]
+    fn test_owned_and_shared_state() {
+        let counter = Arc::new(RootFormula::parse("integrate_dt(1, 1)", &store()).unwrap());
+        let mut shared = RootFormula::new(Variable::new("c"));
+        shared.set_variable_shared(&Variable::new("c"), &counter);
+        let mut owned = RootFormula::new(Variable::new("c"));
+        owned.set_variable_owned(&Variable::new("c"), &counter);
+
+        assert_close(&run(&shared, &[0.0, 0.0]), &[1.0, 2.0]);
+        assert_close(&run(&counter, &[0.0]), &[3.0]);
+        assert_close(&run(&owned, &[0.0]), &[1.0]);
+        shared.reset();
+        assert_close(&run(&counter, &[0.0]), &[1.0]);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::formulas::{Function, FunctionLike, ParserError};
-use crate::function_stores::{ArgumentBounds, GetFunction, Parser, RegisterParser};
+use crate::formulas::{Function, FunctionLike, Limits, ParserError};
+use crate::function_stores::{
+    ArgumentBounds, GetFunction, Parser, RegisterParser, SeedSequence, SetLimits, SetSeed,
+};
 // We can use std, because this module is not imported, when no_std feature is enabled.
 use std::collections::hash_map::Keys;
 use std::collections::HashMap;
@@ -10,12 +12,20 @@ type InnerFunctionParser =
 
 /// Function store based on [`HashMap`].
 #[derive(Default, Clone, Debug)]
-pub struct HashMapFunctionStore(HashMap<&'static str, (InnerFunctionParser, ArgumentBounds)>);
+pub struct HashMapFunctionStore {
+    functions: HashMap<&'static str, (InnerFunctionParser, ArgumentBounds)>,
+    seed: Option<SeedSequence>,
+    limits: Option<Limits>,
+}
 
 impl HashMapFunctionStore {
     /// Creates an empty `HashMapFunctionStore`.
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            functions: HashMap::new(),
+            seed: None,
+            limits: None,
+        }
     }
 }
 
@@ -37,22 +47,32 @@ impl<'a> GetFunction<'a> for HashMapFunctionStore {
         &'b self,
         formula_name: &str,
     ) -> Option<(Box<Parser<'b>>, ArgumentBounds)> {
-        self.0.get(formula_name).map(move |(parser, bounds)| {
-            (
-                Box::new(move |arguments: &[&str]| (*parser)(arguments, self)) as Box<Parser>,
-                bounds.clone(),
-            )
-        })
+        self.functions
+            .get(formula_name)
+            .map(move |(parser, bounds)| {
+                (
+                    Box::new(move |arguments: &[&str]| (*parser)(arguments, self)) as Box<Parser>,
+                    bounds.clone(),
+                )
+            })
     }
 
     fn iter(&'a self) -> Self::Iter {
-        FunctionNamesIterator(self.0.keys())
+        FunctionNamesIterator(self.functions.keys())
+    }
+
+    fn next_seed(&self) -> Option<u64> {
+        self.seed.as_ref().map(SeedSequence::next_seed)
+    }
+
+    fn limits(&self) -> Option<&Limits> {
+        self.limits.as_ref()
     }
 }
 
 impl RegisterParser for HashMapFunctionStore {
     fn register<T: Function + 'static>(&mut self) {
-        self.0.insert(
+        self.functions.insert(
             T::NAME,
             (
                 T::parse_into_box,
@@ -64,3 +84,15 @@ impl RegisterParser for HashMapFunctionStore {
         );
     }
 }
+
+impl SetSeed for HashMapFunctionStore {
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(SeedSequence::new(seed));
+    }
+}
+
+impl SetLimits for HashMapFunctionStore {
+    fn set_limits(&mut self, limits: Limits) {
+        self.limits = Some(limits);
+    }
+}
@@ -6,9 +6,11 @@ pub use empty_store::EmptyFunctionStore;
 mod hashmap_store;
 #[cfg(feature = "std")]
 pub use hashmap_store::HashMapFunctionStore;
+mod seed;
+pub(crate) use seed::SeedSequence;
 
 use crate::__lib::boxed::Box;
-use crate::formulas::{Function, FunctionLike, ParserError};
+use crate::formulas::{Function, FunctionLike, Limits, ParserError};
 
 /// Provides information about bounds on arguments number of function.
 #[derive(Clone, Debug)]
@@ -34,6 +36,19 @@ pub trait GetFunction<'a> {
 
     /// Methode to get iterator over function names stored in function store.
     fn iter(&'a self) -> Self::Iter;
+
+    /// Returns seed for the next random function parsed with this function store,
+    /// or `None` if random functions should be seeded from entropy.
+    #[inline]
+    fn next_seed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns limits, which expressions parsed with this function store must not exceed, `None` if there are no limits.
+    #[inline]
+    fn limits(&self) -> Option<&Limits> {
+        None
+    }
 }
 
 /// Trait for registering new functions in function store.
@@ -41,3 +56,16 @@ pub trait RegisterParser {
     /// Methode for registering new functions in function store.
     fn register<T: Function + 'static>(&mut self);
 }
+
+/// Trait for setting seed of random functions parsed with function store.
+pub trait SetSeed {
+    /// Sets seed, from which seeds of random functions parsed after this call are derived.
+    /// Parsing same expressions in the same order after setting the same seed results in the same random sequences.
+    fn set_seed(&mut self, seed: u64);
+}
+
+/// Trait for setting limits of expressions parsed with function store.
+pub trait SetLimits {
+    /// Sets limits, which are checked by every following parse, including parse of function arguments.
+    fn set_limits(&mut self, limits: Limits);
+}
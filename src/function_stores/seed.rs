@@ -0,0 +1,35 @@
+use crate::formulas::random::{split_mix, GOLDEN_GAMMA};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Sequence of seeds handed out to random functions, while they are parsed.
+#[derive(Debug)]
+pub(crate) struct SeedSequence {
+    seed: u64,
+    counter: AtomicU64,
+}
+
+impl SeedSequence {
+    pub(crate) const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn next_seed(&self) -> u64 {
+        let index = self.counter.fetch_add(1, Ordering::Relaxed);
+        split_mix(
+            self.seed
+                .wrapping_add(index.wrapping_add(1).wrapping_mul(GOLDEN_GAMMA)),
+        )
+    }
+}
+
+impl Clone for SeedSequence {
+    fn clone(&self) -> Self {
+        Self {
+            seed: self.seed,
+            counter: AtomicU64::new(self.counter.load(Ordering::Relaxed)),
+        }
+    }
+}
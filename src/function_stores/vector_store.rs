@@ -2,8 +2,10 @@ use crate::__lib::boxed::Box;
 use crate::__lib::fmt::Debug;
 use crate::__lib::slice::Iter;
 use crate::__lib::vec::Vec;
-use crate::formulas::{Function, FunctionLike, ParserError};
-use crate::function_stores::{ArgumentBounds, GetFunction, Parser, RegisterParser};
+use crate::formulas::{Function, FunctionLike, Limits, ParserError};
+use crate::function_stores::{
+    ArgumentBounds, GetFunction, Parser, RegisterParser, SeedSequence, SetLimits, SetSeed,
+};
 
 #[cfg(all(doc, feature = "std"))]
 use crate::function_stores::HashMapFunctionStore;
@@ -13,12 +15,20 @@ type InnerFunctionParser =
 
 /// Function store based on [`Vec`]. Might be faster then [`HashMapFunctionStore`] for small number of functions.
 #[derive(Default, Clone, Debug)]
-pub struct VectorFunctionStore(Vec<(&'static str, (InnerFunctionParser, ArgumentBounds))>);
+pub struct VectorFunctionStore {
+    functions: Vec<(&'static str, (InnerFunctionParser, ArgumentBounds))>,
+    seed: Option<SeedSequence>,
+    limits: Option<Limits>,
+}
 
 impl VectorFunctionStore {
     /// Creates an empty `VectorFunctionStore`.
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self {
+            functions: Vec::new(),
+            seed: None,
+            limits: None,
+        }
     }
 }
 
@@ -42,7 +52,7 @@ impl<'a> GetFunction<'a> for VectorFunctionStore {
         &'b self,
         formula_name: &str,
     ) -> Option<(Box<Parser<'b>>, ArgumentBounds)> {
-        for (name, (parser, bounds)) in &self.0 {
+        for (name, (parser, bounds)) in &self.functions {
             if *name == formula_name {
                 return Some((
                     Box::new(move |arguments: &[&str]| (*parser)(arguments, self)) as Box<Parser>,
@@ -54,13 +64,21 @@ impl<'a> GetFunction<'a> for VectorFunctionStore {
     }
 
     fn iter(&'a self) -> Self::Iter {
-        FunctionNamesIterator(self.0.iter())
+        FunctionNamesIterator(self.functions.iter())
+    }
+
+    fn next_seed(&self) -> Option<u64> {
+        self.seed.as_ref().map(SeedSequence::next_seed)
+    }
+
+    fn limits(&self) -> Option<&Limits> {
+        self.limits.as_ref()
     }
 }
 
 impl RegisterParser for VectorFunctionStore {
     fn register<T: Function + 'static>(&mut self) {
-        self.0.push((
+        self.functions.push((
             T::NAME,
             (
                 T::parse_into_box,
@@ -72,3 +90,15 @@ impl RegisterParser for VectorFunctionStore {
         ));
     }
 }
+
+impl SetSeed for VectorFunctionStore {
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(SeedSequence::new(seed));
+    }
+}
+
+impl SetLimits for VectorFunctionStore {
+    fn set_limits(&mut self, limits: Limits) {
+        self.limits = Some(limits);
+    }
+}
@@ -100,6 +100,18 @@
 //!     fn clone_into_box(&self) -> Box<dyn FunctionLike> {
 //!         Box::new(Self {arguments: self.arguments.iter().map(|x| RootFormula::new(x.clone_into_box())).collect()})
 //!     }
+//!
+//!     fn reset(&self) {
+//!         for val in self.arguments.iter() {
+//!             val.reset();
+//!         }
+//!     }
+//!
+//!     fn collect_variables(&self, variables: &mut Vec<Variable>) {
+//!         for val in self.arguments.iter() {
+//!             val.collect_variables(variables);
+//!         }
+//!     }
 //! }
 //!
 //! impl Function for Average {
@@ -150,12 +162,32 @@
 //! ```
 
 mod context;
+/// Provides least-squares fitting of formula parameters to data.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod fitting;
+#[cfg(any(feature = "std", feature = "libm"))]
+mod float;
 
 /// Provides basic ways to store function parsers and traits to implement new once.
 pub mod function_stores;
 
 /// Provides basic set of functions and traits to implement new once.
 pub mod formulas;
+/// Provides integration of ordinary differential equations defined by formulas.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod ode;
+/// Provides minimization of formulas.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod optimization;
+/// Provides evaluation of formulas and variables on several threads.
+#[cfg(feature = "std")]
+pub mod parallel;
+
+/// Provides spreadsheet of cells with formulas referencing each other.
+pub mod sheet;
+/// Provides root finding for formulas and systems of equations.
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod solvers;
 mod tokens;
 
 /// Provides basic ways to store variables and traits to implement new once.
@@ -191,10 +223,14 @@ pub mod __lib {
         pub use std::boxed::Box;
     }
     pub mod string {
+        #[cfg(not(feature = "std"))]
+        pub use alloc::format;
         #[cfg(not(feature = "std"))]
         #[allow(clippy::module_name_repetitions)]
         pub use alloc::string::{String, ToString};
         #[cfg(feature = "std")]
+        pub use std::format;
+        #[cfg(feature = "std")]
         #[allow(clippy::module_name_repetitions)]
         pub use std::string::{String, ToString};
     }
@@ -206,9 +242,9 @@ pub mod __lib {
     }
     pub mod fmt {
         #[cfg(not(feature = "std"))]
-        pub use core::fmt::{Debug, Display, Formatter, Result};
+        pub use core::fmt::{Debug, Display, Formatter, Result, Write};
         #[cfg(feature = "std")]
-        pub use std::fmt::{Debug, Display, Formatter, Result};
+        pub use std::fmt::{Debug, Display, Formatter, Result, Write};
     }
     pub mod error {
         #[cfg(all(feature = "std", nightly))]
@@ -232,16 +268,32 @@ pub mod __lib {
     }
     pub mod collections {
         #[cfg(not(feature = "std"))]
-        pub use alloc::collections::VecDeque;
+        pub use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
         #[cfg(feature = "std")]
-        pub use std::collections::VecDeque;
+        pub use std::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
     }
     pub mod vec {
+        #[cfg(not(feature = "std"))]
+        pub use alloc::vec;
         #[cfg(not(feature = "std"))]
         pub use alloc::vec::Vec;
         #[cfg(feature = "std")]
+        pub use std::vec;
+        #[cfg(feature = "std")]
         pub use std::vec::Vec;
     }
+    pub mod cell {
+        #[cfg(not(feature = "std"))]
+        pub use core::cell::{Cell, RefCell};
+        #[cfg(feature = "std")]
+        pub use std::cell::{Cell, RefCell};
+    }
+    pub mod rc {
+        #[cfg(not(feature = "std"))]
+        pub use alloc::rc::Rc;
+        #[cfg(feature = "std")]
+        pub use std::rc::Rc;
+    }
     pub mod convert {
         #[cfg(not(feature = "std"))]
         pub use core::convert::{identity, TryInto};
@@ -256,8 +308,8 @@ pub mod __lib {
     }
     pub mod slice {
         #[cfg(not(feature = "std"))]
-        pub use core::slice::Iter;
+        pub use core::slice::{from_ref, Iter};
         #[cfg(feature = "std")]
-        pub use std::slice::Iter;
+        pub use std::slice::{from_ref, Iter};
     }
 }
@@ -0,0 +1,481 @@
+// `mul_add` is not available without std, number of states is small enough to be converted to float.
+// Last step ends exactly at the end of time span, so time is compared exactly.
+#![allow(
+    clippy::suboptimal_flops,
+    clippy::cast_precision_loss,
+    clippy::float_cmp
+)]
+
+use crate::__lib::string::format;
+use crate::__lib::vec::{vec, Vec};
+use crate::float::{abs, powf, sqrt};
+use crate::formulas::binding::MultiBinding;
+use crate::formulas::{EvaluationError, MathError, RootFormula};
+use crate::variable_stores::{GetVariable, Variable};
+
+/// Method of integration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Method {
+    /// Classic Runge-Kutta method of fourth order with fixed step.
+    Rk4,
+    /// Dormand-Prince method of fifth order with adaptive step.
+    #[default]
+    DormandPrince,
+}
+
+/// Options of integration.
+#[derive(Debug, Clone, Copy)]
+pub struct OdeOptions {
+    method: Method,
+    step: Option<f64>,
+    tolerance: f64,
+    max_steps: usize,
+}
+
+impl Default for OdeOptions {
+    fn default() -> Self {
+        Self {
+            method: Method::default(),
+            step: None,
+            tolerance: 1e-8,
+            max_steps: 100_000,
+        }
+    }
+}
+
+impl OdeOptions {
+    /// Creates default options: Dormand-Prince method, tolerance `1e-8` and maximum 100000 steps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets method of integration.
+    #[must_use]
+    pub const fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets step of fixed step methods, or initial step of adaptive methods.
+    ///
+    /// By default step is hundredth of time span.
+    #[must_use]
+    pub const fn with_step(mut self, step: f64) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Sets tolerance of adaptive methods, it is used both as absolute and relative tolerance of local error.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets maximum number of steps.
+    #[must_use]
+    pub const fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+}
+
+/// Times and states of system at every step of integration.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    times: Vec<f64>,
+    // states stored one after another
+    states: Vec<f64>,
+    dimension: usize,
+}
+
+impl Trajectory {
+    /// Returns times of steps, including start and end of time span.
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// Returns state at step with `index`, in the same order as state variables.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if index is not less than number of steps.
+    pub fn state(&self, index: usize) -> &[f64] {
+        &self.states[index * self.dimension..(index + 1) * self.dimension]
+    }
+
+    /// Returns state at the end of time span.
+    pub fn final_state(&self) -> &[f64] {
+        self.state(self.times.len() - 1)
+    }
+
+    /// Returns number of recorded steps.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Returns `true` if there are no recorded steps.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    fn push(&mut self, time: f64, state: &[f64]) {
+        self.times.push(time);
+        self.states.extend_from_slice(state);
+    }
+}
+
+// Derivatives of state as function of time and state.
+struct System<'a> {
+    derivatives: &'a [(Variable, RootFormula)],
+    binding: MultiBinding<'a>,
+    // time followed by state
+    point: Vec<f64>,
+}
+
+impl System<'_> {
+    fn eval(&mut self, time: f64, state: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+        self.point[0] = time;
+        self.point[1..].copy_from_slice(state);
+        self.binding.set(&self.point);
+        self.derivatives
+            .iter()
+            .map(|(_, derivative)| self.binding.eval(derivative))
+            .collect()
+    }
+}
+
+// `state + step * sum(coefficients[i] * slopes[i])`
+fn combine(state: &[f64], step: f64, coefficients: &[f64], slopes: &[Vec<f64>]) -> Vec<f64> {
+    state
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            x + step
+                * coefficients
+                    .iter()
+                    .zip(slopes)
+                    .map(|(coefficient, slope)| coefficient * slope[i])
+                    .sum::<f64>()
+        })
+        .collect()
+}
+
+fn ode_error(message: &str) -> EvaluationError {
+    EvaluationError::MathError(MathError::new(format!("ode, {message}")))
+}
+
+fn rk4(
+    system: &mut System<'_>,
+    trajectory: &mut Trajectory,
+    mut time: f64,
+    mut state: Vec<f64>,
+    end: f64,
+    step: f64,
+    max_steps: usize,
+) -> Result<(), EvaluationError> {
+    let direction = if end < time { -1.0 } else { 1.0 };
+    let mut steps = 0;
+    let mut finished = time == end;
+    while !finished {
+        if steps == max_steps {
+            return Err(ode_error(&format!(
+                "it did not reach end in {max_steps} steps"
+            )));
+        }
+        steps += 1;
+        let remaining = end - time;
+        let (h, last) = if abs(remaining) <= step * (1.0 + f64::EPSILON) {
+            (remaining, true)
+        } else {
+            (direction * step, false)
+        };
+        let mut slopes = vec![system.eval(time, &state)?];
+        for (node, weight) in [(0.5, 0.5), (0.5, 0.5), (1.0, 1.0)] {
+            let stage_state = combine(&state, h * weight, &[1.0], &slopes[slopes.len() - 1..]);
+            slopes.push(system.eval(time + node * h, &stage_state)?);
+        }
+        state = combine(
+            &state,
+            h,
+            &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+            &slopes,
+        );
+        time = if last { end } else { time + h };
+        finished = last;
+        trajectory.push(time, &state);
+    }
+    Ok(())
+}
+
+// Dormand-Prince tableau
+const NODES: [f64; 6] = [0.2, 0.3, 0.8, 8.0 / 9.0, 1.0, 1.0];
+const STAGES: [&[f64]; 6] = [
+    &[0.2],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+    ],
+    &[
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+    ],
+    &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+// difference of weights of solutions of fifth and fourth order
+const ERROR_WEIGHTS: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339_200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+#[allow(clippy::too_many_arguments)]
+fn dormand_prince(
+    system: &mut System<'_>,
+    trajectory: &mut Trajectory,
+    mut time: f64,
+    mut state: Vec<f64>,
+    end: f64,
+    mut step: f64,
+    tolerance: f64,
+    max_steps: usize,
+) -> Result<(), EvaluationError> {
+    const SAFETY: f64 = 0.9;
+    const MIN_FACTOR: f64 = 0.2;
+    const MAX_FACTOR: f64 = 5.0;
+
+    let direction = if end < time { -1.0 } else { 1.0 };
+    let mut slope = system.eval(time, &state)?;
+    let mut steps = 0;
+    let mut finished = time == end;
+    while !finished {
+        if steps == max_steps {
+            return Err(ode_error(&format!(
+                "it did not reach end in {max_steps} steps"
+            )));
+        }
+        steps += 1;
+        let remaining = end - time;
+        let last = abs(remaining) <= step * (1.0 + f64::EPSILON);
+        let h = if last { remaining } else { direction * step };
+        if abs(h) <= 16.0 * f64::EPSILON * abs(time).max(1.0) {
+            return Err(ode_error(&format!("step became too small at time {time}")));
+        }
+
+        let mut slopes = vec![slope.clone()];
+        for (node, coefficients) in NODES.iter().zip(STAGES) {
+            let stage_state = combine(&state, h, coefficients, &slopes);
+            slopes.push(system.eval(time + node * h, &stage_state)?);
+        }
+        // solution of fifth order is the last stage, its slope is reused by next step
+        let candidate = combine(&state, h, STAGES[5], &slopes);
+        let error = combine(&vec![0.0; state.len()], h, &ERROR_WEIGHTS, &slopes);
+        let norm = sqrt(
+            error
+                .iter()
+                .zip(state.iter().zip(&candidate))
+                .map(|(error, (old, new))| {
+                    let scaled = error / (tolerance * (1.0 + abs(*old).max(abs(*new))));
+                    scaled * scaled
+                })
+                .sum::<f64>()
+                / state.len().max(1) as f64,
+        );
+        if norm.is_nan() {
+            return Err(ode_error(&format!(
+                "derivatives are not defined at time {time}"
+            )));
+        }
+
+        let factor = if norm == 0.0 {
+            MAX_FACTOR
+        } else {
+            (SAFETY * powf(norm, -0.2)).clamp(MIN_FACTOR, MAX_FACTOR)
+        };
+        if norm <= 1.0 {
+            time = if last { end } else { time + h };
+            finished = last;
+            state = candidate;
+            slope = slopes.pop().unwrap_or_default();
+            trajectory.push(time, &state);
+        }
+        step = abs(h) * factor.min(if norm <= 1.0 { MAX_FACTOR } else { 1.0 });
+    }
+    Ok(())
+}
+
+/// Integrates system of ordinary differential equations over time span from `start` to `end`.
+///
+/// Every derivative is a pair of state variable and formula of its derivative by `time`.
+/// Initial state is taken from `store`, as well as other variables.
+///
+/// # Errors
+///
+/// Will return Err if derivatives fail to evaluate, initial state is not in `store`,
+/// maximum number of steps is reached or adaptive step becomes too small.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::ode::{integrate, OdeOptions};
+/// use evaluatorrs::variable_stores::{SetVariable, Variable, VectorVariableStore};
+///
+/// let derivatives = [(Variable::new("x"), RootFormula::parse("k * x", &EmptyFunctionStore).unwrap())];
+/// let mut store = VectorVariableStore::new();
+/// store.set("x", 1.0);
+/// store.set("k", 1.0);
+/// let trajectory = integrate(&derivatives, &Variable::new("t"), 0.0, 1.0, &store, &OdeOptions::new()).unwrap();
+/// assert!((trajectory.final_state()[0] - std::f64::consts::E).abs() < 1e-6);
+/// ```
+pub fn integrate(
+    derivatives: &[(Variable, RootFormula)],
+    time: &Variable,
+    start: f64,
+    end: f64,
+    store: &dyn GetVariable,
+    options: &OdeOptions,
+) -> Result<Trajectory, EvaluationError> {
+    if !(start.is_finite() && end.is_finite()) {
+        return Err(ode_error("time span is not finite"));
+    }
+    let step = options.step.map_or_else(|| abs(end - start) / 100.0, abs);
+    if !(step > 0.0 || start == end) {
+        return Err(ode_error("step is not positive"));
+    }
+    let state = derivatives
+        .iter()
+        .map(|(variable, _)| store.eval(variable))
+        .collect::<Result<Vec<_>, _>>()?;
+    let variables = core::iter::once(time.clone())
+        .chain(derivatives.iter().map(|(variable, _)| variable.clone()))
+        .collect::<Vec<_>>();
+    let mut system = System {
+        derivatives,
+        binding: MultiBinding::new(&variables, store),
+        point: vec![0.0; variables.len()],
+    };
+    let mut trajectory = Trajectory {
+        times: Vec::new(),
+        states: Vec::new(),
+        dimension: state.len(),
+    };
+    trajectory.push(start, &state);
+    match options.method {
+        Method::Rk4 => rk4(
+            &mut system,
+            &mut trajectory,
+            start,
+            state,
+            end,
+            step,
+            options.max_steps,
+        )?,
+        Method::DormandPrince => dormand_prince(
+            &mut system,
+            &mut trajectory,
+            start,
+            state,
+            end,
+            step,
+            options.tolerance,
+            options.max_steps,
+        )?,
+    }
+    Ok(trajectory)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::RootFormula;
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::ode::{integrate, Method, OdeOptions};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+    use core::f64::consts::PI;
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    #[test]
+    fn test_decay() {
+        let derivatives = [(Variable::new("x"), parse("0 - k * x"))];
+        let mut store = VectorVariableStore::new();
+        store.set("x", 1.0);
+        store.set("k", 1.0);
+        for options in [
+            OdeOptions::new().with_method(Method::Rk4).with_step(0.01),
+            OdeOptions::new(),
+        ] {
+            let trajectory = integrate(
+                &derivatives,
+                &Variable::new("t"),
+                0.0,
+                1.0,
+                &store,
+                &options,
+            )
+            .unwrap();
+            assert_eq!(trajectory.times()[0], 0.0);
+            assert_eq!(trajectory.times()[trajectory.len() - 1], 1.0);
+            assert_eq!(trajectory.state(0), [1.0]);
+            // exp(-1)
+            let error = trajectory.final_state()[0] - 0.367_879_441_171_442_3;
+            assert!(error.abs() < 1e-8, "{options:?}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_oscillator() {
+        let derivatives = [
+            (Variable::new("x"), parse("v")),
+            (Variable::new("v"), parse("0 - x")),
+        ];
+        let mut store = VectorVariableStore::new();
+        store.set("x", 1.0);
+        store.set("v", 0.0);
+        let options = OdeOptions::new().with_tolerance(1e-10);
+        let trajectory =
+            integrate(&derivatives, &Variable::new("t"), 0.0, PI, &store, &options).unwrap();
+        assert!((trajectory.final_state()[0] + 1.0).abs() < 1e-8);
+        assert!(trajectory.final_state()[1].abs() < 1e-8);
+        let backwards =
+            integrate(&derivatives, &Variable::new("t"), PI, 0.0, &store, &options).unwrap();
+        assert!((backwards.final_state()[0] + 1.0).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_errors() {
+        let derivatives = [(Variable::new("x"), parse("t * y"))];
+        let mut store = VectorVariableStore::new();
+        store.set("x", 1.0);
+        let t = Variable::new("t");
+        let options = OdeOptions::new();
+        assert!(integrate(&derivatives, &t, 0.0, 1.0, &store, &options).is_err());
+        store.set("y", 1.0);
+        assert!(integrate(&derivatives, &t, 0.0, f64::INFINITY, &store, &options).is_err());
+        let limited = options.with_max_steps(3);
+        assert!(integrate(&derivatives, &t, 0.0, 1.0, &store, &limited).is_err());
+        let trajectory = integrate(&derivatives, &t, 0.0, 1.0, &store, &options).unwrap();
+        assert!((trajectory.final_state()[0] - 1.5).abs() < 1e-9);
+    }
+}
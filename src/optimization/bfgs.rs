@@ -0,0 +1,169 @@
+// `mul_add` is not available without std.
+#![allow(clippy::suboptimal_flops)]
+
+use crate::__lib::vec::{vec, Vec};
+use crate::float::{abs, sqrt};
+use crate::formulas::EvaluationError;
+use crate::optimization::{MinimizeOptions, Minimum, Objective};
+
+const MAX_BACKTRACKS: usize = 40;
+const DECREASE: f64 = 1e-4;
+// step of central differences, approximately cube root of machine epsilon
+const GRADIENT_STEP: f64 = 6e-6;
+
+fn dot(first: &[f64], second: &[f64]) -> f64 {
+    first.iter().zip(second).map(|(x, y)| x * y).sum()
+}
+
+// gradient by central differences, one sided differences are used at bounds
+// and variables with equal bounds are fixed, so they get zero gradient
+fn gradient(objective: &mut Objective<'_>, point: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+    let mut gradient = Vec::with_capacity(point.len());
+    let mut shifted = point.to_vec();
+    for i in 0..point.len() {
+        let step = GRADIENT_STEP * abs(point[i]).max(1.0);
+        let (lower, upper) = objective.bounds[i];
+        if lower >= upper {
+            gradient.push(0.0);
+            continue;
+        }
+        let forward = (point[i] + step).min(upper);
+        let backward = (point[i] - step).max(lower);
+        shifted[i] = forward;
+        let forward_value = objective.value(&shifted)?;
+        shifted[i] = backward;
+        let backward_value = objective.value(&shifted)?;
+        shifted[i] = point[i];
+        gradient.push((forward_value - backward_value) / (forward - backward));
+    }
+    Ok(gradient)
+}
+
+// component can not move, if variable is at bound and gradient pushes it outside
+fn is_blocked(objective: &Objective<'_>, point: &[f64], gradient: &[f64], i: usize) -> bool {
+    let (lower, upper) = objective.bounds[i];
+    (point[i] <= lower && gradient[i] > 0.0) || (point[i] >= upper && gradient[i] < 0.0)
+}
+
+// update of inverse Hessian approximation by BFGS formula
+fn update_inverse_hessian(inverse_hessian: &mut [f64], step: &[f64], change: &[f64]) {
+    let n = step.len();
+    let curvature = dot(step, change);
+    if curvature <= f64::EPSILON * sqrt(dot(step, step) * dot(change, change)) {
+        return;
+    }
+    let rho = 1.0 / curvature;
+    let hessian_change = (0..n)
+        .map(|i| dot(&inverse_hessian[i * n..(i + 1) * n], change))
+        .collect::<Vec<_>>();
+    let change_hessian_change = dot(change, &hessian_change);
+    for i in 0..n {
+        for j in 0..n {
+            inverse_hessian[i * n + j] += rho
+                * ((1.0 + rho * change_hessian_change) * step[i] * step[j]
+                    - hessian_change[i] * step[j]
+                    - step[i] * hessian_change[j]);
+        }
+    }
+}
+
+pub(super) fn minimize(
+    objective: &mut Objective<'_>,
+    start: Vec<f64>,
+    options: &MinimizeOptions,
+) -> Result<Minimum, EvaluationError> {
+    let n = start.len();
+    let mut point = start;
+    let mut value = objective.value(&point)?;
+    let mut gradient_at_point = gradient(objective, &point)?;
+    let mut inverse_hessian = vec![0.0; n * n];
+    for i in 0..n {
+        inverse_hessian[i * n + i] = 1.0;
+    }
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < options.max_iterations {
+        let projected_gradient = (0..n)
+            .map(|i| {
+                if is_blocked(objective, &point, &gradient_at_point, i) {
+                    0.0
+                } else {
+                    gradient_at_point[i]
+                }
+            })
+            .collect::<Vec<_>>();
+        let gradient_norm = projected_gradient
+            .iter()
+            .fold(0.0, |max: f64, x| max.max(abs(*x)));
+        if gradient_norm <= sqrt(options.tolerance) * abs(value).max(1.0) {
+            converged = true;
+            break;
+        }
+        iterations += 1;
+
+        let mut direction = (0..n)
+            .map(|i| -dot(&inverse_hessian[i * n..(i + 1) * n], &projected_gradient))
+            .collect::<Vec<_>>();
+        for (i, component) in direction.iter_mut().enumerate() {
+            if is_blocked(objective, &point, &gradient_at_point, i) {
+                *component = 0.0;
+            }
+        }
+        if dot(&direction, &projected_gradient) >= 0.0 {
+            // approximation of Hessian lost positive definiteness, so steepest descent is used
+            direction = projected_gradient.iter().map(|x| -x).collect();
+            inverse_hessian.fill(0.0);
+            for i in 0..n {
+                inverse_hessian[i * n + i] = 1.0;
+            }
+        }
+
+        let mut scale = 1.0;
+        let mut accepted = None;
+        for _ in 0..MAX_BACKTRACKS {
+            let mut candidate = point
+                .iter()
+                .zip(&direction)
+                .map(|(x, d)| x + scale * d)
+                .collect::<Vec<_>>();
+            objective.clamp(&mut candidate);
+            let candidate_value = objective.value(&candidate)?;
+            let step = candidate
+                .iter()
+                .zip(&point)
+                .map(|(new, old)| new - old)
+                .collect::<Vec<_>>();
+            if candidate_value <= value + DECREASE * dot(&projected_gradient, &step) {
+                accepted = Some((candidate, candidate_value, step));
+                break;
+            }
+            scale /= 2.0;
+        }
+        let Some((candidate, candidate_value, step)) = accepted else {
+            // no decrease along descent direction, point is minimum within precision of gradient
+            converged = true;
+            break;
+        };
+        let candidate_gradient = gradient(objective, &candidate)?;
+        let change = candidate_gradient
+            .iter()
+            .zip(&gradient_at_point)
+            .map(|(new, old)| new - old)
+            .collect::<Vec<_>>();
+        update_inverse_hessian(&mut inverse_hessian, &step, &change);
+        let value_change = abs(value - candidate_value);
+        point = candidate;
+        value = candidate_value;
+        gradient_at_point = candidate_gradient;
+        if value_change <= options.tolerance * abs(value).max(1.0) {
+            converged = true;
+            break;
+        }
+    }
+    Ok(Minimum {
+        values: point,
+        value,
+        iterations,
+        converged,
+    })
+}
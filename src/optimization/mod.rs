@@ -0,0 +1,282 @@
+use crate::__lib::vec::Vec;
+use crate::formulas::binding::MultiBinding;
+use crate::formulas::{EvaluationError, RootFormula};
+use crate::variable_stores::{GetVariable, Variable};
+
+mod bfgs;
+mod nelder_mead;
+
+/// Minimization method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Method {
+    /// Derivative-free Nelder-Mead simplex method.
+    #[default]
+    NelderMead,
+    /// Quasi-Newton BFGS method with numeric gradient.
+    Bfgs,
+}
+
+/// Options of minimization.
+#[derive(Debug, Clone)]
+pub struct MinimizeOptions {
+    method: Method,
+    tolerance: f64,
+    max_iterations: usize,
+    bounds: Vec<(Variable, f64, f64)>,
+}
+
+impl Default for MinimizeOptions {
+    fn default() -> Self {
+        Self {
+            method: Method::default(),
+            tolerance: 1e-10,
+            max_iterations: 1000,
+            bounds: Vec::new(),
+        }
+    }
+}
+
+impl MinimizeOptions {
+    /// Creates default options: Nelder-Mead method, tolerance `1e-10`, maximum 1000 iterations and no bounds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets minimization method.
+    #[must_use]
+    pub const fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets tolerance, minimization stops when value of formula changes less than tolerance,
+    /// relative to `max(1, |value|)`.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets maximum number of iterations.
+    #[must_use]
+    pub const fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Restricts `variable` to interval `[lower, upper]`, infinite values can be used for one sided bounds.
+    /// Variable with equal bounds is fixed.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `lower` is greater than `upper` or any of them is NAN.
+    #[must_use]
+    pub fn with_bounds(mut self, variable: impl Into<Variable>, lower: f64, upper: f64) -> Self {
+        assert!(lower <= upper, "lower bound is greater than upper bound");
+        self.bounds.push((variable.into(), lower, upper));
+        self
+    }
+}
+
+/// Found minimum and information about convergence.
+#[derive(Debug, Clone)]
+pub struct Minimum {
+    values: Vec<f64>,
+    value: f64,
+    iterations: usize,
+    converged: bool,
+}
+
+impl Minimum {
+    /// Returns values of optimized variables at minimum, in the same order as variables.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Returns value of formula at minimum.
+    pub const fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns number of iterations made.
+    pub const fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns `false` if maximum number of iterations was reached before tolerance.
+    pub const fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+// Formula as function of optimized variables, restricted to bounds.
+struct Objective<'a> {
+    formula: &'a RootFormula,
+    binding: MultiBinding<'a>,
+    bounds: Vec<(f64, f64)>,
+}
+
+impl Objective<'_> {
+    // NAN is treated as infinity, so methods move away from points where formula is not defined
+    fn value(&mut self, point: &[f64]) -> Result<f64, EvaluationError> {
+        self.binding.set(point);
+        let value = self.binding.eval(self.formula)?;
+        Ok(if value.is_nan() { f64::INFINITY } else { value })
+    }
+
+    fn clamp(&self, point: &mut [f64]) {
+        for (x, (lower, upper)) in point.iter_mut().zip(&self.bounds) {
+            *x = x.max(*lower).min(*upper);
+        }
+    }
+}
+
+/// Minimizes `formula` over `variables`, starting values of variables and other variables are taken from `store`.
+///
+/// # Errors
+///
+/// Will return Err if formula fails to evaluate or starting value of variable is not in `store`.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::optimization::{minimize, MinimizeOptions};
+/// use evaluatorrs::variable_stores::{SetVariable, Variable, VectorVariableStore};
+///
+/// let formula = RootFormula::parse("(x - 1)^2 + (y + 2)^2", &EmptyFunctionStore).unwrap();
+/// let mut store = VectorVariableStore::new();
+/// store.set("x", 0.0);
+/// store.set("y", 0.0);
+/// let variables = [Variable::new("x"), Variable::new("y")];
+/// let options = MinimizeOptions::new().with_bounds("y", -1.0, 1.0);
+/// let minimum = minimize(&formula, &variables, &store, &options).unwrap();
+/// assert!((minimum.values()[0] - 1.0).abs() < 1e-4);
+/// assert!((minimum.values()[1] + 1.0).abs() < 1e-4);
+/// ```
+pub fn minimize(
+    formula: &RootFormula,
+    variables: &[Variable],
+    store: &dyn GetVariable,
+    options: &MinimizeOptions,
+) -> Result<Minimum, EvaluationError> {
+    let bounds = variables
+        .iter()
+        .map(|variable| {
+            options
+                .bounds
+                .iter()
+                .rev()
+                .find(|(name, _, _)| name == variable)
+                .map_or((f64::NEG_INFINITY, f64::INFINITY), |(_, lower, upper)| {
+                    (*lower, *upper)
+                })
+        })
+        .collect();
+    let mut start = variables
+        .iter()
+        .map(|variable| store.eval(variable))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut objective = Objective {
+        formula,
+        binding: MultiBinding::new(variables, store),
+        bounds,
+    };
+    objective.clamp(&mut start);
+    match options.method {
+        Method::NelderMead => nelder_mead::minimize(&mut objective, &start, options),
+        Method::Bfgs => bfgs::minimize(&mut objective, start, options),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::RootFormula;
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::optimization::{minimize, Method, MinimizeOptions};
+    use crate::variable_stores::{SetVariable, Variable, VectorVariableStore};
+
+    fn rosenbrock(method: Method) {
+        let formula =
+            RootFormula::parse("(a - x)^2 + b * (y - x^2)^2", &EmptyFunctionStore).unwrap();
+        let mut store = VectorVariableStore::new();
+        store.set("a", 1.0);
+        store.set("b", 100.0);
+        store.set("x", -1.2);
+        store.set("y", 1.0);
+        let variables = [Variable::new("x"), Variable::new("y")];
+        let options = MinimizeOptions::new().with_method(method);
+        let minimum = minimize(&formula, &variables, &store, &options).unwrap();
+        assert!(minimum.converged());
+        assert!(minimum.value() < 1e-8, "{minimum:?}");
+        assert!((minimum.values()[0] - 1.0).abs() < 1e-3, "{minimum:?}");
+        assert!((minimum.values()[1] - 1.0).abs() < 1e-3, "{minimum:?}");
+    }
+
+    #[test]
+    fn test_nelder_mead() {
+        rosenbrock(Method::NelderMead);
+    }
+
+    #[test]
+    fn test_bfgs() {
+        rosenbrock(Method::Bfgs);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let formula = RootFormula::parse("(x - 3)^2 + (y - 3)^2", &EmptyFunctionStore).unwrap();
+        let mut store = VectorVariableStore::new();
+        store.set("x", 0.0);
+        store.set("y", 5.0);
+        let variables = [Variable::new("x"), Variable::new("y")];
+        for method in [Method::NelderMead, Method::Bfgs] {
+            let options = MinimizeOptions::new()
+                .with_method(method)
+                .with_bounds("x", -1.0, 1.0)
+                .with_bounds("y", 4.0, f64::INFINITY);
+            let minimum = minimize(&formula, &variables, &store, &options).unwrap();
+            assert!((minimum.values()[0] - 1.0).abs() < 1e-6, "{minimum:?}");
+            assert!((minimum.values()[1] - 4.0).abs() < 1e-6, "{minimum:?}");
+        }
+    }
+
+    #[test]
+    fn test_fixed_variable() {
+        let formula = RootFormula::parse("(x - 3)^2 + (y - 3)^2", &EmptyFunctionStore).unwrap();
+        let mut store = VectorVariableStore::new();
+        store.set("x", 0.0);
+        store.set("y", 0.0);
+        let variables = [Variable::new("x"), Variable::new("y")];
+        for method in [Method::NelderMead, Method::Bfgs] {
+            let options = MinimizeOptions::new()
+                .with_method(method)
+                .with_bounds("x", 2.0, 2.0);
+            let minimum = minimize(&formula, &variables, &store, &options).unwrap();
+            assert!(minimum.converged(), "{minimum:?}");
+            assert!(
+                (minimum.values()[0] - 2.0).abs() < f64::EPSILON,
+                "{minimum:?}"
+            );
+            assert!((minimum.values()[1] - 3.0).abs() < 1e-6, "{minimum:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "lower bound is greater than upper bound")]
+    fn test_inverted_bounds() {
+        let _ = MinimizeOptions::new().with_bounds("x", 1.0, -1.0);
+    }
+
+    #[test]
+    fn test_iteration_limit() {
+        let formula = RootFormula::parse("(x - 3)^2", &EmptyFunctionStore).unwrap();
+        let mut store = VectorVariableStore::new();
+        store.set("x", 0.0);
+        let options = MinimizeOptions::new().with_max_iterations(2);
+        let minimum = minimize(&formula, &[Variable::new("x")], &store, &options).unwrap();
+        assert!(!minimum.converged());
+        assert_eq!(minimum.iterations(), 2);
+    }
+}
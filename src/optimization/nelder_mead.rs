@@ -0,0 +1,139 @@
+// `mul_add` is not available without std, number of variables is small enough to be converted to float.
+// Points are compared exactly to find out if they were moved by bounds.
+#![allow(
+    clippy::suboptimal_flops,
+    clippy::cast_precision_loss,
+    clippy::float_cmp
+)]
+
+use crate::__lib::vec::{vec, Vec};
+use crate::float::{abs, sqrt};
+use crate::formulas::EvaluationError;
+use crate::optimization::{MinimizeOptions, Minimum, Objective};
+
+const REFLECTION: f64 = 1.0;
+const EXPANSION: f64 = 2.0;
+const CONTRACTION: f64 = 0.5;
+const SHRINK: f64 = 0.5;
+
+type Vertex = (Vec<f64>, f64);
+
+// point `from + coefficient * (to - from)`, restricted to bounds
+fn along(objective: &Objective<'_>, from: &[f64], to: &[f64], coefficient: f64) -> Vec<f64> {
+    let mut point = from
+        .iter()
+        .zip(to)
+        .map(|(from, to)| from + coefficient * (to - from))
+        .collect::<Vec<_>>();
+    objective.clamp(&mut point);
+    point
+}
+
+fn vertex(objective: &mut Objective<'_>, point: Vec<f64>) -> Result<Vertex, EvaluationError> {
+    let value = objective.value(&point)?;
+    Ok((point, value))
+}
+
+// simplex with vertices displaced by 5% of starting values along every axis
+fn initial_simplex(
+    objective: &mut Objective<'_>,
+    start: &[f64],
+) -> Result<Vec<Vertex>, EvaluationError> {
+    let mut simplex = Vec::with_capacity(start.len() + 1);
+    simplex.push(vertex(objective, start.to_vec())?);
+    for i in 0..start.len() {
+        let step = if start[i] == 0.0 {
+            0.000_25
+        } else {
+            0.05 * abs(start[i])
+        };
+        let mut point = start.to_vec();
+        point[i] += step;
+        objective.clamp(&mut point);
+        if point[i] == start[i] {
+            point[i] -= step;
+            objective.clamp(&mut point);
+        }
+        simplex.push(vertex(objective, point)?);
+    }
+    Ok(simplex)
+}
+
+fn is_converged(simplex: &[Vertex], tolerance: f64) -> bool {
+    let (best, best_value) = &simplex[0];
+    let values_are_close = simplex
+        .iter()
+        .all(|(_, value)| abs(value - best_value) <= tolerance * abs(*best_value).max(1.0));
+    let points_are_close = simplex.iter().all(|(point, _)| {
+        point
+            .iter()
+            .zip(best)
+            .all(|(x, best)| abs(x - best) <= sqrt(tolerance) * abs(*best).max(1.0))
+    });
+    values_are_close && points_are_close
+}
+
+pub(super) fn minimize(
+    objective: &mut Objective<'_>,
+    start: &[f64],
+    options: &MinimizeOptions,
+) -> Result<Minimum, EvaluationError> {
+    let n = start.len();
+    let mut simplex = initial_simplex(objective, start)?;
+    let mut iterations = 0;
+    loop {
+        simplex.sort_by(|first, second| first.1.total_cmp(&second.1));
+        if is_converged(&simplex, options.tolerance) || iterations == options.max_iterations {
+            break;
+        }
+        iterations += 1;
+
+        let mut centroid = vec![0.0; n];
+        for (point, _) in &simplex[..n] {
+            for (sum, x) in centroid.iter_mut().zip(point) {
+                *sum += x / n as f64;
+            }
+        }
+        let (worst, worst_value) = simplex[n].clone();
+        let reflected = vertex(objective, along(objective, &centroid, &worst, -REFLECTION))?;
+        let replacement = if reflected.1 < simplex[0].1 {
+            let expanded = vertex(
+                objective,
+                along(objective, &centroid, &reflected.0, EXPANSION),
+            )?;
+            Some(if expanded.1 < reflected.1 {
+                expanded
+            } else {
+                reflected
+            })
+        } else if reflected.1 < simplex[n - 1].1 {
+            Some(reflected)
+        } else if reflected.1 < worst_value {
+            let contracted = vertex(
+                objective,
+                along(objective, &centroid, &reflected.0, CONTRACTION),
+            )?;
+            (contracted.1 <= reflected.1).then_some(contracted)
+        } else {
+            let contracted = vertex(objective, along(objective, &centroid, &worst, CONTRACTION))?;
+            (contracted.1 < worst_value).then_some(contracted)
+        };
+        if let Some(replacement) = replacement {
+            simplex[n] = replacement;
+        } else {
+            let best = simplex[0].0.clone();
+            for shrunk in &mut simplex[1..] {
+                let point = along(objective, &best, &shrunk.0, SHRINK);
+                *shrunk = vertex(objective, point)?;
+            }
+        }
+    }
+    let converged = is_converged(&simplex, options.tolerance);
+    let (values, value) = simplex.swap_remove(0);
+    Ok(Minimum {
+        values,
+        value,
+        iterations,
+        converged,
+    })
+}
@@ -0,0 +1,231 @@
+use crate::formulas::{BoundFormula, EvaluationError};
+use crate::variable_stores::{GetVariable, Variable};
+use std::num::NonZeroUsize;
+use std::thread;
+
+// batches with less rows per thread are not worth spawning threads
+const MIN_ROWS_PER_THREAD: usize = 1024;
+
+/// Options of parallel evaluation.
+#[derive(Debug, Clone, Copy)]
+pub struct ParallelOptions {
+    threads: usize,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        }
+    }
+}
+
+impl ParallelOptions {
+    /// Creates default options, number of threads is equal to available parallelism.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets maximum number of threads, zero is treated as one.
+    #[must_use]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Returns maximum number of threads.
+    pub const fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+/// Evaluates `formula` for every row of `columns` and writes results into `output`, rows are split between threads.
+///
+/// Works the same way as [`BoundFormula::eval_batch`]. Functions with inner state are shared between threads,
+/// so order in which they see rows is not specified.
+///
+/// # Errors
+///
+/// Will return Err if there are less columns than variables, length of column differs from length of `output`
+/// or function failed to evaluate. If several threads failed, error of the first rows is returned.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::parallel::{eval_batch, ParallelOptions};
+///
+/// let formula = RootFormula::parse("x * 2", &EmptyFunctionStore).unwrap().bind(&["x"]).unwrap();
+/// let x = (0..10_000).map(f64::from).collect::<Vec<_>>();
+/// let mut output = vec![0.0; x.len()];
+/// eval_batch(&formula, &[&x], &mut output, &ParallelOptions::new().with_threads(4)).unwrap();
+/// assert_eq!(output[5000], 10_000.0);
+/// ```
+pub fn eval_batch(
+    formula: &BoundFormula,
+    columns: &[&[f64]],
+    output: &mut [f64],
+    options: &ParallelOptions,
+) -> Result<(), EvaluationError> {
+    let threads = options
+        .threads
+        .min(output.len() / MIN_ROWS_PER_THREAD)
+        .max(1);
+    if threads == 1 || columns.iter().any(|column| column.len() != output.len()) {
+        // errors of wrong input are reported by sequential evaluation
+        return formula.eval_batch(columns, output);
+    }
+    let rows_per_thread = (output.len() + threads - 1) / threads;
+    thread::scope(|scope| {
+        let handles = output
+            .chunks_mut(rows_per_thread)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let start = index * rows_per_thread;
+                let end = start + chunk.len();
+                scope.spawn(move || {
+                    let columns = columns
+                        .iter()
+                        .map(|column| &column[start..end])
+                        .collect::<Vec<_>>();
+                    formula.eval_batch(&columns, chunk)
+                })
+            })
+            .collect::<Vec<_>>();
+        join(handles).into_iter().collect()
+    })
+}
+
+/// Evaluates `variables` stored in `store`, variables are split between threads.
+///
+/// Returns values in the same order as variables.
+///
+/// # Errors
+///
+/// Will return Err if variable is not in `store` or failed to evaluate. If several variables failed,
+/// error of the first one is returned.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::parallel::{eval_variables, ParallelOptions};
+/// use evaluatorrs::variable_stores::{HashMapVariableStore, SetVariable, Variable};
+///
+/// let mut store = HashMapVariableStore::new();
+/// store.set("a", 2.0);
+/// store.set("b", RootFormula::parse("a * 3", &EmptyFunctionStore).unwrap());
+/// let values = eval_variables(&store, &[Variable::new("b"), Variable::new("a")], &ParallelOptions::new()).unwrap();
+/// assert_eq!(values, [6.0, 2.0]);
+/// ```
+pub fn eval_variables<S: GetVariable + Sync>(
+    store: &S,
+    variables: &[Variable],
+    options: &ParallelOptions,
+) -> Result<Vec<f64>, EvaluationError> {
+    let threads = options.threads.min(variables.len()).max(1);
+    if threads == 1 {
+        return variables
+            .iter()
+            .map(|variable| store.eval(variable))
+            .collect();
+    }
+    let variables_per_thread = (variables.len() + threads - 1) / threads;
+    thread::scope(|scope| {
+        let handles = variables
+            .chunks(variables_per_thread)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|variable| store.eval(variable))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut values = Vec::with_capacity(variables.len());
+        for chunk in join(handles) {
+            values.extend(chunk?);
+        }
+        Ok(values)
+    })
+}
+
+// panics of evaluation are propagated to caller
+fn join<T>(handles: Vec<thread::ScopedJoinHandle<'_, T>>) -> Vec<T> {
+    handles
+        .into_iter()
+        .map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+// parallel evaluation must return exactly the same values as sequential one
+#[allow(clippy::float_cmp)]
+mod test {
+    use crate::formulas::stateful::Prev;
+    use crate::formulas::RootFormula;
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::parallel::{eval_batch, eval_variables, ParallelOptions};
+    use crate::variable_stores::{HashMapVariableStore, SetVariable, Variable};
+
+    #[test]
+    fn test_eval_batch() {
+        let formula = RootFormula::parse("x * y - x / 3", &EmptyFunctionStore).unwrap();
+        let bound = formula.bind(&["x", "y"]).unwrap();
+        let x = (0..10_007).map(f64::from).collect::<Vec<_>>();
+        let y = x.iter().map(|x| x.sqrt()).collect::<Vec<_>>();
+        let mut expected = vec![0.0; x.len()];
+        bound.eval_batch(&[&x, &y], &mut expected).unwrap();
+        for threads in [0, 1, 3, 8, 64] {
+            let options = ParallelOptions::new().with_threads(threads);
+            let mut output = vec![0.0; x.len()];
+            eval_batch(&bound, &[&x, &y], &mut output, &options).unwrap();
+            assert_eq!(output, expected, "{threads} threads");
+        }
+        let options = ParallelOptions::new().with_threads(4);
+        let mut output = vec![0.0; x.len()];
+        assert!(eval_batch(&bound, &[&x, &y[1..]], &mut output, &options).is_err());
+        assert!(eval_batch(&bound, &[&x], &mut output, &options).is_err());
+    }
+
+    #[test]
+    fn test_stateful_batch() {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<Prev>();
+        let formula = RootFormula::parse("prev(x)", &functions).unwrap();
+        let bound = formula.bind(&["x"]).unwrap();
+        let x = vec![1.0; 5000];
+        let mut output = vec![0.0; x.len()];
+        let options = ParallelOptions::new().with_threads(4);
+        eval_batch(&bound, &[&x], &mut output, &options).unwrap();
+        // only the first evaluation of shared state has no previous value
+        assert_eq!(output.iter().filter(|value| value.is_nan()).count(), 1);
+    }
+
+    #[test]
+    fn test_eval_variables() {
+        let mut store = HashMapVariableStore::new();
+        store.set("v0", 1.0);
+        let mut variables = vec![Variable::new("v0")];
+        for i in 1..10 {
+            let expression = format!("v{} + {i}", i - 1);
+            store.set(
+                format!("v{i}"),
+                RootFormula::parse(&expression, &EmptyFunctionStore).unwrap(),
+            );
+            variables.push(Variable::new(format!("v{i}")));
+        }
+        let options = ParallelOptions::new().with_threads(7);
+        let values = eval_variables(&store, &variables, &options).unwrap();
+        for (i, value) in (0_u32..).zip(&values) {
+            assert_eq!(*value, 1.0 + f64::from(i * (i + 1) / 2));
+        }
+        variables.push(Variable::new("missing"));
+        assert!(eval_variables(&store, &variables, &options).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::__lib::boxed::Box;
+use crate::__lib::vec::Vec;
+use crate::formulas::{
+    Evaluate, EvaluationError, Function, FunctionLike, IsConst, Lowering, ParserError, RootFormula,
+};
+use crate::function_stores::GetFunction;
+use crate::variable_stores::GetVariable;
+
+// Aggregate functions take one or more arguments, because ranges are expanded into lists of cells.
+macro_rules! impl_aggregate_function {
+    (
+        $parser_name:expr, $function:expr,
+        $(#[$meta: meta])*
+        $StructName:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $StructName {
+            arguments: Box<[RootFormula]>,
+        }
+
+        impl IsConst for $StructName {
+            fn is_const(&self) -> bool {
+                self.arguments.iter().all(IsConst::is_const)
+            }
+        }
+
+        impl Evaluate for $StructName {
+            fn eval(&self, args: &dyn GetVariable) -> Result<f64, EvaluationError> {
+                let values = self
+                    .arguments
+                    .iter()
+                    .map(|val| val.eval(args))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok($function(&values))
+            }
+        }
+
+        impl FunctionLike for $StructName {
+            crate::delegate_function_like!([arguments]);
+
+            fn clone_into_box(&self) -> Box<dyn FunctionLike> {
+                Box::new(Self {
+                    arguments: self.arguments.clone(),
+                })
+            }
+
+            fn lower(&self) -> Option<Lowering<'_>> {
+                Some(Lowering::Many($function, self.arguments.iter().collect()))
+            }
+        }
+
+        impl Function for $StructName {
+            const MIN_NUMBER_OF_ARGUMENTS: usize = 1;
+            const MAX_NUMBER_OF_ARGUMENTS: usize = usize::MAX;
+            const NAME: &'static str = $parser_name;
+
+            fn parse<T: for<'a> GetFunction<'a>>(
+                arguments: &[&str],
+                formulas: &T,
+            ) -> Result<Self, ParserError>
+            where
+                Self: Sized,
+            {
+                let mut parsed_arguments = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    parsed_arguments.push(RootFormula::parse(argument, formulas)?);
+                }
+                Ok(Self {
+                    arguments: parsed_arguments.into_boxed_slice(),
+                })
+            }
+        }
+    };
+}
+
+fn sum(values: &[f64]) -> f64 {
+    values.iter().sum()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn average(values: &[f64]) -> f64 {
+    sum(values) / values.len() as f64
+}
+
+fn max(values: &[f64]) -> f64 {
+    values.iter().fold(f64::MIN, |max, value| max.max(*value))
+}
+
+impl_aggregate_function!(
+    "SUM", sum,
+    /// Sum of arguments, called as `SUM(A1:A10)` like in spreadsheets.
+    ///
+    /// Name is upper case, so it does not collide with series [`Sum`](crate::formulas::series::Sum).
+    Sum
+);
+
+impl_aggregate_function!(
+    "AVERAGE", average,
+    /// Arithmetic mean of arguments, called as `AVERAGE(A1:A10)`.
+    Average
+);
+
+impl_aggregate_function!(
+    "MAX", max,
+    /// Maximum of arguments, called as `MAX(A1:A10)`.
+    Max
+);
@@ -0,0 +1,589 @@
+mod functions;
+mod reference;
+
+use crate::__lib::cell::Cell;
+use crate::__lib::collections::BTreeMap;
+use crate::__lib::fmt::{Display, Formatter};
+use crate::__lib::string::{format, String};
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::{vec, Vec};
+use crate::formulas::{
+    CycleError, Evaluate, EvaluationError, MathError, NoVariableError, RootFormula,
+};
+use crate::function_stores::GetFunction;
+use crate::variable_stores::{DependencyGraph, GetVariable, Variable};
+use reference::{expand, join, split};
+
+#[cfg(any(feature = "std", nightly))]
+use crate::__lib::error::Error;
+
+pub use functions::{Average, Max, Sum};
+pub use reference::CellRef;
+
+/// Error value of cell, which is shown instead of number, like `#DIV/0!` in spreadsheets.
+///
+/// Cells, which use cell with error, have the same error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CellError {
+    /// Formula divided by zero or its result is infinite.
+    DivisionByZero,
+    /// Result of formula is not a number.
+    Number,
+    /// Function failed to evaluate.
+    Value,
+    /// Formula uses variable, which is not a cell.
+    Name,
+    /// Text of cell is not a formula.
+    Parse,
+    /// Cell depends on itself.
+    Cycle,
+    /// Ranges of formula have more cells than allowed by [`Sheet::with_max_range_cells`].
+    Range,
+}
+
+impl Display for CellError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        let text = match self {
+            Self::DivisionByZero => "#DIV/0!",
+            Self::Number => "#NUM!",
+            Self::Value => "#VALUE!",
+            Self::Name => "#NAME?",
+            Self::Parse => "#ERROR!",
+            Self::Cycle => "#CYCLE!",
+            Self::Range => "#REF!",
+        };
+        write!(f, "{text}")
+    }
+}
+
+#[cfg(any(feature = "std", nightly))]
+impl Error for CellError {}
+
+// Parsed cells, cells are stored as variables named like `A1`.
+#[derive(Debug, Clone, Default)]
+struct Cells {
+    formulas: BTreeMap<Variable, Arc<RootFormula>>,
+    errors: BTreeMap<CellRef, CellError>,
+}
+
+impl GetVariable for Cells {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.formulas.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+}
+
+// Values of already calculated cells, empty cells are 0.
+// Error of used cell is remembered, so it is propagated instead of generic evaluation error.
+struct Values<'a> {
+    results: &'a BTreeMap<CellRef, Result<f64, CellError>>,
+    error: Cell<Option<CellError>>,
+}
+
+impl GetVariable for Values<'_> {
+    fn get(&self, _name: &Variable) -> Option<&Arc<RootFormula>> {
+        None
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
+        let cell = name
+            .as_ref()
+            .parse::<CellRef>()
+            .map_err(|_| EvaluationError::NoVariableError(NoVariableError::new(name.clone())))?;
+        match self.results.get(&cell) {
+            None => Ok(0.0),
+            Some(Ok(value)) => Ok(*value),
+            Some(Err(error)) => {
+                self.error.set(Some(*error));
+                Err(EvaluationError::MathError(MathError::new(format!(
+                    "cell {cell}, it has error {error}"
+                ))))
+            }
+        }
+    }
+}
+
+/// Grid of cells, every cell holds formula, which may use other cells.
+///
+/// Cells are used by their names in A1 notation, like `B2`. `$` marks absolute reference, like `$B$2`,
+/// it does not change value, but is kept in text of cell. Range, like `A1:B10`, is replaced by list of its cells
+/// row by row, so it can be passed to functions of any number of arguments, like [`Sum`].
+/// Leading `=` of formula is optional. Empty cells have value `0`.
+///
+/// Cells are recalculated when they are set, only changed cells and cells, which depend on them, are recalculated.
+/// Cell, which failed to calculate, has [`CellError`].
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::function_stores::{RegisterParser, VectorFunctionStore};
+/// use evaluatorrs::sheet::{CellError, CellRef, Sheet, Sum};
+///
+/// let mut functions = VectorFunctionStore::new();
+/// functions.register::<Sum>();
+/// let mut sheet = Sheet::new(functions);
+/// let cell = |name: &str| name.parse::<CellRef>().unwrap();
+/// sheet.set(cell("A1"), "1");
+/// sheet.set(cell("A2"), "2");
+/// sheet.set(cell("B1"), "=SUM(A1:A3) * $C$1");
+/// sheet.set(cell("C1"), "10");
+/// assert_eq!(sheet.value(cell("B1")), Some(Ok(30.0)));
+/// sheet.set(cell("C1"), "1 / 0");
+/// assert_eq!(sheet.value(cell("B1")), Some(Err(CellError::DivisionByZero)));
+/// sheet.insert_rows(0, 1);
+/// assert_eq!(sheet.text(cell("B2")), Some("=SUM(A2:A4) * $C$2"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sheet<F> {
+    functions: F,
+    texts: BTreeMap<CellRef, String>,
+    cells: Cells,
+    results: BTreeMap<CellRef, Result<f64, CellError>>,
+    dependencies: BTreeMap<CellRef, Vec<CellRef>>,
+    dependents: BTreeMap<CellRef, Vec<CellRef>>,
+    max_range_cells: usize,
+}
+
+impl<F> Sheet<F> {
+    /// Creates empty `Sheet`, formulas of cells are parsed with `functions`.
+    /// Ranges of one formula may have 65536 cells together.
+    pub fn new(functions: F) -> Self {
+        Self {
+            functions,
+            texts: BTreeMap::new(),
+            cells: Cells::default(),
+            results: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            dependents: BTreeMap::new(),
+            max_range_cells: 65_536,
+        }
+    }
+
+    /// Sets maximum number of cells in all ranges of one formula, formulas with larger ranges
+    /// have [`CellError::Range`]. Cells, which are already set, are not checked again.
+    #[must_use]
+    pub const fn with_max_range_cells(mut self, max_range_cells: usize) -> Self {
+        self.max_range_cells = max_range_cells;
+        self
+    }
+
+    /// Returns text of cell, `None` if cell is empty.
+    pub fn text(&self, cell: CellRef) -> Option<&str> {
+        self.texts.get(&cell).map(String::as_str)
+    }
+
+    /// Returns value of cell, `None` if cell is empty.
+    pub fn value(&self, cell: CellRef) -> Option<Result<f64, CellError>> {
+        self.results.get(&cell).copied()
+    }
+
+    /// Returns not empty cells ordered so, that every cell goes after cells it uses.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err with cells of cycle, if cells depend on each other in a cycle.
+    pub fn recalculation_order(&self) -> Result<Vec<CellRef>, CycleError> {
+        let graph = DependencyGraph::new(&self.cells, &self.variables());
+        Ok(graph
+            .topological_order()?
+            .into_iter()
+            .filter_map(|variable| variable.as_ref().parse().ok())
+            .filter(|cell| self.texts.contains_key(cell))
+            .collect())
+    }
+
+    /// Removes text of cell and recalculates cells, which use it.
+    pub fn clear(&mut self, cell: CellRef) {
+        self.texts.remove(&cell);
+        self.cells.formulas.remove(&cell.variable());
+        self.cells.errors.remove(&cell);
+        self.results.remove(&cell);
+        self.track(cell, Vec::new());
+        self.recalculate(Some(&[cell]));
+    }
+
+    fn variables(&self) -> Vec<Variable> {
+        self.texts.keys().map(|cell| cell.variable()).collect()
+    }
+
+    // updates dependencies of `cell` after its formula was changed
+    fn track(&mut self, cell: CellRef, dependencies: Vec<CellRef>) {
+        for dependency in self.dependencies.remove(&cell).unwrap_or_default() {
+            if let Some(dependents) = self.dependents.get_mut(&dependency) {
+                dependents.retain(|x| *x != cell);
+                if dependents.is_empty() {
+                    self.dependents.remove(&dependency);
+                }
+            }
+        }
+        for dependency in &dependencies {
+            self.dependents.entry(*dependency).or_default().push(cell);
+        }
+        if !dependencies.is_empty() {
+            self.dependencies.insert(cell, dependencies);
+        }
+    }
+
+    // Recalculates `changed` cells and cells, which depend on them, `None` recalculates all cells.
+    // Cells are calculated after cells they use by Kahn's algorithm, cells, which are left, are in a cycle
+    // or use a cycle.
+    fn recalculate(&mut self, changed: Option<&[CellRef]>) {
+        let mut dirty = changed.map_or_else(|| self.texts.keys().copied().collect(), <[_]>::to_vec);
+        let mut positions = BTreeMap::new();
+        for (position, cell) in dirty.iter().enumerate() {
+            positions.insert(*cell, position);
+        }
+        let mut next = 0;
+        while let Some(cell) = dirty.get(next).copied() {
+            for dependent in self.dependents.get(&cell).into_iter().flatten() {
+                if !positions.contains_key(dependent) {
+                    positions.insert(*dependent, dirty.len());
+                    dirty.push(*dependent);
+                }
+            }
+            next += 1;
+        }
+        let dependencies = dirty
+            .iter()
+            .map(|cell| {
+                self.dependencies
+                    .get(cell)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dependency| positions.get(dependency).copied())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut dependents = vec![Vec::new(); dirty.len()];
+        for (position, cell_dependencies) in dependencies.iter().enumerate() {
+            for dependency in cell_dependencies {
+                dependents[*dependency].push(position);
+            }
+        }
+        let mut waiting = dependencies.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut ready = (0..dirty.len())
+            .filter(|position| waiting[*position] == 0)
+            .collect::<Vec<_>>();
+        while let Some(position) = ready.pop() {
+            self.update(dirty[position], None);
+            for dependent in &dependents[position] {
+                waiting[*dependent] -= 1;
+                if waiting[*dependent] == 0 {
+                    ready.push(*dependent);
+                }
+            }
+        }
+        // cells, which are left and not used by other left cells, are not in a cycle,
+        // they are removed until only cycles are left
+        let mut used = vec![0_usize; dirty.len()];
+        for (position, cell_dependencies) in dependencies.iter().enumerate() {
+            if waiting[position] > 0 {
+                for dependency in cell_dependencies {
+                    used[*dependency] += 1;
+                }
+            }
+        }
+        let mut unused = (0..dirty.len())
+            .filter(|position| waiting[*position] > 0 && used[*position] == 0)
+            .collect::<Vec<_>>();
+        let mut outside = Vec::new();
+        let mut is_outside = vec![false; dirty.len()];
+        while let Some(position) = unused.pop() {
+            outside.push(position);
+            is_outside[position] = true;
+            for dependency in &dependencies[position] {
+                if waiting[*dependency] > 0 {
+                    used[*dependency] -= 1;
+                    if used[*dependency] == 0 {
+                        unused.push(*dependency);
+                    }
+                }
+            }
+        }
+        for position in 0..dirty.len() {
+            if waiting[position] > 0 && !is_outside[position] {
+                self.update(dirty[position], Some(CellError::Cycle));
+            }
+        }
+        for position in outside.into_iter().rev() {
+            self.update(dirty[position], None);
+        }
+    }
+
+    // Stores result of not empty cell, which is `error` or calculated value.
+    fn update(&mut self, cell: CellRef, error: Option<CellError>) {
+        if self.texts.contains_key(&cell) {
+            let result = error.map_or_else(|| self.calculate(cell, &cell.variable()), Err);
+            self.results.insert(cell, result);
+        }
+    }
+
+    fn calculate(&self, cell: CellRef, variable: &Variable) -> Result<f64, CellError> {
+        if let Some(error) = self.cells.errors.get(&cell) {
+            return Err(*error);
+        }
+        let Some(formula) = self.cells.formulas.get(variable) else {
+            return Ok(0.0);
+        };
+        let values = Values {
+            results: &self.results,
+            error: Cell::new(None),
+        };
+        match formula.eval(&values) {
+            Ok(value) if value.is_nan() => Err(CellError::Number),
+            Ok(value) if value.is_infinite() => Err(CellError::DivisionByZero),
+            Ok(value) => Ok(value),
+            Err(_) => Err(values.error.get().unwrap_or(CellError::Value)),
+        }
+    }
+}
+
+impl<F: for<'a> GetFunction<'a>> Sheet<F> {
+    /// Sets text of cell and recalculates it and cells, which use it.
+    ///
+    /// Text, which is not a formula, or formula, which uses variables other than cells, gives cell error.
+    pub fn set(&mut self, cell: CellRef, text: impl Into<String>) {
+        let text = text.into();
+        self.parse(cell, &text);
+        self.texts.insert(cell, text);
+        self.recalculate(Some(&[cell]));
+    }
+
+    /// Inserts `count` empty rows before `row`, cells and references to them are moved down.
+    pub fn insert_rows(&mut self, row: u32, count: u32) {
+        self.shift(u32::MAX, 0, row, count);
+    }
+
+    /// Inserts `count` empty columns before `column`, cells and references to them are moved right.
+    pub fn insert_columns(&mut self, column: u32, count: u32) {
+        self.shift(column, count, u32::MAX, 0);
+    }
+
+    fn shift(&mut self, column: u32, columns: u32, row: u32, rows: u32) {
+        let texts = core::mem::take(&mut self.texts);
+        self.cells = Cells::default();
+        self.results.clear();
+        self.dependencies.clear();
+        self.dependents.clear();
+        for (cell, text) in texts {
+            let text = join(&split(&text), |reference| {
+                reference.shift(column, columns, row, rows)
+            });
+            let cell = cell.shift(column, columns, row, rows);
+            self.parse(cell, &text);
+            self.texts.insert(cell, text);
+        }
+        self.recalculate(None);
+    }
+
+    fn parse(&mut self, cell: CellRef, text: &str) {
+        let variable = cell.variable();
+        self.cells.formulas.remove(&variable);
+        self.cells.errors.remove(&cell);
+        let text = text.trim();
+        let formula = expand(
+            &split(text.strip_prefix('=').unwrap_or(text)),
+            self.max_range_cells,
+        )
+        .ok_or(CellError::Range)
+        .and_then(|expression| {
+            RootFormula::parse(&expression, &self.functions).map_err(|_| CellError::Parse)
+        })
+        .and_then(|formula| {
+            let dependencies = formula
+                .variables()
+                .iter()
+                .map(|x| x.as_ref().parse::<CellRef>().ok())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(CellError::Name)?;
+            Ok((formula, dependencies))
+        });
+        let dependencies = match formula {
+            Ok((formula, dependencies)) => {
+                self.cells.formulas.insert(variable, Arc::new(formula));
+                dependencies
+            }
+            Err(error) => {
+                self.cells.errors.insert(cell, error);
+                Vec::new()
+            }
+        };
+        self.track(cell, dependencies);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::string::ToString;
+    use crate::__lib::vec::Vec;
+    use crate::formulas::series;
+    use crate::formulas::stateful::IntegrateDt;
+    use crate::function_stores::{RegisterParser, VectorFunctionStore};
+    use crate::sheet::{Average, CellError, CellRef, Max, Sheet, Sum};
+
+    fn cell(name: &str) -> CellRef {
+        name.parse().unwrap()
+    }
+
+    fn sheet() -> Sheet<VectorFunctionStore> {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<Sum>();
+        functions.register::<Average>();
+        functions.register::<Max>();
+        functions.register::<series::Sum>();
+        Sheet::new(functions)
+    }
+
+    #[test]
+    fn test_recalculation() {
+        let mut sheet = sheet();
+        sheet.set(cell("A1"), "1");
+        sheet.set(cell("A2"), "2");
+        sheet.set(cell("A3"), "=A1 + A2");
+        sheet.set(cell("B1"), "=AVERAGE(A1:A2) + MAX(A1:A3)");
+        assert_eq!(sheet.value(cell("A3")), Some(Ok(3.0)));
+        assert_eq!(sheet.value(cell("B1")), Some(Ok(4.5)));
+        sheet.set(cell("A1"), "5");
+        assert_eq!(sheet.value(cell("A3")), Some(Ok(7.0)));
+        assert_eq!(sheet.value(cell("B1")), Some(Ok(10.5)));
+        // series and spreadsheet sum are registered together
+        sheet.set(cell("B2"), "sum(i, 1, 3, i) + SUM(A1:A2)");
+        assert_eq!(sheet.value(cell("B2")), Some(Ok(13.0)));
+        // empty cells are 0
+        sheet.set(cell("C1"), "SUM(C2:C10) + D1");
+        assert_eq!(sheet.value(cell("C1")), Some(Ok(0.0)));
+        sheet.set(cell("C5"), "4");
+        assert_eq!(sheet.value(cell("C1")), Some(Ok(4.0)));
+        sheet.clear(cell("C5"));
+        assert_eq!(sheet.value(cell("C1")), Some(Ok(0.0)));
+        assert_eq!(sheet.value(cell("C5")), None);
+        let order = sheet.recalculation_order().unwrap();
+        let position = |name| order.iter().position(|x| *x == cell(name));
+        assert!(position("A1") < position("A3"));
+        assert!(position("A3") < position("B1"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut sheet = sheet();
+        sheet.set(cell("A1"), "0");
+        sheet.set(cell("A2"), "1 / A1");
+        sheet.set(cell("A3"), "A2 + 1");
+        sheet.set(cell("A4"), "sqrt");
+        sheet.set(cell("A5"), "x + 1");
+        sheet.set(cell("A6"), "0 / A1");
+        assert_eq!(
+            sheet.value(cell("A2")),
+            Some(Err(CellError::DivisionByZero))
+        );
+        assert_eq!(
+            sheet.value(cell("A3")),
+            Some(Err(CellError::DivisionByZero))
+        );
+        assert_eq!(sheet.value(cell("A4")), Some(Err(CellError::Name)));
+        assert_eq!(sheet.value(cell("A5")), Some(Err(CellError::Name)));
+        assert_eq!(sheet.value(cell("A6")), Some(Err(CellError::Number)));
+        sheet.set(cell("A4"), "1 +");
+        assert_eq!(sheet.value(cell("A4")), Some(Err(CellError::Parse)));
+        sheet.set(cell("A1"), "2");
+        assert_eq!(sheet.value(cell("A3")), Some(Ok(1.5)));
+        sheet.set(cell("A7"), "SUM(B1:XFD1048576)");
+        assert_eq!(sheet.value(cell("A7")), Some(Err(CellError::Range)));
+        let mut sheet = sheet.with_max_range_cells(2);
+        sheet.set(cell("A7"), "SUM(A1:A2)");
+        assert_eq!(sheet.value(cell("A7")), Some(Ok(2.5)));
+        sheet.set(cell("A8"), "SUM(A1:A2) + MAX(A1:A2)");
+        assert_eq!(sheet.value(cell("A8")), Some(Err(CellError::Range)));
+        assert_eq!(CellError::DivisionByZero.to_string(), "#DIV/0!");
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut sheet = sheet();
+        sheet.set(cell("A1"), "B1 + 1");
+        sheet.set(cell("B1"), "A1 * 2");
+        sheet.set(cell("C1"), "A1");
+        sheet.set(cell("D1"), "D1");
+        for name in ["A1", "B1", "C1", "D1"] {
+            assert_eq!(
+                sheet.value(cell(name)),
+                Some(Err(CellError::Cycle)),
+                "{name}"
+            );
+        }
+        assert!(sheet.recalculation_order().is_err());
+        sheet.set(cell("E1"), "C1 + 1");
+        sheet.set(cell("E2"), "E1 + 1");
+        assert_eq!(sheet.value(cell("E2")), Some(Err(CellError::Cycle)));
+        sheet.set(cell("B1"), "3");
+        assert_eq!(sheet.value(cell("E2")), Some(Ok(6.0)));
+        sheet.clear(cell("D1"));
+        assert_eq!(sheet.value(cell("C1")), Some(Ok(4.0)));
+        assert_eq!(sheet.recalculation_order().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_only_dependents_recalculated() {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<IntegrateDt>();
+        let mut sheet = Sheet::new(functions);
+        // value of every probe is number of times it was calculated
+        sheet.set(cell("A1"), "1");
+        sheet.set(cell("B1"), "1");
+        sheet.set(cell("A2"), "integrate_dt(0 * A1 + 1, 1)");
+        sheet.set(cell("A3"), "integrate_dt(0 * A2 + 1, 1)");
+        sheet.set(cell("B2"), "integrate_dt(0 * B1 + 1, 1)");
+        let evaluations = |sheet: &Sheet<_>| ["A2", "A3", "B2"].map(|name| sheet.value(cell(name)));
+        assert_eq!(evaluations(&sheet), [Some(Ok(1.0)); 3]);
+        sheet.set(cell("A1"), "2");
+        assert_eq!(
+            evaluations(&sheet),
+            [Some(Ok(2.0)), Some(Ok(2.0)), Some(Ok(1.0))]
+        );
+        sheet.clear(cell("B1"));
+        sheet.set(cell("C1"), "3");
+        assert_eq!(
+            evaluations(&sheet),
+            [Some(Ok(2.0)), Some(Ok(2.0)), Some(Ok(2.0))]
+        );
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut sheet = sheet();
+        sheet.set(cell("A1"), "1");
+        sheet.set(cell("B2"), "2");
+        sheet.set(cell("C3"), "=SUM(A1:B2) + $B$2");
+        sheet.insert_rows(1, 2);
+        assert_eq!(sheet.text(cell("C5")), Some("=SUM(A1:B4) + $B$4"));
+        assert_eq!(sheet.value(cell("C5")), Some(Ok(5.0)));
+        assert_eq!(sheet.value(cell("B2")), None);
+        sheet.insert_columns(0, 1);
+        assert_eq!(sheet.text(cell("D5")), Some("=SUM(B1:C4) + $C$4"));
+        assert_eq!(sheet.value(cell("D5")), Some(Ok(5.0)));
+        let cells = sheet.recalculation_order().unwrap();
+        let names = cells.iter().map(CellRef::to_string).collect::<Vec<_>>();
+        assert_eq!(names, ["B1", "C4", "D5"]);
+    }
+
+    // dependencies of range are collected in linear time, so range of default size is set quickly
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_full_range() {
+        let mut sheet = sheet();
+        sheet.set(cell("A1"), "1");
+        let start = std::time::Instant::now();
+        sheet.set(cell("B1"), "=SUM(A1:A65536)");
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(sheet.value(cell("B1")), Some(Ok(1.0)));
+        sheet.set(cell("A2"), "2");
+        assert_eq!(sheet.value(cell("B1")), Some(Ok(3.0)));
+    }
+}
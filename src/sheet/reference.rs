@@ -0,0 +1,347 @@
+use crate::__lib::fmt::{Display, Formatter};
+use crate::__lib::str::FromStr;
+use crate::__lib::string::String;
+use crate::__lib::vec::Vec;
+use crate::formulas::UnknownTokenError;
+use crate::variable_stores::Variable;
+
+// columns are written with at most 3 letters, like `XFD`
+const MAX_COLUMN_LETTERS: usize = 3;
+
+/// Position of cell in [`Sheet`](crate::sheet::Sheet), written in A1 notation.
+///
+/// Column and row are counted from zero, so `B3` is column 1 and row 2.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::sheet::CellRef;
+///
+/// let cell: CellRef = "AB12".parse().unwrap();
+/// assert_eq!((cell.column(), cell.row()), (27, 11));
+/// assert_eq!(cell.to_string(), "AB12");
+/// assert_eq!("$B$2".parse::<CellRef>().unwrap(), CellRef::new(1, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CellRef {
+    row: u32,
+    column: u32,
+}
+
+impl CellRef {
+    /// Creates `CellRef` from zero based column and row.
+    pub const fn new(column: u32, row: u32) -> Self {
+        Self { row, column }
+    }
+
+    /// Returns zero based column.
+    pub const fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// Returns zero based row.
+    pub const fn row(&self) -> u32 {
+        self.row
+    }
+
+    pub(crate) fn variable(self) -> Variable {
+        Variable::new(crate::__lib::string::ToString::to_string(&self))
+    }
+
+    // Moves cell, which is at or after inserted rows or columns.
+    pub(crate) const fn shift(self, column: u32, columns: u32, row: u32, rows: u32) -> Self {
+        Self {
+            column: if self.column >= column {
+                self.column.saturating_add(columns)
+            } else {
+                self.column
+            },
+            row: if self.row >= row {
+                self.row.saturating_add(rows)
+            } else {
+                self.row
+            },
+        }
+    }
+}
+
+impl Display for CellRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        let mut letters = [0_u8; MAX_COLUMN_LETTERS + 4];
+        let mut start = letters.len();
+        let mut column = self.column;
+        loop {
+            start -= 1;
+            letters[start] = b'A' + (column % 26).to_le_bytes()[0];
+            if column < 26 {
+                break;
+            }
+            column = column / 26 - 1;
+        }
+        for letter in &letters[start..] {
+            write!(f, "{}", char::from(*letter))?;
+        }
+        write!(f, "{}", u64::from(self.row) + 1)
+    }
+}
+
+impl FromStr for CellRef {
+    type Err = UnknownTokenError;
+
+    /// Parses cell written in A1 notation, `$` marks of absolute reference are allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Reference::parse(s) {
+            Some((reference, length)) if length == s.len() => Ok(reference.cell),
+            _ => Err(UnknownTokenError::new(s)),
+        }
+    }
+}
+
+/// Reference to cell as it is written in formula.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Reference {
+    pub(crate) cell: CellRef,
+    absolute_column: bool,
+    absolute_row: bool,
+}
+
+impl Reference {
+    // Parses reference at the start of `text`, returns it with number of parsed bytes.
+    fn parse(text: &str) -> Option<(Self, usize)> {
+        let bytes = text.as_bytes();
+        let mut position = 0;
+        let absolute_column = bytes.first() == Some(&b'$');
+        position += usize::from(absolute_column);
+        let mut column: u32 = 0;
+        let letters_start = position;
+        while let Some(letter) = bytes.get(position).filter(|x| x.is_ascii_uppercase()) {
+            if position - letters_start == MAX_COLUMN_LETTERS {
+                return None;
+            }
+            column = column * 26 + u32::from(letter - b'A') + 1;
+            position += 1;
+        }
+        if position == letters_start {
+            return None;
+        }
+        let absolute_row = bytes.get(position) == Some(&b'$');
+        position += usize::from(absolute_row);
+        let digits_start = position;
+        while bytes.get(position).is_some_and(u8::is_ascii_digit) {
+            position += 1;
+        }
+        let row = text[digits_start..position]
+            .parse::<u32>()
+            .ok()?
+            .checked_sub(1)?;
+        Some((
+            Self {
+                cell: CellRef::new(column - 1, row),
+                absolute_column,
+                absolute_row,
+            },
+            position,
+        ))
+    }
+
+    pub(crate) const fn shift(mut self, column: u32, columns: u32, row: u32, rows: u32) -> Self {
+        self.cell = self.cell.shift(column, columns, row, rows);
+        self
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        let cell = crate::__lib::string::ToString::to_string(&self.cell);
+        let digits = cell
+            .find(|x: char| x.is_ascii_digit())
+            .unwrap_or(cell.len());
+        let (letters, digits) = cell.split_at(digits);
+        let column = if self.absolute_column { "$" } else { "" };
+        let row = if self.absolute_row { "$" } else { "" };
+        write!(f, "{column}{letters}{row}{digits}")
+    }
+}
+
+/// Part of cell text.
+#[derive(Debug)]
+pub(crate) enum Piece<'a> {
+    Text(&'a str),
+    Reference(Reference),
+    Range(Reference, Reference),
+}
+
+fn is_name_char(x: char) -> bool {
+    x.is_alphanumeric() || x == '_'
+}
+
+/// Splits text of cell into references, ranges and text between them.
+///
+/// Reference must not be part of longer name and must not be followed by `(`, so functions are never references.
+pub(crate) fn split(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut text_start = 0;
+    let mut position = 0;
+    while position < text.len() {
+        let previous = text[..position].chars().next_back();
+        let reference = if previous.is_some_and(is_name_char) {
+            None
+        } else {
+            parse_at(text, position)
+        };
+        let Some((first, mut end)) = reference else {
+            position += text[position..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        let mut piece = Piece::Reference(first);
+        if text[end..].starts_with(':') {
+            if let Some((last, range_end)) = parse_at(text, end + 1) {
+                piece = Piece::Range(first, last);
+                end = range_end;
+            }
+        }
+        if text_start < position {
+            pieces.push(Piece::Text(&text[text_start..position]));
+        }
+        pieces.push(piece);
+        position = end;
+        text_start = end;
+    }
+    if text_start < text.len() {
+        pieces.push(Piece::Text(&text[text_start..]));
+    }
+    pieces
+}
+
+fn parse_at(text: &str, position: usize) -> Option<(Reference, usize)> {
+    let (reference, length) = Reference::parse(&text[position..])?;
+    let end = position + length;
+    match text[end..].chars().next() {
+        Some(next) if is_name_char(next) || next == '(' || next == '$' => None,
+        _ => Some((reference, end)),
+    }
+}
+
+/// Returns number of cells of rectangle between `first` and `last`.
+pub(crate) fn range_size(first: CellRef, last: CellRef) -> u64 {
+    let rows = u64::from(first.row.abs_diff(last.row)) + 1;
+    let columns = u64::from(first.column.abs_diff(last.column)) + 1;
+    rows * columns
+}
+
+/// Returns cells of rectangle between `first` and `last` row by row.
+pub(crate) fn range(first: CellRef, last: CellRef) -> impl Iterator<Item = CellRef> {
+    let (top, bottom) = (first.row.min(last.row), first.row.max(last.row));
+    let (left, right) = (first.column.min(last.column), first.column.max(last.column));
+    (top..=bottom).flat_map(move |row| (left..=right).map(move |column| CellRef::new(column, row)))
+}
+
+/// Writes pieces back into text, references are changed by `map`.
+pub(crate) fn join(pieces: &[Piece<'_>], mut map: impl FnMut(Reference) -> Reference) -> String {
+    use crate::__lib::fmt::Write;
+    let mut text = String::new();
+    for piece in pieces {
+        // writing into String never fails
+        let _ = match piece {
+            Piece::Text(part) => write!(text, "{part}"),
+            Piece::Reference(reference) => write!(text, "{}", map(*reference)),
+            Piece::Range(first, last) => write!(text, "{}:{}", map(*first), map(*last)),
+        };
+    }
+    text
+}
+
+/// Writes pieces into expression, which can be parsed: `$` marks are removed and ranges are written as lists of cells.
+///
+/// Returns `None` if ranges have more than `max_cells` cells together.
+pub(crate) fn expand(pieces: &[Piece<'_>], max_cells: usize) -> Option<String> {
+    use crate::__lib::fmt::Write;
+    let cells = pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Range(first, last) => range_size(first.cell, last.cell),
+            _ => 0,
+        })
+        .fold(0, u64::saturating_add);
+    if usize::try_from(cells).map_or(true, |cells| cells > max_cells) {
+        return None;
+    }
+    let mut text = String::new();
+    for piece in pieces {
+        // writing into String never fails
+        let _ = match piece {
+            Piece::Text(part) => write!(text, "{part}"),
+            Piece::Reference(reference) => write!(text, "{}", reference.cell),
+            Piece::Range(first, last) => {
+                for (index, cell) in range(first.cell, last.cell).enumerate() {
+                    let separator = if index == 0 { "" } else { ", " };
+                    let _ = write!(text, "{separator}{cell}");
+                }
+                Ok(())
+            }
+        };
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::string::ToString;
+    use crate::__lib::vec::Vec;
+    use crate::sheet::reference::{expand, join, range, range_size, split, Piece};
+    use crate::sheet::CellRef;
+
+    #[test]
+    fn test_cell_names() {
+        for (name, column, row) in [
+            ("A1", 0, 0),
+            ("Z9", 25, 8),
+            ("AA10", 26, 9),
+            ("AZ1", 51, 0),
+            ("BA1", 52, 0),
+            ("ZZ1", 701, 0),
+            ("AAA1", 702, 0),
+        ] {
+            let cell: CellRef = name.parse().unwrap();
+            assert_eq!(cell, CellRef::new(column, row), "{name}");
+            assert_eq!(cell.to_string(), name);
+        }
+        for name in ["A0", "a1", "1A", "A", "AAAA1", "A1B", "A$", ""] {
+            assert!(name.parse::<CellRef>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let pieces = split("SUM(A1:$B$2) * C3 + LOG10(x) + ABCD1 + AB");
+        let kinds = pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => (*text).to_string(),
+                Piece::Reference(reference) => reference.cell.to_string(),
+                Piece::Range(first, last) => first.cell.to_string() + ":" + &last.cell.to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            ["SUM(", "A1:B2", ") * ", "C3", " + LOG10(x) + ABCD1 + AB"]
+        );
+        let shifted = join(&pieces, |reference| reference.shift(1, 2, 0, 1));
+        assert_eq!(shifted, "SUM(A2:$D$3) * E4 + LOG10(x) + ABCD1 + AB");
+        assert_eq!(
+            expand(&pieces, 4).unwrap(),
+            "SUM(A1, B1, A2, B2) * C3 + LOG10(x) + ABCD1 + AB"
+        );
+        assert!(expand(&pieces, 3).is_none());
+        assert!(expand(&split("SUM(A1:XFD1048576) + SUM(A1:A2)"), 1 << 34).is_none());
+    }
+
+    #[test]
+    fn test_range() {
+        let cells = range(CellRef::new(1, 1), CellRef::new(0, 0))
+            .map(|cell| cell.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(cells, ["A1", "B1", "A2", "B2"]);
+        let last = "XFD1048576".parse().unwrap();
+        assert_eq!(range_size(last, CellRef::new(0, 0)), 16384 * 1_048_576);
+    }
+}
@@ -0,0 +1,68 @@
+// Dense linear algebra for small systems, matrices are stored by rows in flat slices.
+#![allow(clippy::many_single_char_names, clippy::suboptimal_flops)]
+
+use crate::float::abs;
+
+/// Solves `matrix * x = rhs` by Gaussian elimination with partial pivoting, `rhs` is replaced by `x`.
+/// Returns `false` if matrix is singular.
+pub(crate) fn solve_linear(matrix: &mut [f64], rhs: &mut [f64]) -> bool {
+    let n = rhs.len();
+    debug_assert_eq!(matrix.len(), n * n);
+    let scale = matrix.iter().fold(0.0, |max: f64, x| max.max(abs(*x)));
+    if !(scale > 0.0 && scale.is_finite()) {
+        return false;
+    }
+    for column in 0..n {
+        let pivot = (column..n).fold(column, |best, row| {
+            if abs(matrix[row * n + column]) > abs(matrix[best * n + column]) {
+                row
+            } else {
+                best
+            }
+        });
+        if abs(matrix[pivot * n + column]) <= scale * f64::EPSILON * 16.0 {
+            return false;
+        }
+        if pivot != column {
+            for k in 0..n {
+                matrix.swap(pivot * n + k, column * n + k);
+            }
+            rhs.swap(pivot, column);
+        }
+        for row in column + 1..n {
+            let factor = matrix[row * n + column] / matrix[column * n + column];
+            if factor == 0.0 {
+                continue;
+            }
+            for k in column..n {
+                matrix[row * n + k] -= factor * matrix[column * n + k];
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+    for row in (0..n).rev() {
+        let mut sum = rhs[row];
+        for k in row + 1..n {
+            sum -= matrix[row * n + k] * rhs[k];
+        }
+        rhs[row] = sum / matrix[row * n + row];
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::solve_linear;
+
+    #[test]
+    fn test_solve_linear() {
+        let mut matrix = [0.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0];
+        let mut rhs = [7.0, 6.0, 13.0];
+        assert!(solve_linear(&mut matrix, &mut rhs));
+        for (x, expected) in rhs.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((x - expected).abs() < 1e-12);
+        }
+        let mut singular = [1.0, 2.0, 2.0, 4.0];
+        assert!(!solve_linear(&mut singular, &mut [1.0, 2.0]));
+    }
+}
@@ -0,0 +1,6 @@
+pub(crate) mod linear;
+pub(crate) mod root;
+mod system;
+
+pub use root::{Derivative, Method, Solution, SolverOptions, Start};
+pub use system::{solve_system, SystemSolution};
@@ -0,0 +1,440 @@
+// Brent's method compares points of bracket exactly, to find out which interpolation to use.
+#![allow(clippy::float_cmp)]
+
+use crate::__lib::string::format;
+use crate::float::abs;
+use crate::formulas::binding::Binding;
+use crate::formulas::calculus::ridders;
+#[cfg(doc)]
+use crate::formulas::FunctionLike;
+use crate::formulas::{EvaluationError, MathError, RootFormula};
+use crate::variable_stores::{GetVariable, Variable};
+
+/// Point from which root search starts.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Start {
+    /// Interval `[a, b]`, formula must have different signs at its ends. Root is searched by Brent's method.
+    Bracket(f64, f64),
+    /// Initial guess of root. Root is searched by Newton's method.
+    Guess(f64),
+}
+
+/// Derivative used by Newton's method.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub enum Derivative {
+    /// Derivative is calculated numerically by Ridders' method.
+    #[default]
+    Numeric,
+    /// Derivative is given by formula of the same variable, for example `2 * x` for `x^2 - 2`.
+    Formula(RootFormula),
+    /// Derivative is calculated exactly together with value of formula by automatic differentiation,
+    /// see [`FunctionLike::eval_derivative`]. Arithmetic operations, variables and most of functions
+    /// from [`math`](crate::formulas::math) are differentiated, other functions make search fail with [`MathError`].
+    Automatic,
+}
+
+/// Method which was used to find root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Method {
+    /// Brent's method.
+    Brent,
+    /// Newton's method.
+    Newton,
+}
+
+/// Options of root search.
+#[derive(Debug, Clone)]
+pub struct SolverOptions {
+    pub(crate) tolerance: f64,
+    pub(crate) max_iterations: usize,
+    derivative: Derivative,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-12,
+            max_iterations: 100,
+            derivative: Derivative::Numeric,
+        }
+    }
+}
+
+impl SolverOptions {
+    /// Creates default options: tolerance `1e-12`, maximum 100 iterations and numeric derivative.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets tolerance, search stops when root is known with this precision, relative to `max(1, |root|)`.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets maximum number of iterations, after which search is considered not converged.
+    #[must_use]
+    pub const fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets derivative used by Newton's method.
+    #[must_use]
+    pub fn with_derivative(mut self, derivative: Derivative) -> Self {
+        self.derivative = derivative;
+        self
+    }
+}
+
+/// Found root and information about convergence.
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    root: f64,
+    residual: f64,
+    error: f64,
+    iterations: usize,
+    method: Method,
+}
+
+impl Solution {
+    /// Returns found root.
+    pub const fn root(&self) -> f64 {
+        self.root
+    }
+
+    /// Returns value of formula at root.
+    pub const fn residual(&self) -> f64 {
+        self.residual
+    }
+
+    /// Returns estimation of root error.
+    pub const fn error(&self) -> f64 {
+        self.error
+    }
+
+    /// Returns number of iterations made.
+    pub const fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns method which was used to find root.
+    pub const fn method(&self) -> Method {
+        self.method
+    }
+}
+
+fn not_converged(method: &str, iterations: usize) -> EvaluationError {
+    EvaluationError::MathError(MathError::new(format!(
+        "root by {method} method, it did not converge in {iterations} iterations"
+    )))
+}
+
+// Brent's method, combines bisection, secant and inverse quadratic interpolation.
+#[allow(clippy::many_single_char_names, clippy::suboptimal_flops)]
+pub(crate) fn brent(
+    binding: &mut Binding<'_>,
+    lower: f64,
+    upper: f64,
+    options: &SolverOptions,
+) -> Result<Solution, EvaluationError> {
+    let (mut a, mut b) = (lower, upper);
+    let (mut fa, mut fb) = (binding.eval(a)?, binding.eval(b)?);
+    if fa == 0.0 || fb == 0.0 {
+        let (root, residual) = if fa == 0.0 { (a, fa) } else { (b, fb) };
+        return Ok(Solution {
+            root,
+            residual,
+            error: 0.0,
+            iterations: 0,
+            method: Method::Brent,
+        });
+    }
+    if fa.is_nan() || fb.is_nan() || (fa > 0.0) == (fb > 0.0) {
+        return Err(EvaluationError::MathError(MathError::new(format!(
+            "root by Brent method, formula has same sign at {a} and {b}"
+        ))));
+    }
+    let (mut c, mut fc) = (b, fb);
+    let mut d = b - a;
+    let mut e = d;
+    for iteration in 1..=options.max_iterations {
+        if (fb > 0.0) == (fc > 0.0) {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if abs(fc) < abs(fb) {
+            (a, b, c) = (b, c, b);
+            (fa, fb, fc) = (fb, fc, fb);
+        }
+        let tolerance = 2.0 * f64::EPSILON * abs(b) + 0.5 * options.tolerance * abs(b).max(1.0);
+        let middle = 0.5 * (c - b);
+        if abs(middle) <= tolerance || fb == 0.0 {
+            return Ok(Solution {
+                root: b,
+                residual: fb,
+                error: abs(middle),
+                iterations: iteration,
+                method: Method::Brent,
+            });
+        }
+        if abs(e) >= tolerance && abs(fa) > abs(fb) {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * middle * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * middle * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = abs(p);
+            if 2.0 * p < (3.0 * middle * q - abs(tolerance * q)).min(abs(e * q)) {
+                e = d;
+                d = p / q;
+            } else {
+                d = middle;
+                e = d;
+            }
+        } else {
+            d = middle;
+            e = d;
+        }
+        a = b;
+        fa = fb;
+        b += if abs(d) > tolerance {
+            d
+        } else if middle > 0.0 {
+            tolerance
+        } else {
+            -tolerance
+        };
+        fb = binding.eval(b)?;
+    }
+    Err(not_converged("Brent", options.max_iterations))
+}
+
+// Source of derivative for Newton's method.
+enum Slope<'a> {
+    Numeric,
+    Formula(Binding<'a>),
+    Automatic,
+}
+
+impl Slope<'_> {
+    // value and derivative of formula at `x`
+    fn eval(&mut self, binding: &mut Binding<'_>, x: f64) -> Result<(f64, f64), EvaluationError> {
+        match self {
+            Self::Numeric => Ok((binding.eval(x)?, ridders(binding, x)?)),
+            Self::Formula(derivative) => Ok((binding.eval(x)?, derivative.eval(x)?)),
+            Self::Automatic => binding.eval_derivative(x),
+        }
+    }
+}
+
+fn newton(
+    binding: &mut Binding<'_>,
+    mut slope: Slope<'_>,
+    guess: f64,
+    options: &SolverOptions,
+) -> Result<Solution, EvaluationError> {
+    let mut x = guess;
+    for iteration in 1..=options.max_iterations {
+        let (value, slope) = slope.eval(binding, x)?;
+        if value == 0.0 {
+            return Ok(Solution {
+                root: x,
+                residual: value,
+                error: 0.0,
+                iterations: iteration,
+                method: Method::Newton,
+            });
+        }
+        let step = value / slope;
+        if !step.is_finite() {
+            return Err(EvaluationError::MathError(MathError::new(format!(
+                "root by Newton method, derivative is {slope} at {x}"
+            ))));
+        }
+        x -= step;
+        if abs(step) <= options.tolerance * abs(x).max(1.0) {
+            return Ok(Solution {
+                root: x,
+                residual: binding.eval(x)?,
+                error: abs(step),
+                iterations: iteration,
+                method: Method::Newton,
+            });
+        }
+    }
+    Err(not_converged("Newton", options.max_iterations))
+}
+
+impl RootFormula {
+    /// Finds value of `variable`, for which formula evaluates to zero. Other variables are taken from `store`.
+    /// Uses Brent's method for [`Start::Bracket`] and Newton's method for [`Start::Guess`],
+    /// with numeric derivative unless other [`Derivative`] is set by [`RootFormula::solve_for_with`].
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if formula fails to evaluate, bracket does not contain sign change or search did not converge.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use evaluatorrs::formulas::RootFormula;
+    /// use evaluatorrs::function_stores::EmptyFunctionStore;
+    /// use evaluatorrs::solvers::Start;
+    /// use evaluatorrs::variable_stores::{EmptyVariableStore, Variable};
+    ///
+    /// let formula = RootFormula::parse("x * x - 2", &EmptyFunctionStore).unwrap();
+    /// let solution = formula
+    ///     .solve_for(&Variable::new("x"), Start::Bracket(0.0, 2.0), &EmptyVariableStore)
+    ///     .unwrap();
+    /// assert!((solution.root() - 2.0_f64.sqrt()).abs() < 1e-12);
+    /// ```
+    pub fn solve_for(
+        &self,
+        variable: &Variable,
+        start: Start,
+        store: &dyn GetVariable,
+    ) -> Result<Solution, EvaluationError> {
+        self.solve_for_with(variable, start, store, &SolverOptions::default())
+    }
+
+    /// Same as [`RootFormula::solve_for`], but with given `options`.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if formula fails to evaluate, bracket does not contain sign change or search did not converge.
+    pub fn solve_for_with(
+        &self,
+        variable: &Variable,
+        start: Start,
+        store: &dyn GetVariable,
+        options: &SolverOptions,
+    ) -> Result<Solution, EvaluationError> {
+        let mut binding = Binding::new(self, variable, store);
+        match start {
+            Start::Bracket(lower, upper) => brent(&mut binding, lower, upper, options),
+            Start::Guess(guess) => {
+                let slope = match &options.derivative {
+                    Derivative::Numeric => Slope::Numeric,
+                    Derivative::Formula(derivative) => {
+                        Slope::Formula(Binding::new(derivative, variable, store))
+                    }
+                    Derivative::Automatic => Slope::Automatic,
+                };
+                newton(&mut binding, slope, guess, options)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::math::{Gamma, Sin};
+    use crate::formulas::{EvaluationError, FunctionLike, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::solvers::{Derivative, Method, SolverOptions, Start};
+    use crate::variable_stores::{
+        EmptyVariableStore, LayeredVariableStore, SetVariable, Variable, VectorVariableStore,
+    };
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    #[test]
+    fn test_brent() {
+        let mut store = VectorVariableStore::new();
+        store.set("price", 4.0);
+        store.set("cost", 100.0);
+        let profit = parse("price * units - cost");
+        let solution = profit
+            .solve_for(&Variable::new("units"), Start::Bracket(0.0, 1000.0), &store)
+            .unwrap();
+        assert_eq!(solution.method(), Method::Brent);
+        assert!((solution.root() - 25.0).abs() < 1e-9);
+        assert!(solution.residual().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_newton() {
+        let formula = parse("x^3 - 2 * x - 5");
+        let root = 2.094_551_481_542_326_5;
+        let solution = formula
+            .solve_for(&Variable::new("x"), Start::Guess(2.0), &EmptyVariableStore)
+            .unwrap();
+        assert_eq!(solution.method(), Method::Newton);
+        assert!((solution.root() - root).abs() < 1e-12);
+
+        let options =
+            SolverOptions::new().with_derivative(Derivative::Formula(parse("3 * x^2 - 2")));
+        let solution = formula
+            .solve_for_with(
+                &Variable::new("x"),
+                Start::Guess(2.0),
+                &EmptyVariableStore,
+                &options,
+            )
+            .unwrap();
+        assert!((solution.root() - root).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_automatic_derivative() {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<Sin>();
+        functions.register::<Gamma>();
+        let mut store = VectorVariableStore::new();
+        store.set("scale", 2.0);
+        store.set(
+            "y",
+            RootFormula::parse("x ^ 3 / scale", &functions).unwrap(),
+        );
+        let formula = RootFormula::parse("sin(x) + y - 1", &functions).unwrap();
+        let x = Variable::new("x");
+        let mut variables = LayeredVariableStore::new(&store);
+        variables.set("x", 0.5);
+        let (value, derivative) = formula.eval_derivative(&variables, &x).unwrap();
+        assert!((value - (0.5_f64.sin() + 0.0625 - 1.0)).abs() < 1e-15);
+        assert!((derivative - (0.5_f64.cos() + 0.375)).abs() < 1e-15);
+
+        let options = SolverOptions::new().with_derivative(Derivative::Automatic);
+        let solution = formula
+            .solve_for_with(&x, Start::Guess(0.5), &store, &options)
+            .unwrap();
+        assert_eq!(solution.method(), Method::Newton);
+        assert!(solution.residual().abs() < 1e-12);
+
+        let formula = RootFormula::parse("gamma(x) - 2", &functions).unwrap();
+        assert!(matches!(
+            formula.solve_for_with(&x, Start::Guess(3.0), &store, &options),
+            Err(EvaluationError::MathError(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        let formula = parse("x * x + 1");
+        let variable = Variable::new("x");
+        assert!(formula
+            .solve_for(&variable, Start::Bracket(-1.0, 1.0), &EmptyVariableStore)
+            .is_err());
+        let options = SolverOptions::new().with_max_iterations(5);
+        assert!(formula
+            .solve_for_with(&variable, Start::Guess(0.5), &EmptyVariableStore, &options)
+            .is_err());
+    }
+}
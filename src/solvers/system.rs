@@ -0,0 +1,249 @@
+use crate::__lib::string::format;
+use crate::__lib::vec::{vec, Vec};
+use crate::float::{abs, sqrt};
+use crate::formulas::binding::MultiBinding;
+use crate::formulas::{EvaluationError, MathError, RootFormula};
+use crate::solvers::linear::solve_linear;
+use crate::solvers::SolverOptions;
+use crate::variable_stores::{GetVariable, SetVariable, Variable};
+
+/// Solution of system of equations and information about convergence.
+#[derive(Debug, Clone)]
+pub struct SystemSolution {
+    values: Vec<f64>,
+    residual: f64,
+    iterations: usize,
+}
+
+impl SystemSolution {
+    /// Returns found values of unknowns, in the same order as unknowns.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Returns maximum absolute value of equations at solution.
+    pub const fn residual(&self) -> f64 {
+        self.residual
+    }
+
+    /// Returns number of iterations made.
+    pub const fn iterations(&self) -> usize {
+        self.iterations
+    }
+}
+
+struct System<'a> {
+    equations: &'a [RootFormula],
+    binding: MultiBinding<'a>,
+}
+
+impl System<'_> {
+    fn residuals(&mut self, point: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+        self.binding.set(point);
+        self.equations
+            .iter()
+            .map(|equation| self.binding.eval(equation))
+            .collect()
+    }
+
+    // Jacobian by central differences, stored by rows
+    fn jacobian(&mut self, point: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+        let n = point.len();
+        let mut jacobian = vec![0.0; n * n];
+        let mut shifted = point.to_vec();
+        for column in 0..n {
+            let step = sqrt(f64::EPSILON) * abs(point[column]).max(1.0);
+            shifted[column] = point[column] + step;
+            let forward = self.residuals(&shifted)?;
+            shifted[column] = point[column] - step;
+            let backward = self.residuals(&shifted)?;
+            shifted[column] = point[column];
+            for (row, (forward, backward)) in forward.iter().zip(backward).enumerate() {
+                jacobian[row * n + column] = (forward - backward) / (2.0 * step);
+            }
+        }
+        Ok(jacobian)
+    }
+}
+
+fn max_norm(values: &[f64]) -> f64 {
+    values.iter().fold(0.0, |max: f64, x| max.max(abs(*x)))
+}
+
+fn merit(values: &[f64]) -> f64 {
+    values.iter().map(|x| x * x).sum::<f64>() / 2.0
+}
+
+fn system_error(message: &str) -> EvaluationError {
+    EvaluationError::MathError(MathError::new(format!("system of equations, {message}")))
+}
+
+/// Solves system of equations `equations[i] = 0` for `unknowns`, found values are written back to `store`.
+///
+/// System is solved by Newton-Raphson method with numeric Jacobian and backtracking line search.
+/// Initial guesses of unknowns are taken from `store`, as well as other variables.
+///
+/// Search stops when all equations are less than tolerance of `options` by absolute value, or when step becomes
+/// smaller than tolerance, relative to `max(1, |unknown|)`. Derivative of `options` is not used.
+///
+/// # Errors
+///
+/// Will return Err if numbers of equations and unknowns differ, equations fail to evaluate,
+/// Jacobian is singular or search did not converge.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::solvers::{solve_system, SolverOptions};
+/// use evaluatorrs::variable_stores::{GetVariable, SetVariable, Variable, VectorVariableStore};
+///
+/// let equations = [
+///     RootFormula::parse("x + y - 3", &EmptyFunctionStore).unwrap(),
+///     RootFormula::parse("x * y - 2", &EmptyFunctionStore).unwrap(),
+/// ];
+/// let unknowns = [Variable::new("x"), Variable::new("y")];
+/// let mut store = VectorVariableStore::new();
+/// store.set("x", 1.5);
+/// store.set("y", 0.0);
+/// solve_system(&equations, &unknowns, &mut store, &SolverOptions::new()).unwrap();
+/// assert!((store.eval(&unknowns[0]).unwrap() - 2.0).abs() < 1e-9);
+/// ```
+pub fn solve_system<S: GetVariable + SetVariable>(
+    equations: &[RootFormula],
+    unknowns: &[Variable],
+    store: &mut S,
+    options: &SolverOptions,
+) -> Result<SystemSolution, EvaluationError> {
+    if equations.len() != unknowns.len() {
+        return Err(system_error(&format!(
+            "number of equations {} differs from number of unknowns {}",
+            equations.len(),
+            unknowns.len()
+        )));
+    }
+    let mut point = unknowns
+        .iter()
+        .map(|unknown| store.eval(unknown))
+        .collect::<Result<Vec<_>, _>>()?;
+    let solution = newton_raphson(equations, unknowns, &*store, &mut point, options)?;
+    for (unknown, value) in unknowns.iter().zip(&point) {
+        store.set(unknown.clone(), *value);
+    }
+    Ok(solution)
+}
+
+// `mul_add` is not available without std.
+#[allow(clippy::suboptimal_flops)]
+fn newton_raphson(
+    equations: &[RootFormula],
+    unknowns: &[Variable],
+    store: &dyn GetVariable,
+    point: &mut Vec<f64>,
+    options: &SolverOptions,
+) -> Result<SystemSolution, EvaluationError> {
+    const MAX_BACKTRACKS: usize = 30;
+    const DECREASE: f64 = 1e-4;
+
+    let mut system = System {
+        equations,
+        binding: MultiBinding::new(unknowns, store),
+    };
+    let mut residuals = system.residuals(point)?;
+    for iteration in 0..=options.max_iterations {
+        let residual = max_norm(&residuals);
+        if residual <= options.tolerance {
+            return Ok(SystemSolution {
+                values: point.clone(),
+                residual,
+                iterations: iteration,
+            });
+        }
+        if iteration == options.max_iterations {
+            break;
+        }
+        let mut jacobian = system.jacobian(point)?;
+        let mut step = residuals.iter().map(|x| -x).collect::<Vec<_>>();
+        if !solve_linear(&mut jacobian, &mut step) {
+            return Err(system_error("Jacobian is singular"));
+        }
+        let current = merit(&residuals);
+        let mut scale = 1.0;
+        let mut accepted = None;
+        for _ in 0..MAX_BACKTRACKS {
+            let candidate = point
+                .iter()
+                .zip(&step)
+                .map(|(x, dx)| x + scale * dx)
+                .collect::<Vec<_>>();
+            let candidate_residuals = system.residuals(&candidate)?;
+            if merit(&candidate_residuals) <= (1.0 - DECREASE * scale) * current {
+                accepted = Some((candidate, candidate_residuals));
+                break;
+            }
+            scale /= 2.0;
+        }
+        let Some((candidate, candidate_residuals)) = accepted else {
+            return Err(system_error("line search failed to decrease residuals"));
+        };
+        let step_is_small = point
+            .iter()
+            .zip(&candidate)
+            .all(|(x, new)| abs(new - x) <= options.tolerance * abs(*new).max(1.0));
+        *point = candidate;
+        residuals = candidate_residuals;
+        if step_is_small {
+            return Ok(SystemSolution {
+                values: point.clone(),
+                residual: max_norm(&residuals),
+                iterations: iteration + 1,
+            });
+        }
+    }
+    Err(system_error(&format!(
+        "it did not converge in {} iterations",
+        options.max_iterations
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::RootFormula;
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::solvers::{solve_system, SolverOptions};
+    use crate::variable_stores::{GetVariable, SetVariable, Variable, VectorVariableStore};
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    #[test]
+    fn test_solve_system() {
+        let equations = [parse("x^2 + y^2 - r^2"), parse("y - k * x")];
+        let unknowns = [Variable::new("x"), Variable::new("y")];
+        let mut store = VectorVariableStore::new();
+        store.set("r", 5.0);
+        store.set("k", 0.75);
+        store.set("x", 10.0);
+        store.set("y", 1.0);
+        let solution =
+            solve_system(&equations, &unknowns, &mut store, &SolverOptions::new()).unwrap();
+        assert!(solution.residual() < 1e-9);
+        assert!((solution.values()[0] - 4.0).abs() < 1e-9);
+        assert!((store.eval(&unknowns[1]).unwrap() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_errors() {
+        let unknowns = [Variable::new("x"), Variable::new("y")];
+        let mut store = VectorVariableStore::new();
+        store.set("x", 1.0);
+        store.set("y", 1.0);
+        let options = SolverOptions::new();
+        assert!(solve_system(&[parse("x - y")], &unknowns, &mut store, &options).is_err());
+        let singular = [parse("x + y - 1"), parse("2 * x + 2 * y - 2")];
+        assert!(solve_system(&singular, &unknowns, &mut store, &options).is_err());
+        let without_guess = [Variable::new("x"), Variable::new("z")];
+        assert!(solve_system(&singular, &without_guess, &mut store, &options).is_err());
+    }
+}
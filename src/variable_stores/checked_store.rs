@@ -0,0 +1,169 @@
+use crate::__lib::collections::BTreeSet;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::{vec, Vec};
+use crate::formulas::{CycleError, RootFormula};
+use crate::variable_stores::{
+    EvaluationBudget, GetVariable, ListVariables, PopVariable, ResolutionChain, SetVariable,
+    Variable,
+};
+
+/// Variable store, that refuses to set variable, if it makes variables depend on each other in a cycle.
+///
+/// Wraps other variable store, variables can be set only by [`CheckedVariableStore::try_set`].
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{CheckedVariableStore, Variable, VectorVariableStore};
+///
+/// let mut store = CheckedVariableStore::new(VectorVariableStore::new());
+/// store.try_set("a", RootFormula::parse("b + 1", &EmptyFunctionStore).unwrap()).unwrap();
+/// let error = store.try_set("b", RootFormula::parse("a * 2", &EmptyFunctionStore).unwrap()).unwrap_err();
+/// assert_eq!(error.variables(), [Variable::new("b"), Variable::new("a")]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CheckedVariableStore<S>(S);
+
+impl<S> CheckedVariableStore<S> {
+    /// Creates `CheckedVariableStore` on top of `store`, variables already stored in it are not checked.
+    pub const fn new(store: S) -> Self {
+        Self(store)
+    }
+
+    /// Returns wrapped store.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S: GetVariable + SetVariable> CheckedVariableStore<S> {
+    /// Sets variable by `value`, if it does not make variable depend on itself.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err with variables of cycle, starting from `name`, if `value` depends on `name`
+    /// directly or through other stored variables. Store is not changed in this case.
+    pub fn try_set(
+        &mut self,
+        name: impl Into<Variable>,
+        value: impl Into<RootFormula>,
+    ) -> Result<(), CycleError> {
+        let name = name.into();
+        let value = value.into();
+        if let Some(cycle) = find_cycle(&self.0, &name, &value) {
+            return Err(CycleError::new(cycle));
+        }
+        self.0.set(name, value);
+        Ok(())
+    }
+}
+
+// Depth first search of path from variables of `formula` back to `name`, stack is kept on heap,
+// so long chains of variables can not overflow it.
+fn find_cycle(
+    store: &dyn GetVariable,
+    name: &Variable,
+    formula: &RootFormula,
+) -> Option<Vec<Variable>> {
+    let mut visited = BTreeSet::new();
+    let mut path = vec![(name.clone(), formula.variables())];
+    while let Some((_, dependencies)) = path.last_mut() {
+        let Some(next) = dependencies.pop() else {
+            path.pop();
+            continue;
+        };
+        if next == *name {
+            return Some(path.into_iter().map(|(variable, _)| variable).collect());
+        }
+        if visited.contains(&next) {
+            continue;
+        }
+        // missing variables can not be part of cycle
+        if let Some(formula) = store.get(&next) {
+            let dependencies = formula.variables();
+            visited.insert(next.clone());
+            path.push((next, dependencies));
+        }
+    }
+    None
+}
+
+impl<S: GetVariable> GetVariable for CheckedVariableStore<S> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.0.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.0.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.0.budget()
+    }
+}
+
+impl<S: ListVariables> ListVariables for CheckedVariableStore<S> {
+    fn variables(&self) -> Vec<Variable> {
+        self.0.variables()
+    }
+}
+
+impl<S: PopVariable> PopVariable for CheckedVariableStore<S> {
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        self.0.pop(variable)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::RootFormula;
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        CheckedVariableStore, GetVariable, PopVariable, Variable, VectorVariableStore,
+    };
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    #[test]
+    fn test_try_set() {
+        let mut store = CheckedVariableStore::new(VectorVariableStore::new());
+        store.try_set("a", parse("b + c")).unwrap();
+        store.try_set("b", parse("c * 2")).unwrap();
+        store.try_set("c", 1.0).unwrap();
+        assert!((store.eval(&Variable::new("a")).unwrap() - 3.0).abs() < f64::EPSILON);
+        let error = store.try_set("c", parse("a - 1")).unwrap_err();
+        assert_eq!(error.variables()[0], Variable::new("c"));
+        assert_eq!(error.variables()[1], Variable::new("a"));
+        let error = store.try_set("d", parse("d + 1")).unwrap_err();
+        assert_eq!(error.variables(), [Variable::new("d")]);
+        // failed attempts do not change store
+        assert!((store.eval(&Variable::new("a")).unwrap() - 3.0).abs() < f64::EPSILON);
+        assert!(store.get(&Variable::new("d")).is_none());
+        // replacing variable removes its old dependencies
+        store.try_set("b", 2.0).unwrap();
+        store.try_set("c", parse("b + 1")).unwrap();
+        assert!(store.pop(&Variable::new("c")).is_some());
+        store.try_set("c", parse("a + 1")).unwrap_err();
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let mut store = CheckedVariableStore::new(VectorVariableStore::new());
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        for pair in names.windows(2) {
+            store.try_set(pair[0], parse(pair[1])).unwrap();
+        }
+        let error = store.try_set("h", parse("x + a")).unwrap_err();
+        let mut expected = names.map(Variable::new);
+        expected.rotate_right(1);
+        assert_eq!(error.variables(), expected.as_slice());
+        store.try_set("h", parse("x + 1")).unwrap();
+    }
+}
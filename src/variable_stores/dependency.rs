@@ -0,0 +1,347 @@
+use crate::__lib::collections::BTreeMap;
+use crate::__lib::fmt::Write;
+use crate::__lib::string::String;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::{vec, Vec};
+use crate::formulas::{CycleError, RootFormula};
+use crate::variable_stores::{GetVariable, Variable};
+
+/// Graph of dependencies between variables, edge leads from variable to variable, which formula depends on.
+///
+/// Variables, which are used by formulas but are not stored, are part of graph without dependencies.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{ListVariables, SetVariable, Variable, VectorVariableStore};
+///
+/// let mut store = VectorVariableStore::new();
+/// store.set("total", RootFormula::parse("price * count", &EmptyFunctionStore).unwrap());
+/// store.set("price", RootFormula::parse("base + tax", &EmptyFunctionStore).unwrap());
+/// let graph = store.dependency_graph();
+/// assert_eq!(graph.affected(&Variable::new("tax")), [&Variable::new("price"), &Variable::new("total")]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DependencyGraph {
+    variables: Vec<Variable>,
+    indices: BTreeMap<Variable, usize>,
+    formulas: Vec<Option<Arc<RootFormula>>>,
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+impl DependencyGraph {
+    /// Creates graph of `variables` and all variables they depend on directly or through other stored variables.
+    pub fn new(store: &dyn GetVariable, variables: &[Variable]) -> Self {
+        let mut graph = Self {
+            variables: Vec::new(),
+            indices: BTreeMap::new(),
+            formulas: Vec::new(),
+            dependencies: Vec::new(),
+            dependents: Vec::new(),
+        };
+        for variable in variables {
+            graph.node(store, variable);
+        }
+        let mut node = 0;
+        while node < graph.variables.len() {
+            let variables = graph.formulas[node]
+                .as_ref()
+                .map(|formula| formula.variables())
+                .unwrap_or_default();
+            let dependencies = variables
+                .iter()
+                .map(|variable| graph.node(store, variable))
+                .collect();
+            graph.dependencies.push(dependencies);
+            node += 1;
+        }
+        graph.dependents = vec![Vec::new(); graph.variables.len()];
+        for (node, dependencies) in graph.dependencies.iter().enumerate() {
+            for dependency in dependencies {
+                graph.dependents[*dependency].push(node);
+            }
+        }
+        graph
+    }
+
+    fn node(&mut self, store: &dyn GetVariable, variable: &Variable) -> usize {
+        if let Some(node) = self.index(variable) {
+            return node;
+        }
+        let node = self.variables.len();
+        self.variables.push(variable.clone());
+        self.indices.insert(variable.clone(), node);
+        self.formulas.push(store.get(variable).cloned());
+        node
+    }
+
+    fn index(&self, variable: &Variable) -> Option<usize> {
+        self.indices.get(variable).copied()
+    }
+
+    fn collect(&self, nodes: &[usize]) -> Vec<&Variable> {
+        nodes.iter().map(|node| self.variable(*node)).collect()
+    }
+
+    /// Returns all variables of graph.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    /// Returns variables, which formula of `variable` uses directly.
+    pub fn dependencies(&self, variable: &Variable) -> Vec<&Variable> {
+        self.index(variable)
+            .map_or_else(Vec::new, |node| self.collect(&self.dependencies[node]))
+    }
+
+    /// Returns variables, which formulas use `variable` directly.
+    pub fn dependents(&self, variable: &Variable) -> Vec<&Variable> {
+        self.index(variable)
+            .map_or_else(Vec::new, |node| self.collect(&self.dependents[node]))
+    }
+
+    /// Returns variables, which depend on `variable` directly or through other variables,
+    /// so their values may change, when `variable` changes.
+    ///
+    /// Variables are returned in the same order as in [`DependencyGraph::topological_order`],
+    /// variables of cycles are returned in unspecified order.
+    pub fn affected(&self, variable: &Variable) -> Vec<&Variable> {
+        let Some(start) = self.index(variable) else {
+            return Vec::new();
+        };
+        let mut reached = vec![false; self.variables.len()];
+        let mut queue = vec![start];
+        while let Some(node) = queue.pop() {
+            for dependent in &self.dependents[node] {
+                if !reached[*dependent] {
+                    reached[*dependent] = true;
+                    queue.push(*dependent);
+                }
+            }
+        }
+        self.components()
+            .into_iter()
+            .flatten()
+            .filter(|node| reached[*node])
+            .map(|node| &self.variables[node])
+            .collect()
+    }
+
+    /// Returns variables ordered so, that every variable goes after variables it depends on.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err with variables of cycle, if variables depend on each other in a cycle.
+    pub fn topological_order(&self) -> Result<Vec<&Variable>, CycleError> {
+        let mut order = Vec::with_capacity(self.variables.len());
+        for component in self.components() {
+            if let [node] = component[..] {
+                if !self.depends_on_itself(node) {
+                    order.push(self.variable(node));
+                    continue;
+                }
+            }
+            return Err(CycleError::new(self.cycle(&component)));
+        }
+        Ok(order)
+    }
+
+    // Finds cycle inside of strongly connected component, following dependencies until some variable repeats.
+    // Search starts from the earliest variable of graph, so cycle does not depend on order of component.
+    fn cycle(&self, component: &[usize]) -> Vec<Variable> {
+        let mut inside = vec![false; self.variables.len()];
+        for node in component {
+            inside[*node] = true;
+        }
+        // position of every node in path, so repeated node is found without searching path
+        let mut positions: Vec<Option<usize>> = vec![None; self.variables.len()];
+        let mut path = vec![component.iter().copied().min().unwrap_or_default()];
+        loop {
+            let node = path[path.len() - 1];
+            positions[node] = Some(path.len() - 1);
+            let next = self.dependencies[node]
+                .iter()
+                .copied()
+                .find(|next| inside[*next])
+                .unwrap_or(node);
+            if let Some(start) = positions[next] {
+                return self.collect(&path[start..]).into_iter().cloned().collect();
+            }
+            path.push(next);
+        }
+    }
+
+    /// Returns graph in DOT format, edge leads from variable to variable, which depends on it.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (node, variable) in self.variables.iter().enumerate() {
+            if self.dependencies[node].is_empty() && self.dependents[node].is_empty() {
+                // writing into String never fails
+                let _ = writeln!(dot, "    {:?};", variable.as_ref());
+            }
+            for dependency in &self.dependencies[node] {
+                let _ = writeln!(
+                    dot,
+                    "    {:?} -> {:?};",
+                    self.variable(*dependency).as_ref(),
+                    variable.as_ref()
+                );
+            }
+        }
+        dot.push('}');
+        dot
+    }
+
+    // formula of variable, `None` if variable is not stored
+    #[cfg(any(feature = "std", feature = "libm"))]
+    pub(crate) fn formula(&self, node: usize) -> Option<&Arc<RootFormula>> {
+        self.formulas[node].as_ref()
+    }
+
+    pub(crate) fn variable(&self, node: usize) -> &Variable {
+        &self.variables[node]
+    }
+
+    pub(crate) fn depends_on_itself(&self, node: usize) -> bool {
+        self.dependencies[node].contains(&node)
+    }
+
+    // Tarjan's algorithm with explicit stack, components are returned after components they depend on.
+    pub(crate) fn components(&self) -> Vec<Vec<usize>> {
+        let count = self.variables.len();
+        let mut index = vec![None; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+        for root in 0..count {
+            if index[root].is_some() {
+                continue;
+            }
+            let mut calls = vec![(root, 0)];
+            index[root] = Some(next_index);
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((node, edge)) = calls.last_mut() {
+                let node = *node;
+                if let Some(&next) = self.dependencies[node].get(*edge) {
+                    *edge += 1;
+                    match index[next] {
+                        None => {
+                            index[next] = Some(next_index);
+                            low[next] = next_index;
+                            next_index += 1;
+                            stack.push(next);
+                            on_stack[next] = true;
+                            calls.push((next, 0));
+                        }
+                        Some(next_index) if on_stack[next] => low[node] = low[node].min(next_index),
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some((parent, _)) = calls.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+                if Some(low[node]) == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::string::String;
+    use crate::__lib::vec::Vec;
+    use crate::formulas::RootFormula;
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        DependencyGraph, ListVariables, SetVariable, Variable, VectorVariableStore,
+    };
+
+    fn store(definitions: &[(&str, &str)]) -> VectorVariableStore {
+        let mut store = VectorVariableStore::new();
+        for (name, expression) in definitions {
+            store.set(
+                *name,
+                RootFormula::parse(expression, &EmptyFunctionStore).unwrap(),
+            );
+        }
+        store
+    }
+
+    fn names(variables: &[&Variable]) -> Vec<String> {
+        variables
+            .iter()
+            .map(|variable| String::from(variable.as_ref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let store = store(&[("a", "b + c"), ("b", "c * d"), ("c", "2"), ("e", "a")]);
+        let graph = store.dependency_graph();
+        assert_eq!(graph.variables().len(), 5);
+        let a = Variable::new("a");
+        let c = Variable::new("c");
+        assert_eq!(names(&graph.dependencies(&a)), ["b", "c"]);
+        assert_eq!(names(&graph.dependents(&c)), ["a", "b"]);
+        assert_eq!(names(&graph.affected(&c)), ["b", "a", "e"]);
+        assert_eq!(names(&graph.affected(&Variable::new("d"))), ["b", "a", "e"]);
+        assert!(graph.affected(&Variable::new("e")).is_empty());
+        assert!(graph.dependencies(&Variable::new("missing")).is_empty());
+        let order = graph.topological_order().unwrap();
+        for variable in &order {
+            let position = order.iter().position(|x| x == variable);
+            for dependency in graph.dependencies(variable) {
+                assert!(order.iter().position(|x| *x == dependency) < position);
+            }
+        }
+        let graph = DependencyGraph::new(&store, &[Variable::new("b")]);
+        assert_eq!(graph.variables().len(), 3);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let store = store(&[("a", "b + 1"), ("b", "c"), ("c", "a * b"), ("d", "d")]);
+        let error = DependencyGraph::new(&store, &[Variable::new("a")])
+            .topological_order()
+            .unwrap_err();
+        assert_eq!(
+            error.variables(),
+            [Variable::new("a"), Variable::new("b"), Variable::new("c")]
+        );
+        let error = DependencyGraph::new(&store, &[Variable::new("d")])
+            .topological_order()
+            .unwrap_err();
+        assert_eq!(error.variables(), [Variable::new("d")]);
+        let graph = store.dependency_graph();
+        assert_eq!(names(&graph.affected(&Variable::new("c"))).len(), 3);
+    }
+
+    #[test]
+    fn test_dot() {
+        let store = store(&[("a", "b + c"), ("d", "1")]);
+        assert_eq!(
+            store.dependency_graph().to_dot(),
+            "digraph dependencies {\n    \"b\" -> \"a\";\n    \"c\" -> \"a\";\n    \"d\";\n}"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
 use crate::formulas::RootFormula;
-use crate::variable_stores::{GetVariable, PopVariable, SetVariable, Variable};
+use crate::variable_stores::{GetVariable, ListVariables, PopVariable, SetVariable, Variable};
 
 /// Variable store that can not contain any functions.
 #[derive(Debug, Copy, Clone)]
@@ -20,6 +21,12 @@ impl SetVariable for EmptyVariableStore {
     fn set(&mut self, _name: impl Into<Variable>, _value: impl Into<RootFormula>) {}
 }
 
+impl ListVariables for EmptyVariableStore {
+    fn variables(&self) -> Vec<Variable> {
+        Vec::new()
+    }
+}
+
 impl PopVariable for EmptyVariableStore {
     fn pop(&mut self, _variable: &Variable) -> Option<Arc<RootFormula>> {
         None
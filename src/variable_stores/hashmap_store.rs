@@ -1,5 +1,5 @@
 use crate::formulas::RootFormula;
-use crate::variable_stores::{GetVariable, PopVariable, SetVariable, Variable};
+use crate::variable_stores::{GetVariable, ListVariables, PopVariable, SetVariable, Variable};
 // We can use std, because this module is not imported, when no_std feature is enabled.
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -31,6 +31,15 @@ impl SetVariable for HashMapVariableStore {
     }
 }
 
+impl ListVariables for HashMapVariableStore {
+    /// Returns variables in sorted order.
+    fn variables(&self) -> Vec<Variable> {
+        let mut variables = self.0.keys().cloned().collect::<Vec<_>>();
+        variables.sort();
+        variables
+    }
+}
+
 impl PopVariable for HashMapVariableStore {
     fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
         self.0.remove(variable)
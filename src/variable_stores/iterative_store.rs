@@ -0,0 +1,295 @@
+use crate::__lib::slice;
+use crate::__lib::string::{format, String};
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::float::abs;
+use crate::formulas::{Evaluate, EvaluationError, MathError, NoVariableError, RootFormula};
+use crate::variable_stores::{
+    DependencyGraph, EvaluationBudget, GetVariable, LayeredVariableStore, ListVariables,
+    PopVariable, ResolutionChain, SetVariable, Variable,
+};
+
+/// Options of fixed-point iteration used by [`IterativeVariableStore`].
+#[derive(Debug, Clone, Copy)]
+pub struct IterationOptions {
+    tolerance: f64,
+    max_iterations: usize,
+    initial: f64,
+}
+
+impl Default for IterationOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-10,
+            max_iterations: 100,
+            initial: 0.0,
+        }
+    }
+}
+
+impl IterationOptions {
+    /// Creates default options: tolerance `1e-10`, maximum 100 iterations and initial value `0`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets tolerance, iteration stops when no variable changes more than tolerance, relative to `max(1, |value|)`.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets maximum number of iterations, after which variables are considered not converged.
+    #[must_use]
+    pub const fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets value, which variables of cycle have before the first iteration.
+    #[must_use]
+    pub const fn with_initial(mut self, initial: f64) -> Self {
+        self.initial = initial;
+        self
+    }
+}
+
+/// Variable store, that allows variables to depend on each other in a cycle.
+///
+/// Variables are split into strongly connected components, every component is evaluated after components it depends on.
+/// Variables of component with cycle are solved by fixed-point iteration, every iteration evaluates them one by one
+/// using the latest values, like iterative calculation of spreadsheets.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{
+///     GetVariable, IterationOptions, IterativeVariableStore, SetVariable, Variable, VectorVariableStore,
+/// };
+///
+/// let mut store = IterativeVariableStore::with_store(VectorVariableStore::new(), IterationOptions::new());
+/// store.set("balance", RootFormula::parse("1000 + interest", &EmptyFunctionStore).unwrap());
+/// store.set("interest", RootFormula::parse("balance * 0.05", &EmptyFunctionStore).unwrap());
+/// let balance = store.eval(&Variable::new("balance")).unwrap();
+/// assert!((balance - 1000.0 / 0.95).abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IterativeVariableStore<S> {
+    store: S,
+    options: IterationOptions,
+}
+
+impl<S: Default> IterativeVariableStore<S> {
+    /// Creates empty `IterativeVariableStore`.
+    pub fn new(options: IterationOptions) -> Self {
+        Self::with_store(S::default(), options)
+    }
+}
+
+impl<S> IterativeVariableStore<S> {
+    /// Creates `IterativeVariableStore` on top of `store`.
+    pub const fn with_store(store: S, options: IterationOptions) -> Self {
+        Self { store, options }
+    }
+
+    /// Returns wrapped store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S: GetVariable> IterativeVariableStore<S> {
+    /// Evaluates `variables` together, so every cycle is solved only once.
+    ///
+    /// Returns values in the same order as variables.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if variable is not in store, formula failed to evaluate or cycle did not converge.
+    pub fn eval_all(&self, variables: &[Variable]) -> Result<Vec<f64>, EvaluationError> {
+        let graph = DependencyGraph::new(&self.store, variables);
+        let mut solved = LayeredVariableStore::new(&self.store);
+        for component in graph.components() {
+            self.solve(&graph, &component, &mut solved)?;
+        }
+        variables
+            .iter()
+            .map(|variable| {
+                solved
+                    .get(variable)
+                    .ok_or_else(|| {
+                        EvaluationError::NoVariableError(NoVariableError::new(variable.clone()))
+                    })
+                    .and_then(|formula| formula.eval(&solved))
+            })
+            .collect()
+    }
+
+    fn solve(
+        &self,
+        graph: &DependencyGraph,
+        component: &[usize],
+        solved: &mut LayeredVariableStore<'_>,
+    ) -> Result<(), EvaluationError> {
+        if let [node] = *component {
+            if !graph.depends_on_itself(node) {
+                // missing variables are reported, when they are evaluated
+                if let Some(formula) = graph.formula(node) {
+                    let value = formula.eval(solved)?;
+                    solved.set(graph.variable(node).clone(), value);
+                }
+                return Ok(());
+            }
+        }
+        // variables of cycle always have formulas, because missing variables have no dependencies
+        let formulas = component
+            .iter()
+            .filter_map(|node| Some((graph.variable(*node), graph.formula(*node)?)))
+            .collect::<Vec<_>>();
+        for (variable, _) in &formulas {
+            solved.set((*variable).clone(), self.options.initial);
+        }
+        for _ in 0..self.options.max_iterations {
+            let mut converged = true;
+            for (variable, formula) in &formulas {
+                let previous = solved.eval(variable)?;
+                let value = formula.eval(solved)?;
+                let change = abs(value - previous);
+                if change.is_nan() || change > self.options.tolerance * abs(value).max(1.0) {
+                    converged = false;
+                }
+                solved.set((*variable).clone(), value);
+            }
+            if converged {
+                return Ok(());
+            }
+        }
+        let names = formulas
+            .iter()
+            .map(|(variable, _)| format!("{variable}"))
+            .collect::<Vec<String>>();
+        Err(EvaluationError::MathError(MathError::new(format!(
+            "variables {} by fixed-point iteration, it did not converge in {} iterations",
+            names.join(", "),
+            self.options.max_iterations
+        ))))
+    }
+}
+
+impl<S: GetVariable> GetVariable for IterativeVariableStore<S> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.store.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.store.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.store.budget()
+    }
+
+    fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
+        self.eval_all(slice::from_ref(name)).map(|values| values[0])
+    }
+}
+
+impl<S: SetVariable> SetVariable for IterativeVariableStore<S> {
+    fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
+        self.store.set(name, value);
+    }
+}
+
+impl<S: ListVariables> ListVariables for IterativeVariableStore<S> {
+    fn variables(&self) -> Vec<Variable> {
+        self.store.variables()
+    }
+}
+
+impl<S: PopVariable> PopVariable for IterativeVariableStore<S> {
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        self.store.pop(variable)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::{Evaluate, EvaluationError, RootFormula};
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        GetVariable, IterationOptions, IterativeVariableStore, SetVariable, Variable,
+        VectorVariableStore,
+    };
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut store = IterativeVariableStore::<VectorVariableStore>::new(IterationOptions::new());
+        store.set("balance", parse("1000 + interest"));
+        store.set("interest", parse("balance * rate"));
+        store.set("rate", parse("base / 2"));
+        store.set("base", 0.1);
+        store.set("total", parse("balance + x"));
+        store.set("x", parse("1 / (1 + x)"));
+        let values = store
+            .eval_all(&[
+                Variable::new("total"),
+                Variable::new("x"),
+                Variable::new("interest"),
+            ])
+            .unwrap();
+        // x is positive root of x^2 + x - 1
+        let x = (5.0_f64.sqrt() - 1.0) / 2.0;
+        assert!((values[1] - x).abs() < 1e-8);
+        assert!((values[0] - 1000.0 / 0.95 - x).abs() < 1e-6);
+        assert!((values[2] - 1000.0 / 0.95 / 20.0).abs() < 1e-6);
+        let formula = parse("interest + 1");
+        assert!((formula.eval(&store).unwrap() - values[2] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_not_converged() {
+        let options = IterationOptions::new().with_max_iterations(50);
+        let mut store = IterativeVariableStore::<VectorVariableStore>::new(options);
+        store.set("a", parse("b * 2"));
+        store.set("b", parse("a + 1"));
+        store.set("c", 1.0);
+        assert!(matches!(
+            store.eval(&Variable::new("a")),
+            Err(EvaluationError::MathError(_))
+        ));
+        assert!((store.eval(&Variable::new("c")).unwrap() - 1.0).abs() < f64::EPSILON);
+        assert!(matches!(
+            store.eval(&Variable::new("missing")),
+            Err(EvaluationError::NoVariableError(_))
+        ));
+        store.set("c", parse("d + 1"));
+        assert!(matches!(
+            store.eval(&Variable::new("c")),
+            Err(EvaluationError::NoVariableError(_))
+        ));
+    }
+
+    #[test]
+    fn test_components_order() {
+        let mut store = VectorVariableStore::new();
+        store.set("a", parse("b + c"));
+        store.set("b", parse("c * 2 + a / 10"));
+        store.set("c", parse("d + e"));
+        store.set("d", parse("e"));
+        store.set("e", 1.0);
+        let store = IterativeVariableStore::with_store(store, IterationOptions::new());
+        let a = store.eval(&Variable::new("a")).unwrap();
+        // a = b + 2, b = 4 + a / 10
+        assert!((a - 6.0 / 0.9).abs() < 1e-8);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::__lib::fmt::{Debug, Formatter};
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::RootFormula;
+use crate::variable_stores::{
+    EvaluationBudget, GetVariable, PopVariable, ResolutionChain, SetVariable, Variable,
+};
+
+/// Variable store, that stores its own variables on top of other variable store.
+/// Own variables shadow variables with the same name in parent store, other variables are taken from parent.
+///
+/// Formulas taken from parent are evaluated with layered store, so own variables are visible for them too.
+pub struct LayeredVariableStore<'a> {
+    parent: &'a dyn GetVariable,
+    variables: Vec<(Variable, Arc<RootFormula>)>,
+}
+
+impl<'a> LayeredVariableStore<'a> {
+    /// Creates `LayeredVariableStore` without own variables on top of `parent`.
+    pub fn new(parent: &'a dyn GetVariable) -> Self {
+        Self {
+            parent,
+            variables: Vec::new(),
+        }
+    }
+}
+
+impl Debug for LayeredVariableStore<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        f.debug_struct("LayeredVariableStore")
+            .field("variables", &self.variables)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GetVariable for LayeredVariableStore<'_> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        for (variable, formula) in &self.variables {
+            if *variable == *name {
+                return Some(formula);
+            }
+        }
+        self.parent.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.parent.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.parent.budget()
+    }
+}
+
+impl SetVariable for LayeredVariableStore<'_> {
+    fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
+        let name = name.into();
+        let value = Arc::new(value.into());
+        match self.variables.iter_mut().find(|(x, _)| *x == name) {
+            Some((_, formula)) => *formula = value,
+            None => self.variables.push((name, value)),
+        }
+    }
+}
+
+impl PopVariable for LayeredVariableStore<'_> {
+    /// Removes own variable, variables of parent store are never removed.
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        let index = self.variables.iter().position(|(x, _)| *x == *variable);
+        index.map(|x| self.variables.remove(x).1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::formulas::{Evaluate, RootFormula};
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        GetVariable, LayeredVariableStore, PopVariable, SetVariable, Variable, VectorVariableStore,
+    };
+
+    #[test]
+    fn test_shadowing() {
+        let mut parent = VectorVariableStore::new();
+        parent.set("a", 1.0);
+        parent.set(
+            "b",
+            RootFormula::parse("a * 10", &EmptyFunctionStore).unwrap(),
+        );
+        let mut layered = LayeredVariableStore::new(&parent);
+        assert!((layered.eval(&Variable::new("b")).unwrap() - 10.0).abs() < f64::EPSILON);
+        layered.set("a", 2.0);
+        layered.set("a", 3.0);
+        assert!((layered.eval(&Variable::new("a")).unwrap() - 3.0).abs() < f64::EPSILON);
+        assert!((layered.eval(&Variable::new("b")).unwrap() - 30.0).abs() < f64::EPSILON);
+        assert!(layered.pop(&Variable::new("a")).is_some());
+        assert!(layered.pop(&Variable::new("a")).is_none());
+        let formula = RootFormula::parse("a + b", &EmptyFunctionStore).unwrap();
+        assert!((formula.eval(&layered).unwrap() - 11.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::{LimitError, Limits, RootFormula};
+use crate::variable_stores::{
+    GetVariable, ListVariables, PopVariable, ResolutionChain, SetVariable, Variable,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Limits of evaluation together with number of steps, which evaluation already made.
+///
+/// Budget is owned by [`LimitedVariableStore`], stores, which wrap it, return it from [`GetVariable::budget`].
+#[derive(Debug)]
+pub struct EvaluationBudget {
+    limits: Limits,
+    steps: AtomicUsize,
+}
+
+impl EvaluationBudget {
+    const fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns limits of evaluation.
+    pub const fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns number of steps made since budget was created or reset.
+    pub fn steps(&self) -> usize {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    // Counts one evaluated variable or one evaluation of expression by function.
+    pub(crate) fn step(&self) -> Result<(), LimitError> {
+        let steps = self.steps.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+        self.limits.check_steps(steps)
+    }
+}
+
+impl Clone for EvaluationBudget {
+    fn clone(&self) -> Self {
+        Self {
+            limits: self.limits,
+            steps: AtomicUsize::new(self.steps()),
+        }
+    }
+}
+
+/// Variable store, that stops evaluation, which exceeds [`Limits`].
+///
+/// Every evaluated variable and every evaluation of expression by function, like a term of series, is one step,
+/// so formulas without variables and functions are evaluated without any checks.
+/// Steps are counted by all evaluations with this store, until [`LimitedVariableStore::reset`] is called.
+/// Recursion depth is number of variables, which are evaluated one inside of another.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::{Evaluate, EvaluationError, Limits, RootFormula};
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{LimitedVariableStore, SetVariable, VectorVariableStore};
+///
+/// let mut store = LimitedVariableStore::new(VectorVariableStore::new(), Limits::new().with_max_steps(5));
+/// store.set("x", 2.0);
+/// let formula = RootFormula::parse("x * x + x", &EmptyFunctionStore).unwrap();
+/// assert_eq!(formula.eval(&store).unwrap(), 6.0);
+/// assert!(matches!(formula.eval(&store), Err(EvaluationError::LimitError(_))));
+/// store.reset();
+/// assert_eq!(formula.eval(&store).unwrap(), 6.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LimitedVariableStore<S> {
+    store: S,
+    budget: EvaluationBudget,
+}
+
+impl<S> LimitedVariableStore<S> {
+    /// Creates `LimitedVariableStore` on top of `store`.
+    pub const fn new(store: S, limits: Limits) -> Self {
+        Self {
+            store,
+            budget: EvaluationBudget::new(limits),
+        }
+    }
+
+    /// Returns number of steps made since store was created or reset.
+    pub fn steps(&self) -> usize {
+        self.budget.steps()
+    }
+
+    /// Resets number of made steps to zero.
+    pub fn reset(&mut self) {
+        *self.budget.steps.get_mut() = 0;
+    }
+
+    /// Returns wrapped store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S: GetVariable> GetVariable for LimitedVariableStore<S> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.store.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.store.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        Some(&self.budget)
+    }
+}
+
+impl<S: SetVariable> SetVariable for LimitedVariableStore<S> {
+    fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
+        self.store.set(name, value);
+    }
+}
+
+impl<S: PopVariable> PopVariable for LimitedVariableStore<S> {
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        self.store.pop(variable)
+    }
+}
+
+impl<S: ListVariables> ListVariables for LimitedVariableStore<S> {
+    fn variables(&self) -> Vec<Variable> {
+        self.store.variables()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::string::{format, String};
+    use crate::formulas::{series, Evaluate, EvaluationError, Limit, Limits, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{
+        CheckedVariableStore, GetVariable, LayeredVariableStore, LimitedVariableStore,
+        ReactiveVariableStore, SetVariable, Variable, VectorVariableStore,
+    };
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    fn limit(result: Result<f64, EvaluationError>) -> Limit {
+        match result {
+            Err(EvaluationError::LimitError(e)) => e.limit(),
+            other => panic!("expected exceeded limit, got {other:?}"),
+        }
+    }
+
+    // names without digits, so any number of them can be parsed
+    fn name(index: usize) -> String {
+        let letter = |place: usize| char::from(b'a' + (index / place % 26).to_le_bytes()[0]);
+        format!("v{}{}", letter(26), letter(1))
+    }
+
+    #[test]
+    fn test_recursion() {
+        let mut inner = VectorVariableStore::new();
+        inner.set(name(0), 1.0);
+        for i in 1..100 {
+            inner.set(name(i), parse(&format!("{} + 1", name(i - 1))));
+        }
+        let limits = Limits::unlimited().with_max_recursion(50);
+        let store = LimitedVariableStore::new(inner, limits);
+        assert!((store.eval(&Variable::new(name(49))).unwrap() - 50.0).abs() < f64::EPSILON);
+        assert_eq!(
+            limit(store.eval(&Variable::new(name(50)))),
+            Limit::Recursion
+        );
+        assert_eq!(limit(parse(&name(99)).eval(&store)), Limit::Recursion);
+        // without limits variables are evaluated as usual
+        let store = store.into_inner();
+        assert!((store.eval(&Variable::new(name(99))).unwrap() - 100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_steps() {
+        let mut store = LimitedVariableStore::new(
+            VectorVariableStore::new(),
+            Limits::new().with_max_steps(100),
+        );
+        store.set("x", 1.0);
+        store.set("y", parse("x + x"));
+        // 6 steps: `y` and its two `x` twice, numbers and operators are not counted
+        let formula = parse("y * y");
+        for _ in 0..16 {
+            assert!(formula.eval(&store).is_ok());
+        }
+        assert_eq!(store.steps(), 96);
+        assert_eq!(limit(formula.eval(&store)), Limit::Steps);
+        store.reset();
+        assert_eq!(store.steps(), 0);
+        assert!(formula.eval(&store).is_ok());
+    }
+
+    // stores wrapping limited store return its budget
+    #[test]
+    fn test_limits_of_wrapped_store() {
+        let limits = Limits::unlimited().with_max_recursion(2);
+        let mut store = CheckedVariableStore::new(LimitedVariableStore::new(
+            VectorVariableStore::new(),
+            limits,
+        ));
+        store.try_set("a", 1.0).unwrap();
+        store.try_set("b", parse("a + 1")).unwrap();
+        store.try_set("c", parse("b + 1")).unwrap();
+        assert!((store.eval(&Variable::new("b")).unwrap() - 2.0).abs() < f64::EPSILON);
+        assert_eq!(limit(store.eval(&Variable::new("c"))), Limit::Recursion);
+    }
+
+    // stores borrowing limited store as parent return its budget, functions binding variables too
+    #[test]
+    fn test_limits_of_parent() {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<series::Sum>();
+        let mut parent = LimitedVariableStore::new(
+            VectorVariableStore::new(),
+            Limits::unlimited().with_max_steps(30),
+        );
+        parent.set("a", 1.0);
+        let mut layered = LayeredVariableStore::new(&parent);
+        layered.set(
+            "b",
+            RootFormula::parse("sum(i, 1, 10, a)", &functions).unwrap(),
+        );
+        // 21 steps: `b`, every term and its `a`
+        let b = Variable::new("b");
+        assert!((layered.eval(&b).unwrap() - 10.0).abs() < f64::EPSILON);
+        assert_eq!(limit(layered.eval(&b)), Limit::Steps);
+        assert_eq!(parent.steps(), 31);
+    }
+
+    // recalculation after change spends steps of wrapped store
+    #[test]
+    fn test_limits_of_recalculation() {
+        let mut store = ReactiveVariableStore::new(LimitedVariableStore::new(
+            VectorVariableStore::new(),
+            Limits::new().with_max_steps(50),
+        ));
+        store.set("a", 1.0);
+        store.set("b", parse("a + 1"));
+        let b = Variable::new("b");
+        assert_eq!(store.value(&b), Some(2.0));
+        // 2 steps: `a` and `b` are recalculated
+        for i in 0..25 {
+            store.set("a", f64::from(i));
+        }
+        assert_eq!(store.value(&b), None);
+        assert_eq!(limit(store.eval(&b)), Limit::Steps);
+    }
+}
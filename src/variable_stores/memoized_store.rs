@@ -0,0 +1,291 @@
+use crate::__lib::cell::RefCell;
+use crate::__lib::collections::BTreeMap;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::{CycleError, Evaluate, EvaluationError, NoVariableError, RootFormula};
+use crate::variable_stores::{
+    DependencyGraph, EvaluationBudget, GetVariable, ListVariables, PopVariable, ResolutionChain,
+    SetVariable, Variable,
+};
+
+/// Variable store, that evaluates every variable only once and remembers its value.
+///
+/// Values are kept until variable or variables it depends on are changed through this store,
+/// or until [`MemoizedVariableStore::invalidate`] or [`MemoizedVariableStore::clear`] is called.
+/// Functions with inner state are evaluated only once for every remembered value.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{GetVariable, MemoizedVariableStore, SetVariable, Variable, VectorVariableStore};
+///
+/// let mut store = MemoizedVariableStore::new(VectorVariableStore::new());
+/// store.set("a", 2.0);
+/// store.set("b", RootFormula::parse("a * a", &EmptyFunctionStore).unwrap());
+/// store.set("c", RootFormula::parse("b + b", &EmptyFunctionStore).unwrap());
+/// assert_eq!(store.eval(&Variable::new("c")).unwrap(), 8.0);
+/// store.set("a", 3.0);
+/// assert_eq!(store.eval(&Variable::new("c")).unwrap(), 18.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoizedVariableStore<S> {
+    store: S,
+    values: RefCell<BTreeMap<Variable, f64>>,
+    evaluating: RefCell<Vec<Variable>>,
+    // position of every variable in `evaluating`, so cycles are found without searching the stack
+    positions: RefCell<BTreeMap<Variable, usize>>,
+}
+
+impl<S> MemoizedVariableStore<S> {
+    /// Creates `MemoizedVariableStore` on top of `store` without remembered values.
+    pub const fn new(store: S) -> Self {
+        Self {
+            store,
+            values: RefCell::new(BTreeMap::new()),
+            evaluating: RefCell::new(Vec::new()),
+            positions: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns wrapped store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Forgets all remembered values.
+    pub fn clear(&mut self) {
+        self.values.get_mut().clear();
+    }
+}
+
+impl<S: GetVariable> MemoizedVariableStore<S> {
+    /// Forgets values of `variable` and variables, which depend on it.
+    ///
+    /// Must be called, if wrapped store was changed not through this store.
+    pub fn invalidate(&mut self, variable: &Variable) {
+        let values = self.values.get_mut();
+        let remembered = values.keys().cloned().collect::<Vec<_>>();
+        let graph = DependencyGraph::new(&self.store, &remembered);
+        for affected in graph.affected(variable) {
+            values.remove(affected);
+        }
+        values.remove(variable);
+    }
+}
+
+impl<S: GetVariable + ListVariables> MemoizedVariableStore<S> {
+    /// Evaluates every stored variable once, variables are evaluated after variables they depend on.
+    ///
+    /// Returns variables with their values in the same order as [`ListVariables::variables`] of wrapped store.
+    ///
+    /// # Errors
+    ///
+    /// Will return Err if variables depend on each other in a cycle, some variable is not in store
+    /// or formula failed to evaluate.
+    pub fn eval_all(&self) -> Result<Vec<(Variable, f64)>, EvaluationError> {
+        let variables = self.store.variables();
+        let graph = DependencyGraph::new(&self.store, &variables);
+        // dependencies are already remembered, when variable is evaluated, so evaluation never goes deep
+        for variable in graph
+            .topological_order()
+            .map_err(EvaluationError::CycleError)?
+        {
+            if self.store.get(variable).is_some() {
+                self.eval(variable)?;
+            }
+        }
+        variables
+            .into_iter()
+            .map(|variable| {
+                let value = self.eval(&variable)?;
+                Ok((variable, value))
+            })
+            .collect()
+    }
+}
+
+impl<S: GetVariable> GetVariable for MemoizedVariableStore<S> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.store.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.store.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.store.budget()
+    }
+
+    fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
+        if let Some(value) = self.values.borrow().get(name) {
+            return Ok(*value);
+        }
+        {
+            let mut evaluating = self.evaluating.borrow_mut();
+            let mut positions = self.positions.borrow_mut();
+            if let Some(start) = positions.get(name) {
+                let cycle = evaluating[*start..].to_vec();
+                return Err(EvaluationError::CycleError(CycleError::new(cycle)));
+            }
+            // formulas are evaluated with this store, so its own stack counts recursion depth
+            if let Some(budget) = self.budget() {
+                let outer = self.resolution().map_or(0, ResolutionChain::depth);
+                budget
+                    .limits()
+                    .check_recursion(outer + evaluating.len() + 1)
+                    .map_err(EvaluationError::LimitError)?;
+                budget.step().map_err(EvaluationError::LimitError)?;
+            }
+            positions.insert(name.clone(), evaluating.len());
+            evaluating.push(name.clone());
+        }
+        let value = self
+            .store
+            .get(name)
+            .ok_or_else(|| EvaluationError::NoVariableError(NoVariableError::new(name.clone())))
+            .and_then(|formula| formula.eval(self));
+        self.evaluating.borrow_mut().pop();
+        self.positions.borrow_mut().remove(name);
+        let value = value?;
+        self.values.borrow_mut().insert(name.clone(), value);
+        Ok(value)
+    }
+}
+
+impl<S: GetVariable + SetVariable> SetVariable for MemoizedVariableStore<S> {
+    fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
+        let name = name.into();
+        self.store.set(name.clone(), value);
+        self.invalidate(&name);
+    }
+}
+
+impl<S: GetVariable + PopVariable> PopVariable for MemoizedVariableStore<S> {
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        let formula = self.store.pop(variable);
+        self.invalidate(variable);
+        formula
+    }
+}
+
+impl<S: ListVariables> ListVariables for MemoizedVariableStore<S> {
+    fn variables(&self) -> Vec<Variable> {
+        self.store.variables()
+    }
+}
+
+#[cfg(test)]
+// values are exact sums of powers of two
+#[allow(clippy::float_cmp)]
+mod test {
+    use crate::__lib::string::{format, String};
+    use crate::__lib::vec::Vec;
+    use crate::formulas::{EvaluationError, Limit, Limits, RootFormula};
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        GetVariable, LimitedVariableStore, MemoizedVariableStore, PopVariable, SetVariable,
+        Variable, VectorVariableStore,
+    };
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    // names without digits, so any number of them can be parsed
+    fn name(index: usize) -> String {
+        let mut name = String::from("v");
+        for place in [26 * 26, 26, 1] {
+            let letter = (index / place % 26).to_le_bytes()[0];
+            name.push(char::from(b'a' + letter));
+        }
+        name
+    }
+
+    #[test]
+    fn test_diamonds() {
+        let mut store = MemoizedVariableStore::new(VectorVariableStore::new());
+        store.set(name(0), 1.0);
+        for i in 1..60 {
+            let previous = name(i - 1);
+            store.set(name(i), parse(&format!("{previous} + {previous}")));
+        }
+        assert_eq!(
+            store.eval(&Variable::new(name(59))).unwrap(),
+            2.0_f64.powi(59)
+        );
+        store.set(name(30), 0.5);
+        assert_eq!(
+            store.eval(&Variable::new(name(59))).unwrap(),
+            2.0_f64.powi(28)
+        );
+        assert_eq!(
+            store.eval(&Variable::new(name(29))).unwrap(),
+            2.0_f64.powi(29)
+        );
+        assert!(store.pop(&Variable::new(name(0))).is_some());
+        assert!(matches!(
+            store.eval(&Variable::new(name(29))),
+            Err(EvaluationError::NoVariableError(_))
+        ));
+        // does not depend on removed variable anymore
+        assert_eq!(
+            store.eval(&Variable::new(name(59))).unwrap(),
+            2.0_f64.powi(28)
+        );
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let mut inner = VectorVariableStore::new();
+        inner.set(name(0), 1.0);
+        for i in 1..100 {
+            inner.set(name(i), parse(&format!("{} + 1", name(i - 1))));
+        }
+        let limits = Limits::unlimited().with_max_recursion(50);
+        let store = MemoizedVariableStore::new(LimitedVariableStore::new(inner, limits));
+        assert!(store.budget().is_some());
+        assert!(matches!(
+            store.eval(&Variable::new(name(99))),
+            Err(EvaluationError::LimitError(e)) if e.limit() == Limit::Recursion
+        ));
+        // remembered values are not evaluated again, so they do not count
+        assert_eq!(store.eval(&Variable::new(name(49))).unwrap(), 50.0);
+        assert_eq!(store.eval(&Variable::new(name(99))).unwrap(), 100.0);
+    }
+
+    #[test]
+    fn test_eval_all() {
+        let mut inner = VectorVariableStore::new();
+        for i in (1..2000).rev() {
+            inner.set(name(i), parse(&format!("{} + 1", name(i - 1))));
+        }
+        inner.set(name(0), 0.0);
+        let store = MemoizedVariableStore::new(inner);
+        let values = store.eval_all().unwrap();
+        assert_eq!(values.len(), 2000);
+        assert_eq!(values[0], (Variable::new(name(1999)), 1999.0));
+        assert_eq!(values[1999], (Variable::new(name(0)), 0.0));
+        let mut inner = store.into_inner();
+        inner.set("a", parse("b"));
+        inner.set("b", parse("a + 1"));
+        let mut store = MemoizedVariableStore::new(inner);
+        assert!(matches!(
+            store.eval_all(),
+            Err(EvaluationError::CycleError(_))
+        ));
+        assert!(matches!(
+            store.eval(&Variable::new("b")),
+            Err(EvaluationError::CycleError(_))
+        ));
+        store.set("b", 1.0);
+        assert_eq!(store.eval(&Variable::new("a")).unwrap(), 1.0);
+        let values: Vec<_> = store.eval_all().unwrap();
+        assert_eq!(values.len(), 2002);
+    }
+}
@@ -1,8 +1,26 @@
+mod checked_store;
+mod dependency;
 mod empty_store;
+pub use crate::variable_stores::checked_store::CheckedVariableStore;
+pub use crate::variable_stores::dependency::DependencyGraph;
 pub use crate::variable_stores::empty_store::EmptyVariableStore;
 #[cfg(feature = "std")]
 mod hashmap_store;
+#[cfg(any(feature = "std", feature = "libm"))]
+mod iterative_store;
+mod layered_store;
+mod limited_store;
+mod memoized_store;
+mod reactive_store;
+mod resolution;
 mod vector_store;
+#[cfg(any(feature = "std", feature = "libm"))]
+pub use iterative_store::{IterationOptions, IterativeVariableStore};
+pub use layered_store::LayeredVariableStore;
+pub use limited_store::{EvaluationBudget, LimitedVariableStore};
+pub use memoized_store::MemoizedVariableStore;
+pub use reactive_store::{ReactiveVariableStore, Subscription};
+pub use resolution::ResolutionChain;
 pub use vector_store::VectorVariableStore;
 
 #[cfg(feature = "std")]
@@ -11,8 +29,9 @@ pub use crate::variable_stores::hashmap_store::HashMapVariableStore;
 use crate::__lib::fmt::{Display, Formatter};
 use crate::__lib::string::String;
 use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
 use crate::formulas::RootFormula;
-use crate::formulas::{Evaluate, EvaluationError, IsConst, NoVariableError};
+use crate::formulas::{Evaluate, EvaluationError, IsConst};
 
 /// Type that stored in variable store as "key".
 #[derive(Debug, PartialEq, Hash, Eq, Clone, Ord, PartialOrd)]
@@ -72,16 +91,25 @@ pub trait GetVariable {
     ///
     /// # Errors
     ///
-    ///Will return Err if name is not in store or formula in store errors.
+    ///Will return Err if name is not in store, formula in store errors or variable depends on itself.
     fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
-        self.get(name).map_or_else(
-            || {
-                Err(EvaluationError::NoVariableError(NoVariableError::new(
-                    name.clone(),
-                )))
-            },
-            |formula| formula.eval(self.as_dyn()),
-        )
+        ResolutionChain::resolve(name, self.as_dyn(), self.resolution())
+    }
+
+    /// Returns variables, which are being evaluated, used by [`GetVariable::eval`] to detect cycles.
+    ///
+    /// Stores, which wrap other store, should return resolution of wrapped store,
+    /// otherwise cycles going through them are not detected.
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        None
+    }
+
+    /// Returns budget, which limits evaluation with this store, `None` if evaluation is not limited.
+    ///
+    /// Stores, which wrap other store, should return budget of wrapped store,
+    /// otherwise evaluation through them is not limited.
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        None
     }
 }
 
@@ -91,6 +119,21 @@ pub trait SetVariable {
     fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>);
 }
 
+/// Trait for listing variables stored in variable store.
+pub trait ListVariables {
+    /// Returns all stored variables.
+    fn variables(&self) -> Vec<Variable>;
+
+    /// Returns graph of dependencies between all stored variables,
+    /// variables go in the same order as [`ListVariables::variables`].
+    fn dependency_graph(&self) -> DependencyGraph
+    where
+        Self: GetVariable,
+    {
+        DependencyGraph::new(self.as_dyn(), &self.variables())
+    }
+}
+
 /// Trait for removing variables from variable store.
 pub trait PopVariable {
     /// Removes variable from variable store.
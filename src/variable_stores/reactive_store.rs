@@ -0,0 +1,392 @@
+use crate::__lib::boxed::Box;
+use crate::__lib::collections::BTreeMap;
+use crate::__lib::fmt::{Debug, Formatter};
+use crate::__lib::slice;
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::{Evaluate, EvaluationError, RootFormula};
+use crate::variable_stores::{
+    EvaluationBudget, GetVariable, ListVariables, PopVariable, ResolutionChain, SetVariable,
+    Variable,
+};
+
+type Callback = Box<dyn FnMut(&Variable, Option<f64>, Option<f64>)>;
+
+/// Identifier of callback registered by [`ReactiveVariableStore::subscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(usize);
+
+/// Variable store, that keeps values of all variables up to date, like spreadsheet.
+///
+/// When variable is set or removed, only variables depending on it are recalculated,
+/// every variable after variables it depends on. Values of variables, which failed to evaluate
+/// or depend on each other in a cycle, are `None`, their errors are returned by [`GetVariable::eval`].
+/// Callbacks subscribed to variable are called with its old and new value, whenever value changes.
+///
+/// # Examples
+/// ```rust
+/// use evaluatorrs::formulas::RootFormula;
+/// use evaluatorrs::function_stores::EmptyFunctionStore;
+/// use evaluatorrs::variable_stores::{ReactiveVariableStore, SetVariable, Variable, VectorVariableStore};
+/// use std::sync::{Arc, Mutex};
+///
+/// let mut store = ReactiveVariableStore::new(VectorVariableStore::new());
+/// store.set("price", 10.0);
+/// store.set("total", RootFormula::parse("price * 2", &EmptyFunctionStore).unwrap());
+/// let changes = Arc::new(Mutex::new(Vec::new()));
+/// let log = Arc::clone(&changes);
+/// store.subscribe("total", move |_, old, new| log.lock().unwrap().push((old, new)));
+/// store.set("price", 12.0);
+/// assert_eq!(store.value(&Variable::new("total")), Some(24.0));
+/// assert_eq!(*changes.lock().unwrap(), [(Some(20.0), Some(24.0))]);
+/// ```
+pub struct ReactiveVariableStore<S> {
+    store: S,
+    values: BTreeMap<Variable, Option<f64>>,
+    dependencies: BTreeMap<Variable, Vec<Variable>>,
+    dependents: BTreeMap<Variable, Vec<Variable>>,
+    subscriptions: Vec<(Subscription, Variable, Callback)>,
+    next_subscription: usize,
+}
+
+impl<S: Debug> Debug for ReactiveVariableStore<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        f.debug_struct("ReactiveVariableStore")
+            .field("store", &self.store)
+            .field("values", &self.values)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: GetVariable + ListVariables> ReactiveVariableStore<S> {
+    /// Creates `ReactiveVariableStore` on top of `store` and evaluates all variables already stored in it.
+    pub fn new(store: S) -> Self {
+        let variables = store.variables();
+        let mut reactive = Self {
+            store,
+            values: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            dependents: BTreeMap::new(),
+            subscriptions: Vec::new(),
+            next_subscription: 0,
+        };
+        for variable in &variables {
+            reactive.track(variable);
+        }
+        reactive.recalculate(&variables);
+        reactive
+    }
+}
+
+impl<S> ReactiveVariableStore<S> {
+    /// Returns current value of `variable`, `None` if it is not stored or failed to evaluate.
+    pub fn value(&self, variable: &Variable) -> Option<f64> {
+        self.values.get(variable).copied().flatten()
+    }
+
+    /// Registers `callback`, which is called with variable, its old and new value, when value of `variable` changes.
+    pub fn subscribe(
+        &mut self,
+        variable: impl Into<Variable>,
+        callback: impl FnMut(&Variable, Option<f64>, Option<f64>) + 'static,
+    ) -> Subscription {
+        let subscription = Subscription(self.next_subscription);
+        self.next_subscription += 1;
+        self.subscriptions
+            .push((subscription, variable.into(), Box::new(callback)));
+        subscription
+    }
+
+    /// Removes callback, returns `false` if it was already removed.
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|(x, _, _)| *x != subscription);
+        self.subscriptions.len() != count
+    }
+
+    /// Returns wrapped store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S: GetVariable> ReactiveVariableStore<S> {
+    // updates dependencies of `variable` after its formula was changed
+    fn track(&mut self, variable: &Variable) {
+        let dependencies = self
+            .store
+            .get(variable)
+            .map(|formula| formula.variables())
+            .unwrap_or_default();
+        for dependency in self.dependencies.remove(variable).unwrap_or_default() {
+            if let Some(dependents) = self.dependents.get_mut(&dependency) {
+                dependents.retain(|x| x != variable);
+            }
+        }
+        for dependency in &dependencies {
+            self.dependents
+                .entry(dependency.clone())
+                .or_default()
+                .push(variable.clone());
+        }
+        if !dependencies.is_empty() {
+            self.dependencies.insert(variable.clone(), dependencies);
+        }
+    }
+
+    // Recalculates `changed` variables and variables, which depend on them.
+    // Dirty variables are evaluated after their dirty dependencies by Kahn's algorithm,
+    // variables, which are left, depend on cycle.
+    fn recalculate(&mut self, changed: &[Variable]) {
+        let mut dirty = changed.to_vec();
+        let mut positions = BTreeMap::new();
+        for (position, variable) in dirty.iter().enumerate() {
+            positions.insert(variable.clone(), position);
+        }
+        let mut next = 0;
+        while let Some(variable) = dirty.get(next) {
+            for dependent in self.dependents.get(variable).into_iter().flatten() {
+                if !positions.contains_key(dependent) {
+                    positions.insert(dependent.clone(), dirty.len());
+                    dirty.push(dependent.clone());
+                }
+            }
+            next += 1;
+        }
+        let mut waiting = dirty
+            .iter()
+            .map(|variable| {
+                self.dependencies
+                    .get(variable)
+                    .into_iter()
+                    .flatten()
+                    .filter(|dependency| positions.contains_key(*dependency))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        let old = dirty
+            .iter()
+            .map(|variable| self.values.insert(variable.clone(), None).flatten())
+            .collect::<Vec<_>>();
+        let mut ready = (0..dirty.len())
+            .filter(|index| waiting[*index] == 0)
+            .collect::<Vec<_>>();
+        while let Some(index) = ready.pop() {
+            let variable = &dirty[index];
+            let value = self.store.get(variable).and_then(|formula| {
+                // recalculated variable is one step, like variable resolved by evaluation
+                if let Some(budget) = self.budget() {
+                    budget.step().ok()?;
+                }
+                formula.eval(self).ok()
+            });
+            self.values.insert(variable.clone(), value);
+            for dependent in self.dependents.get(variable).into_iter().flatten() {
+                if let Some(position) = positions.get(dependent) {
+                    waiting[*position] -= 1;
+                    if waiting[*position] == 0 {
+                        ready.push(*position);
+                    }
+                }
+            }
+        }
+        for (variable, old) in dirty.iter().zip(old) {
+            let new = self.values.get(variable).copied().flatten();
+            if self.store.get(variable).is_none() {
+                self.values.remove(variable);
+            }
+            if old.map(f64::to_bits) != new.map(f64::to_bits) {
+                for (_, subscribed, callback) in &mut self.subscriptions {
+                    if subscribed == variable {
+                        callback(variable, old, new);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S: GetVariable> GetVariable for ReactiveVariableStore<S> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.store.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        self.store.resolution()
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.store.budget()
+    }
+
+    fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
+        match self.values.get(name) {
+            Some(Some(value)) => Ok(*value),
+            // evaluates again to find out the error
+            _ => ResolutionChain::resolve(name, &self.store, self.resolution()),
+        }
+    }
+}
+
+impl<S: GetVariable + SetVariable> SetVariable for ReactiveVariableStore<S> {
+    fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
+        let name = name.into();
+        self.store.set(name.clone(), value);
+        self.track(&name);
+        self.recalculate(slice::from_ref(&name));
+    }
+}
+
+impl<S: GetVariable + PopVariable> PopVariable for ReactiveVariableStore<S> {
+    fn pop(&mut self, variable: &Variable) -> Option<Arc<RootFormula>> {
+        let formula = self.store.pop(variable)?;
+        self.track(variable);
+        self.recalculate(slice::from_ref(variable));
+        Some(formula)
+    }
+}
+
+impl<S: ListVariables> ListVariables for ReactiveVariableStore<S> {
+    fn variables(&self) -> Vec<Variable> {
+        self.store.variables()
+    }
+}
+
+#[cfg(test)]
+// values are exact
+#[allow(clippy::float_cmp)]
+mod test {
+    use crate::__lib::cell::RefCell;
+    use crate::__lib::rc::Rc;
+    use crate::__lib::string::format;
+    use crate::__lib::vec::Vec;
+    use crate::formulas::stateful::IntegrateDt;
+    use crate::formulas::{EvaluationError, RootFormula};
+    use crate::function_stores::{EmptyFunctionStore, RegisterParser, VectorFunctionStore};
+    use crate::variable_stores::{
+        GetVariable, PopVariable, ReactiveVariableStore, SetVariable, Variable, VectorVariableStore,
+    };
+
+    type Changes = Rc<RefCell<Vec<(Variable, Option<f64>, Option<f64>)>>>;
+
+    fn parse(expression: &str) -> RootFormula {
+        RootFormula::parse(expression, &EmptyFunctionStore).unwrap()
+    }
+
+    fn log(store: &mut ReactiveVariableStore<VectorVariableStore>, variables: &[&str]) -> Changes {
+        let changes = Changes::default();
+        for variable in variables {
+            let changes = Rc::clone(&changes);
+            store.subscribe(*variable, move |variable, old, new| {
+                changes.borrow_mut().push((variable.clone(), old, new));
+            });
+        }
+        changes
+    }
+
+    #[test]
+    fn test_recalculation() {
+        let mut inner = VectorVariableStore::new();
+        inner.set("total", parse("price * count + fee"));
+        inner.set("price", parse("base * 2"));
+        inner.set("base", 5.0);
+        inner.set("count", 3.0);
+        let mut store = ReactiveVariableStore::new(inner);
+        let total = Variable::new("total");
+        assert_eq!(store.value(&total), None);
+        assert!(matches!(
+            store.eval(&total),
+            Err(EvaluationError::NoVariableError(_))
+        ));
+        let changes = log(&mut store, &["total", "price", "count"]);
+        store.set("fee", 1.0);
+        assert_eq!(store.value(&total), Some(31.0));
+        store.set("base", 6.0);
+        store.set("count", 3.0);
+        assert_eq!(store.eval(&total).unwrap(), 37.0);
+        assert_eq!(
+            *changes.borrow(),
+            [
+                (total.clone(), None, Some(31.0)),
+                (Variable::new("price"), Some(10.0), Some(12.0)),
+                (total.clone(), Some(31.0), Some(37.0)),
+            ]
+        );
+        changes.borrow_mut().clear();
+        assert!(store.pop(&Variable::new("count")).is_some());
+        assert!(store.pop(&Variable::new("count")).is_none());
+        assert_eq!(
+            *changes.borrow(),
+            [
+                (Variable::new("count"), Some(3.0), None),
+                (total, Some(37.0), None),
+            ]
+        );
+    }
+
+    // value of every probe is number of times it was evaluated
+    fn probe(dependency: &str) -> RootFormula {
+        let mut functions = VectorFunctionStore::new();
+        functions.register::<IntegrateDt>();
+        let expression = format!("integrate_dt(0 * {dependency} + 1, 1)");
+        RootFormula::parse(&expression, &functions).unwrap()
+    }
+
+    fn evaluations(store: &ReactiveVariableStore<VectorVariableStore>) -> [f64; 3] {
+        ["b", "d", "e"].map(|probe| store.value(&Variable::new(probe)).unwrap())
+    }
+
+    #[test]
+    fn test_only_dependents_recalculated() {
+        let mut store = ReactiveVariableStore::new(VectorVariableStore::new());
+        store.set("a", 1.0);
+        store.set("c", 10.0);
+        store.set("b", probe("a"));
+        store.set("d", probe("b"));
+        store.set("e", probe("c"));
+        assert_eq!(evaluations(&store), [1.0, 1.0, 1.0]);
+        store.set("a", 2.0);
+        assert_eq!(evaluations(&store), [2.0, 2.0, 1.0]);
+        store.set("c", 11.0);
+        assert_eq!(evaluations(&store), [2.0, 2.0, 2.0]);
+        store.set("x", 0.0);
+        assert!(store.pop(&Variable::new("x")).is_some());
+        assert_eq!(evaluations(&store), [2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let mut store = ReactiveVariableStore::new(VectorVariableStore::new());
+        store.set("a", 1.0);
+        store.set("b", parse("a + 1"));
+        let calls = Rc::new(RefCell::new(0));
+        let counter = Rc::clone(&calls);
+        let subscription = store.subscribe("b", move |_, _, _| *counter.borrow_mut() += 1);
+        store.set("a", 2.0);
+        store.set("a", 2.0);
+        assert!(store.unsubscribe(subscription));
+        assert!(!store.unsubscribe(subscription));
+        store.set("a", 3.0);
+        assert_eq!(*calls.borrow(), 1);
+        assert_eq!(store.value(&Variable::new("b")), Some(4.0));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut store = ReactiveVariableStore::new(VectorVariableStore::new());
+        store.set("a", parse("b + 1"));
+        store.set("b", parse("a * 2"));
+        store.set("c", parse("b"));
+        assert!(matches!(
+            store.eval(&Variable::new("c")),
+            Err(EvaluationError::CycleError(_))
+        ));
+        store.set("b", 4.0);
+        assert_eq!(store.value(&Variable::new("a")), Some(5.0));
+        assert_eq!(store.value(&Variable::new("c")), Some(4.0));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::__lib::fmt::{Debug, Formatter};
+use crate::__lib::sync::Arc;
+use crate::__lib::vec::Vec;
+use crate::formulas::{CycleError, Evaluate, EvaluationError, NoVariableError, RootFormula};
+use crate::variable_stores::{EvaluationBudget, GetVariable, Variable};
+
+/// Variables, which are being evaluated, from the innermost one to the one, evaluation started with.
+///
+/// Chain is created by [`GetVariable::eval`], every link lives on the stack while formula of its variable is evaluated,
+/// so variable, that is evaluated again before its own evaluation finished, is detected as cycle.
+pub struct ResolutionChain<'a> {
+    variable: &'a Variable,
+    store: &'a dyn GetVariable,
+    parent: Option<&'a Self>,
+    depth: usize,
+}
+
+impl Debug for ResolutionChain<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> crate::__lib::fmt::Result {
+        let mut list = f.debug_list();
+        let mut link = Some(self);
+        while let Some(current) = link {
+            list.entry(current.variable);
+            link = current.parent;
+        }
+        list.finish()
+    }
+}
+
+impl<'a> ResolutionChain<'a> {
+    /// Evaluates `name` stored in `store`, `parent` contains variables, which are already being evaluated.
+    pub(crate) fn resolve(
+        name: &'a Variable,
+        store: &'a dyn GetVariable,
+        parent: Option<&'a Self>,
+    ) -> Result<f64, EvaluationError> {
+        if let Some(cycle) = parent.and_then(|parent| parent.cycle(name)) {
+            return Err(EvaluationError::CycleError(cycle));
+        }
+        let depth = parent.map_or(1, |parent| parent.depth + 1);
+        if let Some(budget) = store.budget() {
+            budget
+                .limits()
+                .check_recursion(depth)
+                .map_err(EvaluationError::LimitError)?;
+            budget.step().map_err(EvaluationError::LimitError)?;
+        }
+        let formula = store
+            .get(name)
+            .ok_or_else(|| EvaluationError::NoVariableError(NoVariableError::new(name.clone())))?;
+        formula.eval(&ResolutionChain {
+            variable: name,
+            store,
+            parent,
+            depth,
+        })
+    }
+
+    /// Number of variables in chain.
+    pub(crate) const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn cycle(&self, name: &Variable) -> Option<CycleError> {
+        let mut variables = Vec::new();
+        let mut link = Some(self);
+        while let Some(current) = link {
+            variables.push(current.variable.clone());
+            if current.variable == name {
+                variables.reverse();
+                return Some(CycleError::new(variables));
+            }
+            link = current.parent;
+        }
+        None
+    }
+}
+
+impl GetVariable for ResolutionChain<'_> {
+    fn get(&self, name: &Variable) -> Option<&Arc<RootFormula>> {
+        self.store.get(name)
+    }
+
+    fn as_dyn(&self) -> &dyn GetVariable {
+        self
+    }
+
+    fn eval(&self, name: &Variable) -> Result<f64, EvaluationError> {
+        // own store is reused, so lookups do not go through every link of the chain
+        ResolutionChain::resolve(name, self.store, Some(self))
+    }
+
+    fn resolution(&self) -> Option<&ResolutionChain<'_>> {
+        Some(self)
+    }
+
+    fn budget(&self) -> Option<&EvaluationBudget> {
+        self.store.budget()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::vec::Vec;
+    use crate::formulas::{Evaluate, EvaluationError, RootFormula};
+    use crate::function_stores::EmptyFunctionStore;
+    use crate::variable_stores::{
+        GetVariable, LayeredVariableStore, SetVariable, Variable, VectorVariableStore,
+    };
+
+    fn cycle(result: Result<f64, EvaluationError>) -> Vec<Variable> {
+        match result {
+            Err(EvaluationError::CycleError(e)) => e.variables().to_vec(),
+            other => panic!("expected cycle, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut store = VectorVariableStore::new();
+        store.set(
+            "a",
+            RootFormula::parse("b + 1", &EmptyFunctionStore).unwrap(),
+        );
+        store.set(
+            "b",
+            RootFormula::parse("a * 2", &EmptyFunctionStore).unwrap(),
+        );
+        store.set(
+            "c",
+            RootFormula::parse("b - 1", &EmptyFunctionStore).unwrap(),
+        );
+        store.set("d", RootFormula::parse("d", &EmptyFunctionStore).unwrap());
+        assert_eq!(
+            cycle(store.eval(&Variable::new("a"))),
+            [Variable::new("a"), Variable::new("b")]
+        );
+        assert_eq!(
+            cycle(store.eval(&Variable::new("c"))),
+            [Variable::new("b"), Variable::new("a")]
+        );
+        assert_eq!(cycle(store.eval(&Variable::new("d"))), [Variable::new("d")]);
+        let formula = RootFormula::parse("c + 1", &EmptyFunctionStore).unwrap();
+        assert!(matches!(
+            formula.eval(&store),
+            Err(EvaluationError::CycleError(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_cycle() {
+        let mut store = VectorVariableStore::new();
+        store.set("a", 1.0);
+        store.set(
+            "b",
+            RootFormula::parse("a + a", &EmptyFunctionStore).unwrap(),
+        );
+        store.set(
+            "c",
+            RootFormula::parse("b * a + b", &EmptyFunctionStore).unwrap(),
+        );
+        assert!((store.eval(&Variable::new("c")).unwrap() - 4.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_cycle_through_layered_store() {
+        let mut parent = VectorVariableStore::new();
+        parent.set(
+            "a",
+            RootFormula::parse("b + 1", &EmptyFunctionStore).unwrap(),
+        );
+        let mut layered = LayeredVariableStore::new(&parent);
+        layered.set(
+            "b",
+            RootFormula::parse("a * 2", &EmptyFunctionStore).unwrap(),
+        );
+        assert_eq!(
+            cycle(layered.eval(&Variable::new("b"))),
+            [Variable::new("b"), Variable::new("a")]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::__lib::sync::Arc;
 use crate::__lib::vec::Vec;
 use crate::formulas::RootFormula;
-use crate::variable_stores::{GetVariable, PopVariable, SetVariable, Variable};
+use crate::variable_stores::{GetVariable, ListVariables, PopVariable, SetVariable, Variable};
 
 /// Variable store based on [`Vec`] of tuples.
 #[derive(Debug, Clone, Default)]
@@ -36,7 +36,22 @@ impl GetVariable for VectorVariableStore {
 
 impl SetVariable for VectorVariableStore {
     fn set(&mut self, name: impl Into<Variable>, value: impl Into<RootFormula>) {
-        self.0.push((name.into(), Arc::new(value.into())));
+        let name = name.into();
+        let value = Arc::new(value.into());
+        match self.0.iter_mut().find(|(x, _)| *x == name) {
+            Some((_, formula)) => *formula = value,
+            None => self.0.push((name, value)),
+        }
+    }
+}
+
+impl ListVariables for VectorVariableStore {
+    /// Returns variables in order they were set.
+    fn variables(&self) -> Vec<Variable> {
+        self.0
+            .iter()
+            .map(|(variable, _)| variable.clone())
+            .collect()
     }
 }
 
@@ -46,3 +61,27 @@ impl PopVariable for VectorVariableStore {
         index.map(|x| self.0.remove(x).1)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::__lib::vec::vec;
+    use crate::variable_stores::{
+        GetVariable, ListVariables, PopVariable, SetVariable, Variable, VectorVariableStore,
+    };
+
+    #[test]
+    fn test_set_replaces() {
+        let mut store = VectorVariableStore::new();
+        store.set("a", 1.0);
+        store.set("b", 2.0);
+        store.set("a", 3.0);
+        assert_eq!(
+            store.variables(),
+            vec![Variable::new("a"), Variable::new("b")]
+        );
+        assert!((store.eval(&Variable::new("a")).unwrap() - 3.0).abs() < f64::EPSILON);
+        // old value is not left behind the replaced one
+        assert!(store.pop(&Variable::new("a")).is_some());
+        assert!(store.get(&Variable::new("a")).is_none());
+    }
+}